@@ -0,0 +1,346 @@
+// backend_visit_counter/src/api_keys.rs
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::Request;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::clock::SharedClock;
+use crate::models::{ApiKeyRecord, ApiKeyResponse, ApiKeyScope};
+use crate::namespaces;
+
+/// Id reported for the key in the `API_KEY` environment variable.
+const LEGACY_KEY_ID: &str = "legacy";
+
+/// How often uses of keys are written to disk. `last_used` is kept in memory in between,
+/// and written when the store is dropped.
+const LAST_USED_SAVE_INTERVAL: Duration = Duration::minutes(5);
+
+/// Managed API keys, persisted as hashes in a JSON file.
+/// The key in the `API_KEY` environment variable, if set, is accepted with every scope in every namespace.
+pub struct ApiKeyStore {
+    keys: Mutex<HashMap<String, ApiKeyRecord>>,
+    path: Option<String>,
+    legacy_hash: Option<String>,
+    clock: SharedClock,
+    /// When the keys were last written, and whether uses were recorded since.
+    saved: Mutex<(DateTime<Utc>, bool)>,
+}
+
+impl ApiKeyStore {
+    /// Loads the keys stored at `path`, or starts empty. `None` keeps the keys in memory only.
+    pub fn new(path: Option<String>, clock: SharedClock) -> Self {
+        let keys = path.as_deref()
+            .filter(|path| Path::new(path).exists())
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        let legacy_hash = std::env::var("API_KEY")
+            .ok()
+            .filter(|key| !key.is_empty())
+            .map(|key| hash_key(&key));
+
+        ApiKeyStore {
+            keys: Mutex::new(keys),
+            path,
+            legacy_hash,
+            saved: Mutex::new((clock.now(), false)),
+            clock,
+        }
+    }
+
+    /// Creates a key for the counters of `namespace` and returns its plaintext value together with its details.
+    /// `global` keys may also read `/metrics`; callers only set it for owners of every namespace.
+    pub fn create(&self, namespace: &str, name: &str, scopes: Vec<ApiKeyScope>, prefixes: Vec<String>, global: bool) -> (String, ApiKeyResponse) {
+        let id = random_hex(8);
+        let key = format!("vc_{}", random_hex(32));
+
+        let record = ApiKeyRecord {
+            id: id.clone(),
+            name: name.to_string(),
+            hash: hash_key(&key),
+            scopes,
+            prefixes,
+            namespace: namespace.to_string(),
+            global,
+            created_at: self.clock.now(),
+            last_used: None,
+        };
+        let response = to_response(&record);
+
+        self.keys.lock().unwrap().insert(id, record);
+        self.save();
+
+        (key, response)
+    }
+
+    /// Lists the stored keys of a namespace, oldest first.
+    pub fn list(&self, namespace: &str) -> Vec<ApiKeyResponse> {
+        let keys = self.keys.lock().unwrap();
+        let mut list: Vec<_> = keys.values()
+            .filter(|record| record.namespace == namespace)
+            .map(to_response)
+            .collect();
+        list.sort_by_key(|key| key.created_at);
+        list
+    }
+
+    /// Revokes a key of a namespace. Returns false if it does not exist there.
+    pub fn revoke(&self, namespace: &str, id: &str) -> bool {
+        let removed = {
+            let mut keys = self.keys.lock().unwrap();
+            let in_namespace = keys.get(id).is_some_and(|record| record.namespace == namespace);
+            in_namespace && keys.remove(id).is_some()
+        };
+        if removed {
+            self.save();
+        }
+        removed
+    }
+
+    /// Finds the key matching `presented` and records its use.
+    /// Uses are saved at most every `LAST_USED_SAVE_INTERVAL`, not on every request.
+    pub fn authenticate(&self, presented: &str) -> Option<ApiKey> {
+        let hash = hash_key(presented);
+
+        if let Some(legacy) = &self.legacy_hash {
+            if bool::from(legacy.as_bytes().ct_eq(hash.as_bytes())) {
+                return Some(ApiKey {
+                    id: LEGACY_KEY_ID.to_string(),
+                    scopes: vec![ApiKeyScope::Admin],
+                    prefixes: Vec::new(),
+                    namespace: None,
+                    global: true,
+                });
+            }
+        }
+
+        let now = self.clock.now();
+        let key = {
+            let mut keys = self.keys.lock().unwrap();
+            let record = keys.values_mut()
+                .find(|record| bool::from(record.hash.as_bytes().ct_eq(hash.as_bytes())))?;
+            record.last_used = Some(now);
+            ApiKey {
+                id: record.id.clone(),
+                scopes: record.scopes.clone(),
+                prefixes: record.prefixes.clone(),
+                namespace: Some(record.namespace.clone()),
+                global: record.global,
+            }
+        };
+
+        let save_due = {
+            let mut saved = self.saved.lock().unwrap();
+            saved.1 = true;
+            now - saved.0 >= LAST_USED_SAVE_INTERVAL
+        };
+        if save_due {
+            self.save();
+        }
+
+        Some(key)
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        *self.saved.lock().unwrap() = (self.clock.now(), false);
+        let keys = self.keys.lock().unwrap();
+        if let Ok(content) = serde_json::to_string_pretty(&*keys) {
+            if let Err(e) = std::fs::write(path, content) {
+                eprintln!("Failed to save API keys: {}", e);
+            }
+        }
+    }
+}
+
+/// Writes the uses recorded since the last save when the server shuts down.
+impl Drop for ApiKeyStore {
+    fn drop(&mut self) {
+        if self.saved.lock().unwrap().1 {
+            self.save();
+        }
+    }
+}
+
+fn to_response(record: &ApiKeyRecord) -> ApiKeyResponse {
+    ApiKeyResponse {
+        id: record.id.clone(),
+        name: record.name.clone(),
+        scopes: record.scopes.clone(),
+        prefixes: record.prefixes.clone(),
+        namespace: record.namespace.clone(),
+        global: record.global,
+        created_at: record.created_at,
+        last_used: record.last_used,
+    }
+}
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+fn random_hex(bytes: usize) -> String {
+    let mut buffer = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut buffer);
+    hex::encode(buffer)
+}
+
+/// A valid API key presented in the "x-api-key" header.
+pub struct ApiKey {
+    id: String,
+    scopes: Vec<ApiKeyScope>,
+    prefixes: Vec<String>,
+    /// `None` for the `API_KEY` environment variable, which applies to every namespace.
+    namespace: Option<String>,
+    /// Whether the key covers every namespace for `/metrics`
+    global: bool,
+}
+
+impl ApiKey {
+    /// Id of the stored key, or `legacy` for the `API_KEY` environment variable.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Whether the key grants `scope` on the counter stored under `key`.
+    /// Prefixes apply to the name within the namespace.
+    pub fn allows(&self, scope: ApiKeyScope, key: &str) -> bool {
+        let key_namespace = namespaces::namespace_of(key);
+        let in_namespace = self.namespace.as_deref().is_none_or(|namespace| namespace == key_namespace);
+        let name = namespaces::local_name(key);
+
+        let has_scope = self.scopes.contains(&scope) || self.scopes.contains(&ApiKeyScope::Admin);
+        let has_prefix = self.prefixes.is_empty() || self.prefixes.iter().any(|prefix| name.starts_with(prefix));
+        in_namespace && has_scope && has_prefix
+    }
+
+    /// Whether the key covers every namespace: the `API_KEY` environment variable, or a stored key
+    /// created as global by an owner of every namespace. The namespace of a key alone never makes it global.
+    pub fn is_global(&self) -> bool {
+        self.global
+    }
+
+    /// Like `allows`, but fails with 403 Forbidden.
+    pub fn require(&self, scope: ApiKeyScope, key: &str) -> Result<(), Status> {
+        if self.allows(scope, key) {
+            Ok(())
+        } else {
+            Err(Status::Forbidden)
+        }
+    }
+}
+
+/// Why no `ApiKey` could be extracted from a request.
+#[derive(Debug)]
+pub enum ApiKeyError {
+    Missing,
+    Invalid,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiKey {
+    type Error = ApiKeyError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(presented) = req.headers().get_one("x-api-key") else {
+            return Outcome::Error((Status::Unauthorized, ApiKeyError::Missing));
+        };
+        let Some(store) = req.rocket().state::<ApiKeyStore>() else {
+            return Outcome::Error((Status::InternalServerError, ApiKeyError::Invalid));
+        };
+
+        match store.authenticate(presented) {
+            Some(key) => Outcome::Success(key),
+            None => Outcome::Error((Status::Unauthorized, ApiKeyError::Invalid)),
+        }
+    }
+}
+
+/// Applies the optional API key of a public route: requests without a key pass,
+/// requests with an unknown key get 401, and known keys must grant `scope` on the counter `counter`.
+pub fn check_optional(key: Result<ApiKey, ApiKeyError>, scope: ApiKeyScope, counter: &str) -> Result<(), Status> {
+    match key {
+        Ok(key) => key.require(scope, counter),
+        Err(ApiKeyError::Missing) => Ok(()),
+        Err(ApiKeyError::Invalid) => Err(Status::Unauthorized),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    fn stored_last_used(path: &str) -> Vec<Option<DateTime<Utc>>> {
+        let keys: HashMap<String, ApiKeyRecord> = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        keys.into_values().map(|record| record.last_used).collect()
+    }
+
+    #[test]
+    fn uses_are_saved_at_most_every_interval_and_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api_keys.json").to_string_lossy().into_owned();
+        let clock = ManualClock::at("2024-01-01T08:00:00Z");
+        let store = ApiKeyStore::new(Some(path.clone()), clock.clone());
+        let (key, _) = store.create("default", "ci", vec![ApiKeyScope::Read], Vec::new(), false);
+
+        assert!(store.authenticate(&key).is_some());
+        assert_eq!(store.list("default")[0].last_used, Some(clock.now()));
+        assert_eq!(stored_last_used(&path), vec![None]);
+
+        clock.advance(LAST_USED_SAVE_INTERVAL);
+        assert!(store.authenticate(&key).is_some());
+        assert_eq!(stored_last_used(&path), vec![Some(clock.now())]);
+
+        clock.advance(Duration::minutes(1));
+        assert!(store.authenticate(&key).is_some());
+        assert_ne!(stored_last_used(&path), vec![Some(clock.now())]);
+        drop(store);
+        assert_eq!(stored_last_used(&path), vec![Some(clock.now())]);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let store = ApiKeyStore::new(None, ManualClock::at("2024-01-01T08:00:00Z"));
+        let (key, _) = store.create("default", "ci", vec![ApiKeyScope::Read], Vec::new(), false);
+        assert!(store.authenticate(&key).is_some());
+        assert!(store.authenticate("vc_unknown").is_none());
+        assert!(store.revoke("default", &store.list("default")[0].id));
+        assert!(store.authenticate(&key).is_none());
+    }
+
+    #[test]
+    fn keys_only_grant_their_scopes_namespace_and_prefixes() {
+        let key = ApiKey {
+            id: "ci".to_string(),
+            scopes: vec![ApiKeyScope::Increment],
+            prefixes: vec!["docs-".to_string()],
+            namespace: Some("default".to_string()),
+            global: false,
+        };
+        assert!(key.allows(ApiKeyScope::Increment, "docs-home"));
+        assert!(!key.allows(ApiKeyScope::Set, "docs-home"));
+        assert!(!key.allows(ApiKeyScope::Increment, "blog-home"));
+        assert!(!key.allows(ApiKeyScope::Increment, "teama/docs-home"));
+    }
+
+    #[test]
+    fn only_keys_created_as_global_are_global() {
+        let store = ApiKeyStore::new(None, ManualClock::at("2024-01-01T08:00:00Z"));
+        let (global, _) = store.create("default", "metrics", vec![ApiKeyScope::Admin], Vec::new(), true);
+        let (local, _) = store.create("default", "ci", vec![ApiKeyScope::Admin], Vec::new(), false);
+        assert!(store.authenticate(&global).unwrap().is_global());
+        assert!(!store.authenticate(&local).unwrap().is_global());
+        assert!(store.list("default").iter().any(|key| key.global));
+    }
+}
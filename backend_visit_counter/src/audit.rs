@@ -0,0 +1,232 @@
+// backend_visit_counter/src/audit.rs
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+
+use crate::api_keys::ApiKey;
+use crate::auth::AuthGuard;
+use crate::clock::SharedClock;
+use crate::models::{AuditAction, AuditEntry};
+use crate::namespaces;
+
+/// Who made an administrative change, as recorded in the audit log.
+#[derive(Clone, Copy)]
+pub enum Actor<'a> {
+    User(&'a AuthGuard),
+    ApiKey(&'a ApiKey),
+    /// A command run on the server, e.g. `import`
+    Cli,
+}
+
+impl Actor<'_> {
+    /// `user:<username>`, `api_key:<id>` or `cli`.
+    fn label(&self) -> String {
+        match self {
+            Actor::User(auth) => format!("user:{}", auth.username()),
+            Actor::ApiKey(key) => format!("api_key:{}", key.id()),
+            Actor::Cli => "cli".to_string(),
+        }
+    }
+}
+
+/// Filters of an audit log query. `None` matches everything.
+#[derive(Default)]
+pub struct AuditFilter {
+    /// Matches the entries about counters of a namespace.
+    pub namespace: Option<String>,
+    pub counter: Option<String>,
+    /// Matches the full actor (`user:alice`) or just its name or key id (`alice`).
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl AuditFilter {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        let actor_matches = self.actor.as_deref().is_none_or(|actor| {
+            entry.actor == actor || entry.actor.split_once(':').is_some_and(|(_, id)| id == actor)
+        });
+        self.namespace.as_deref().is_none_or(|namespace| namespaces::namespace_of(&entry.counter) == namespace)
+            && self.counter.as_deref().is_none_or(|counter| entry.counter == counter)
+            && actor_matches
+            && self.action.is_none_or(|action| entry.action == action)
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp < until)
+    }
+}
+
+/// Append-only log of administrative changes to counters, one JSON object per line.
+/// Entries are also kept in memory to answer queries.
+pub struct AuditLog {
+    entries: Mutex<Vec<AuditEntry>>,
+    path: Option<String>,
+    clock: SharedClock,
+}
+
+impl AuditLog {
+    /// Loads the log at `path`, or starts empty. `None` keeps the log in memory only.
+    pub fn new(path: Option<String>, clock: SharedClock) -> Self {
+        let entries = path.as_deref()
+            .filter(|path| Path::new(path).exists())
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|content| {
+                content.lines()
+                    .filter_map(|line| serde_json::from_str(line).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        AuditLog {
+            entries: Mutex::new(entries),
+            clock,
+            path,
+        }
+    }
+
+    /// Records a change. `old_value` and `new_value` are `None` when the counter did not exist before or after.
+    pub fn record(
+        &self,
+        actor: Actor<'_>,
+        action: AuditAction,
+        counter: &str,
+        old_value: Option<u64>,
+        new_value: Option<u64>,
+        client_ip: Option<IpAddr>,
+    ) {
+        self.push(AuditEntry {
+            timestamp: self.clock.now(),
+            actor: actor.label(),
+            action,
+            counter: counter.to_string(),
+            target: None,
+            old_value,
+            new_value,
+            client_ip,
+        });
+    }
+
+    /// Records a rename or merge of `counter` into `target`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_move(
+        &self,
+        actor: Actor<'_>,
+        action: AuditAction,
+        counter: &str,
+        target: &str,
+        old_value: Option<u64>,
+        new_value: Option<u64>,
+        client_ip: Option<IpAddr>,
+    ) {
+        self.push(AuditEntry {
+            timestamp: self.clock.now(),
+            actor: actor.label(),
+            action,
+            counter: counter.to_string(),
+            target: Some(target.to_string()),
+            old_value,
+            new_value,
+            client_ip,
+        });
+    }
+
+    fn push(&self, entry: AuditEntry) {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(path) = &self.path {
+            if let Err(e) = append_line(path, &entry) {
+                eprintln!("Failed to write audit log: {}", e);
+            }
+        }
+        entries.push(entry);
+    }
+
+    /// Returns one page of the entries matching `filter`, newest first, and the number of matches.
+    pub fn query(&self, filter: &AuditFilter, page: usize, per_page: usize) -> (Vec<AuditEntry>, usize) {
+        let entries = self.entries.lock().unwrap();
+        let matching: Vec<_> = entries.iter().rev().filter(|entry| filter.matches(entry)).collect();
+        let total = matching.len();
+
+        let page = matching.into_iter()
+            .skip(page.saturating_sub(1) * per_page)
+            .take(per_page)
+            .cloned()
+            .collect();
+        (page, total)
+    }
+}
+
+fn append_line(path: &str, entry: &AuditEntry) -> std::io::Result<()> {
+    let line = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    #[test]
+    fn entries_are_appended_to_the_file_and_reloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log").to_string_lossy().into_owned();
+        let clock = ManualClock::at("2024-01-01T08:00:00Z");
+        let log = AuditLog::new(Some(path.clone()), clock.clone());
+        log.record(Actor::Cli, AuditAction::Create, "home", None, Some(0), None);
+        log.record_move(Actor::Cli, AuditAction::Rename, "home", "start", Some(0), Some(0), None);
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+        let reloaded = AuditLog::new(Some(path), clock);
+        let (entries, total) = reloaded.query(&AuditFilter::default(), 1, 10);
+        assert_eq!(total, 2);
+        assert_eq!(entries[0].action, AuditAction::Rename);
+        assert_eq!(entries[0].target.as_deref(), Some("start"));
+        assert_eq!(entries[1].actor, "cli");
+    }
+
+    #[test]
+    fn queries_filter_and_page_newest_first() {
+        let clock = ManualClock::at("2024-01-01T08:00:00Z");
+        let log = AuditLog::new(None, clock.clone());
+        let start = clock.now();
+        for (counter, action) in [("home", AuditAction::Create), ("docs/home", AuditAction::Create), ("home", AuditAction::Set)] {
+            log.record(Actor::Cli, action, counter, None, Some(1), None);
+            clock.advance(Duration::hours(1));
+        }
+
+        let query = |filter: AuditFilter| {
+            let (entries, total) = log.query(&filter, 1, 10);
+            assert_eq!(entries.len(), total);
+            entries.into_iter().map(|entry| (entry.counter, entry.action)).collect::<Vec<_>>()
+        };
+        assert_eq!(query(AuditFilter::default()), vec![
+            ("home".to_string(), AuditAction::Set),
+            ("docs/home".to_string(), AuditAction::Create),
+            ("home".to_string(), AuditAction::Create),
+        ]);
+        assert_eq!(query(AuditFilter { namespace: Some("docs".to_string()), ..AuditFilter::default() }).len(), 1);
+        assert_eq!(query(AuditFilter { namespace: Some("default".to_string()), ..AuditFilter::default() }).len(), 2);
+        assert_eq!(query(AuditFilter { counter: Some("home".to_string()), ..AuditFilter::default() }).len(), 2);
+        assert_eq!(query(AuditFilter { action: Some(AuditAction::Set), ..AuditFilter::default() }).len(), 1);
+        assert_eq!(query(AuditFilter { actor: Some("cli".to_string()), ..AuditFilter::default() }).len(), 3);
+        assert_eq!(query(AuditFilter { actor: Some("alice".to_string()), ..AuditFilter::default() }).len(), 0);
+        let window = AuditFilter {
+            since: Some(start + Duration::hours(1)),
+            until: Some(start + Duration::hours(2)),
+            ..AuditFilter::default()
+        };
+        assert_eq!(query(window), vec![("docs/home".to_string(), AuditAction::Create)]);
+
+        let (page, total) = log.query(&AuditFilter::default(), 2, 2);
+        assert_eq!(total, 3);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].action, AuditAction::Create);
+        assert_eq!(page[0].counter, "home");
+    }
+}
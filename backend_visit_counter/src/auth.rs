@@ -0,0 +1,497 @@
+// backend_visit_counter/src/auth.rs
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::net::IpAddr;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use rocket::http::{Header, Status};
+use rocket::request::{FromRequest, Outcome};
+use rocket::serde::{Deserialize, Serialize};
+use rocket::Request;
+use sha2::{Digest, Sha256};
+
+use crate::clock::SharedClock;
+use crate::models::Role;
+use crate::settings::AuthSettings;
+use crate::users::{UserStore, BOOTSTRAP_USERNAME};
+
+/// Hashes a password into an Argon2 PHC string, as expected in `ADMIN_PASSWORD_HASH`.
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let salt = SaltString::encode_b64(&salt).map_err(|e| e.to_string())?;
+
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Checks a password against an Argon2 PHC string in constant time.
+pub fn verify_password(hash: &str, password: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+        .unwrap_or(false)
+}
+
+/// Runs a password check that always fails and takes as long as a real one, for logins of accounts that
+/// do not exist, so their response time does not tell which usernames do.
+pub fn reject_password(password: &str) -> bool {
+    verify_password(dummy_hash(), password);
+    false
+}
+
+/// Hash of a random password, made with the same parameters as stored hashes.
+fn dummy_hash() -> &'static str {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    DUMMY_HASH.get_or_init(|| {
+        let mut password = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut password);
+        hash_password(&hex::encode(password)).expect("hashing a random password")
+    })
+}
+
+/// `hash-password` command: reads a password from the arguments or stdin and prints its hash.
+pub fn hash_password_command(password: Option<String>) -> Result<(), String> {
+    let password = match password {
+        Some(password) => password,
+        None => {
+            eprintln!("Password:");
+            let mut line = String::new();
+            std::io::stdin().lock().read_line(&mut line).map_err(|e| e.to_string())?;
+            line.trim_end_matches(['\r', '\n']).to_string()
+        }
+    };
+    if password.is_empty() {
+        return Err("the password must not be empty".to_string());
+    }
+
+    println!("{}", hash_password(&password)?);
+    Ok(())
+}
+
+/// Argon2 hash of the password of the bootstrap `admin` owner.
+pub struct AdminCredentials {
+    hash: Option<String>,
+}
+
+impl AdminCredentials {
+    /// Reads `ADMIN_PASSWORD_HASH`, or hashes the plaintext `ADMIN_PASSWORD` kept for existing setups.
+    pub fn from_env() -> Result<Self, String> {
+        if let Ok(hash) = std::env::var("ADMIN_PASSWORD_HASH") {
+            return AdminCredentials::from_hash(hash)
+                .map_err(|e| format!("ADMIN_PASSWORD_HASH is not a valid password hash: {}", e));
+        }
+
+        if let Ok(password) = std::env::var("ADMIN_PASSWORD") {
+            eprintln!("Warning: ADMIN_PASSWORD is deprecated, set ADMIN_PASSWORD_HASH instead (see `visit_counter hash-password`).");
+            return Ok(AdminCredentials { hash: Some(hash_password(&password)?) });
+        }
+
+        eprintln!("Warning: neither ADMIN_PASSWORD_HASH nor ADMIN_PASSWORD is set, admin login is disabled.");
+        Ok(AdminCredentials { hash: None })
+    }
+
+    /// Credentials checking passwords against an Argon2 `hash`, as printed by `visit_counter hash-password`.
+    pub fn from_hash(hash: String) -> Result<Self, String> {
+        PasswordHash::new(&hash).map_err(|e| e.to_string())?;
+        Ok(AdminCredentials { hash: Some(hash) })
+    }
+
+    /// Whether a password is set, i.e. whether the `admin` account exists.
+    pub fn is_configured(&self) -> bool {
+        self.hash.is_some()
+    }
+
+    /// Checks a password in constant time.
+    pub fn verify(&self, password: &str) -> bool {
+        match &self.hash {
+            Some(hash) => verify_password(hash, password),
+            None => reject_password(password),
+        }
+    }
+}
+
+/// Failed logins of one client.
+struct FailedLogins {
+    failures: u32,
+    blocked_until: Option<DateTime<Utc>>,
+}
+
+/// Slows down password guessing: after a few failures each client must wait an
+/// exponentially growing delay between attempts, and is locked out after too many.
+pub struct LoginThrottle {
+    settings: AuthSettings,
+    clients: Mutex<HashMap<IpAddr, FailedLogins>>,
+    clock: SharedClock,
+}
+
+impl LoginThrottle {
+    pub fn new(settings: AuthSettings, clock: SharedClock) -> Self {
+        LoginThrottle {
+            settings,
+            clients: Mutex::new(HashMap::new()),
+            clock,
+        }
+    }
+
+    /// Seconds the client must still wait before trying again, if any.
+    pub fn retry_after(&self, ip: IpAddr) -> Option<i64> {
+        let clients = self.clients.lock().unwrap();
+        let blocked_until = clients.get(&ip)?.blocked_until?;
+        let remaining = (blocked_until - self.clock.now()).num_seconds();
+        (remaining > 0).then_some(remaining)
+    }
+
+    /// Records a failed attempt and blocks the client according to the backoff policy.
+    pub fn record_failure(&self, ip: IpAddr) {
+        let mut clients = self.clients.lock().unwrap();
+        let now = self.clock.now();
+
+        // Forget clients that are no longer blocked so the map does not grow without bound.
+        if clients.len() > 10_000 {
+            clients.retain(|_, client| client.blocked_until.is_some_and(|until| until > now));
+        }
+
+        let client = clients.entry(ip).or_insert(FailedLogins { failures: 0, blocked_until: None });
+        client.failures += 1;
+        client.blocked_until = self.delay(client.failures).map(|delay| now + delay);
+    }
+
+    /// Clears the failures of a client after a successful login.
+    pub fn record_success(&self, ip: IpAddr) {
+        self.clients.lock().unwrap().remove(&ip);
+    }
+
+    fn delay(&self, failures: u32) -> Option<Duration> {
+        let lockout = Duration::minutes(self.settings.lockout_minutes as i64);
+        if failures >= self.settings.lockout_attempts {
+            return Some(lockout);
+        }
+        if failures < self.settings.free_login_attempts {
+            return None;
+        }
+        let exponent = (failures - self.settings.free_login_attempts).min(20);
+        Some(Duration::seconds(1 << exponent).min(lockout))
+    }
+}
+
+/// A logged-in admin session. Only the hash of the token is stored.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde")]
+struct Session {
+    /// Sessions created before user accounts existed belong to `admin`.
+    #[serde(default = "bootstrap_username")]
+    username: String,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+/// Bearer tokens of admin sessions, persisted next to the counters when a path is given.
+pub struct SessionStore {
+    ttl: Duration,
+    sessions: Mutex<HashMap<String, Session>>,
+    path: Option<String>,
+    clock: SharedClock,
+}
+
+impl SessionStore {
+    /// Loads the sessions stored at `path`, or starts empty. `None` keeps them in memory only.
+    pub fn new(path: Option<String>, ttl_minutes: u32, clock: SharedClock) -> Self {
+        let sessions: HashMap<String, Session> = path.as_deref()
+            .filter(|path| Path::new(path).exists())
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        let store = SessionStore {
+            ttl: Duration::minutes(ttl_minutes as i64),
+            sessions: Mutex::new(sessions),
+            path,
+            clock,
+        };
+        store.purge_expired();
+        store
+    }
+
+    /// Starts a session for `username` and returns its token and expiry.
+    pub fn create(&self, username: &str) -> (String, DateTime<Utc>) {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let token = hex::encode(bytes);
+
+        let now = self.clock.now();
+        let expires_at = now + self.ttl;
+        self.sessions.lock().unwrap().insert(hash_token(&token), Session {
+            username: username.to_string(),
+            created_at: now,
+            expires_at,
+        });
+        self.purge_expired();
+
+        (token, expires_at)
+    }
+
+    /// The user of the session of `token`, if it has not expired.
+    pub fn validate(&self, token: &str) -> Option<String> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions.get(&hash_token(token))
+            .filter(|session| session.expires_at > self.clock.now())
+            .map(|session| session.username.clone())
+    }
+
+    /// Ends the session of `token`.
+    pub fn revoke(&self, token: &str) {
+        let removed = self.sessions.lock().unwrap().remove(&hash_token(token)).is_some();
+        if removed {
+            self.save();
+        }
+    }
+
+    /// Drops expired sessions and saves the rest.
+    fn purge_expired(&self) {
+        let now = self.clock.now();
+        self.sessions.lock().unwrap().retain(|_, session| session.expires_at > now);
+        self.save();
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let sessions = self.sessions.lock().unwrap();
+        if let Ok(content) = serde_json::to_string_pretty(&*sessions) {
+            if let Err(e) = std::fs::write(path, content) {
+                eprintln!("Failed to save sessions: {}", e);
+            }
+        }
+    }
+}
+
+fn bootstrap_username() -> String {
+    BOOTSTRAP_USERNAME.to_string()
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Request guard for admin routes: requires `Authorization: Bearer <token>` of a live session.
+/// Any role passes; use `EditorGuard` or `OwnerGuard` to require more.
+pub struct AuthGuard {
+    token: String,
+    username: String,
+    role: Role,
+    namespace: Option<String>,
+}
+
+impl AuthGuard {
+    /// The bearer token that authenticated the request.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    /// The only namespace the user may administer, or `None` for all of them.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AuthGuard {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let token = req.headers()
+            .get_one("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "));
+        let (Some(token), Some(sessions), Some(users)) =
+            (token, req.rocket().state::<SessionStore>(), req.rocket().state::<UserStore>())
+        else {
+            return Outcome::Error((Status::Unauthorized, ()));
+        };
+
+        // The role is looked up on every request, so role changes and deleted users apply at once.
+        let user = sessions.validate(token)
+            .and_then(|username| users.access_of(&username).map(|access| (username, access)));
+        match user {
+            Some((username, (role, namespace))) => {
+                Outcome::Success(AuthGuard { token: token.to_string(), username, role, namespace })
+            }
+            None => Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+/// Authenticates the request and requires at least `role`, failing with 403 Forbidden otherwise.
+async fn require_role(req: &Request<'_>, role: Role) -> Outcome<AuthGuard, ()> {
+    let auth = match req.guard::<AuthGuard>().await {
+        Outcome::Success(auth) => auth,
+        Outcome::Error(e) => return Outcome::Error(e),
+        Outcome::Forward(status) => return Outcome::Forward(status),
+    };
+    if auth.role >= role {
+        Outcome::Success(auth)
+    } else {
+        Outcome::Error((Status::Forbidden, ()))
+    }
+}
+
+/// Request guard for admin routes that create or change data.
+pub struct EditorGuard(pub AuthGuard);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for EditorGuard {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        require_role(req, Role::Editor).await.map(EditorGuard)
+    }
+}
+
+impl Deref for EditorGuard {
+    type Target = AuthGuard;
+
+    fn deref(&self) -> &AuthGuard {
+        &self.0
+    }
+}
+
+/// Request guard for admin routes that delete data or manage users.
+pub struct OwnerGuard(pub AuthGuard);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for OwnerGuard {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        require_role(req, Role::Owner).await.map(OwnerGuard)
+    }
+}
+
+impl Deref for OwnerGuard {
+    type Target = AuthGuard;
+
+    fn deref(&self) -> &AuthGuard {
+        &self.0
+    }
+}
+
+/// Why a login was refused.
+#[derive(Responder)]
+pub enum LoginError {
+    #[response(status = 401)]
+    InvalidCredentials(&'static str),
+    #[response(status = 429)]
+    TooManyAttempts(&'static str, Header<'static>),
+}
+
+impl LoginError {
+    pub fn too_many_attempts(retry_after: i64) -> Self {
+        LoginError::TooManyAttempts(
+            "Too many failed login attempts",
+            Header::new("Retry-After", retry_after.to_string()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn missing_accounts_still_check_a_password() {
+        // A hash that parses, so the check runs Argon2 like a real one
+        assert!(PasswordHash::new(dummy_hash()).is_ok());
+        assert!(!reject_password("anything"));
+        assert!(!AdminCredentials { hash: None }.verify("anything"));
+    }
+
+    #[test]
+    fn sessions_expire_after_their_ttl() {
+        let clock = ManualClock::at("2024-01-01T08:00:00Z");
+        let sessions = SessionStore::new(None, 60, clock.clone());
+        let (token, expires_at) = sessions.create("alice");
+        assert_eq!(expires_at, DateTime::parse_from_rfc3339("2024-01-01T09:00:00Z").unwrap());
+
+        clock.advance(Duration::minutes(59));
+        assert_eq!(sessions.validate(&token).as_deref(), Some("alice"));
+
+        clock.advance(Duration::minutes(1));
+        assert_eq!(sessions.validate(&token), None);
+    }
+
+    #[test]
+    fn passwords_are_checked_against_their_hash() {
+        let hash = hash_password("correct horse").unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert_ne!(hash, hash_password("correct horse").unwrap());
+        assert!(verify_password(&hash, "correct horse"));
+        assert!(!verify_password(&hash, "correct horse "));
+        assert!(!verify_password("not a hash", "correct horse"));
+
+        let credentials = AdminCredentials::from_hash(hash).unwrap();
+        assert!(credentials.is_configured());
+        assert!(credentials.verify("correct horse"));
+        assert!(AdminCredentials::from_hash("plaintext".to_string()).is_err());
+    }
+
+    #[test]
+    fn sessions_are_stored_hashed_and_survive_restarts_until_revoked() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.json").to_string_lossy().into_owned();
+        let clock = ManualClock::at("2024-01-01T08:00:00Z");
+        let sessions = SessionStore::new(Some(path.clone()), 60, clock.clone());
+        let (token, _) = sessions.create("alice");
+        assert!(!std::fs::read_to_string(&path).unwrap().contains(&token));
+
+        let restarted = SessionStore::new(Some(path.clone()), 60, clock.clone());
+        assert_eq!(restarted.validate(&token).as_deref(), Some("alice"));
+        restarted.revoke(&token);
+        assert_eq!(restarted.validate(&token), None);
+        assert_eq!(SessionStore::new(Some(path), 60, clock).validate(&token), None);
+    }
+
+    #[test]
+    fn failed_logins_back_off_then_lock_out() {
+        let clock = ManualClock::at("2024-01-01T08:00:00Z");
+        let throttle = LoginThrottle::new(AuthSettings::default(), clock.clone());
+        let client = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+        for _ in 0..2 {
+            throttle.record_failure(client);
+        }
+        assert_eq!(throttle.retry_after(client), None);
+
+        throttle.record_failure(client);
+        assert_eq!(throttle.retry_after(client), Some(1));
+        throttle.record_failure(client);
+        assert_eq!(throttle.retry_after(client), Some(2));
+        clock.advance(Duration::seconds(2));
+        assert_eq!(throttle.retry_after(client), None);
+
+        for _ in 0..6 {
+            throttle.record_failure(client);
+        }
+        assert_eq!(throttle.retry_after(client), Some(15 * 60));
+        clock.advance(Duration::minutes(15));
+        assert_eq!(throttle.retry_after(client), None);
+    }
+}
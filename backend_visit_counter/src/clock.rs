@@ -0,0 +1,61 @@
+// backend_visit_counter/src/clock.rs
+use std::fmt;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+
+/// Source of the current time for the storage, sessions and rate limits,
+/// so tests can decide what time it is.
+pub trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// A clock shared by every part of the server, managed as Rocket state.
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The system clock, used outside of tests.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// The system clock, shared.
+pub fn system() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// A clock that stands still until it is set or advanced, for tests.
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl ManualClock {
+    pub fn new(now: DateTime<Utc>) -> Arc<Self> {
+        Arc::new(ManualClock { now: Mutex::new(now) })
+    }
+
+    /// A clock standing at an RFC 3339 timestamp.
+    pub fn at(time: &str) -> Arc<Self> {
+        ManualClock::new(DateTime::parse_from_rfc3339(time).expect("valid timestamp").with_timezone(&Utc))
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
@@ -0,0 +1,202 @@
+// backend_visit_counter/src/cors.rs
+use std::io::Cursor;
+
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{Header, Method, Status};
+use rocket::{Request, Response};
+
+use crate::settings::CorsSettings;
+
+/// Path prefixes served with the restricted (admin) policy.
+const ADMIN_PREFIXES: [&str; 2] = ["/api/admin", "/api/auth"];
+
+const ALLOWED_METHODS: &str = "GET, POST, PUT, DELETE, OPTIONS";
+const ALLOWED_HEADERS: &str = "Authorization, Content-Type, x-api-key";
+
+/// How a group of routes answers cross-origin requests.
+#[derive(Debug, Clone)]
+pub struct CorsPolicy {
+    /// `None` allows any origin; otherwise only the listed origins.
+    pub allowed_origins: Option<Vec<String>>,
+    pub allow_credentials: bool,
+}
+
+impl CorsPolicy {
+    /// Any origin, without cookies or authorization headers being exposed.
+    pub fn public() -> Self {
+        CorsPolicy { allowed_origins: None, allow_credentials: false }
+    }
+
+    /// Only the given origins, with credentials.
+    pub fn restricted(origins: Vec<String>) -> Self {
+        CorsPolicy { allowed_origins: Some(origins), allow_credentials: true }
+    }
+
+    fn allows(&self, origin: &str) -> bool {
+        match &self.allowed_origins {
+            None => true,
+            Some(origins) => origins.iter().any(|allowed| allowed == origin),
+        }
+    }
+}
+
+/// Fairing applying the public policy to badge and counter routes,
+/// and the admin policy to `/api/admin` and `/api/auth`.
+pub struct Cors {
+    public: CorsPolicy,
+    admin: CorsPolicy,
+}
+
+impl Cors {
+    pub fn new(public: CorsPolicy, admin: CorsPolicy) -> Self {
+        Cors { public, admin }
+    }
+
+    pub fn from_settings(settings: &CorsSettings) -> Self {
+        Cors::new(CorsPolicy::public(), CorsPolicy::restricted(settings.admin_origins.clone()))
+    }
+
+    fn policy_for(&self, path: &str) -> &CorsPolicy {
+        let is_admin = ADMIN_PREFIXES.iter().any(|prefix| {
+            path == *prefix || path.starts_with(&format!("{}/", prefix))
+        });
+        if is_admin { &self.admin } else { &self.public }
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for Cors {
+    fn info(&self) -> Info {
+        Info {
+            name: "CORS",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let Some(origin) = req.headers().get_one("Origin") else {
+            return;
+        };
+
+        let policy = self.policy_for(req.uri().path().as_str());
+        if !policy.allows(origin) {
+            return;
+        }
+
+        if policy.allow_credentials {
+            res.set_header(Header::new("Access-Control-Allow-Origin", origin.to_string()));
+            res.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
+            res.set_header(Header::new("Vary", "Origin"));
+        } else {
+            res.set_header(Header::new("Access-Control-Allow-Origin", "*"));
+        }
+
+        // Answer preflight requests directly, there are no OPTIONS routes.
+        let is_preflight = req.method() == Method::Options
+            && req.headers().contains("Access-Control-Request-Method");
+        if is_preflight {
+            res.set_header(Header::new("Access-Control-Allow-Methods", ALLOWED_METHODS));
+            res.set_header(Header::new("Access-Control-Allow-Headers", ALLOWED_HEADERS));
+            res.set_header(Header::new("Access-Control-Max-Age", "86400"));
+            res.set_status(Status::NoContent);
+            res.set_sized_body(0, Cursor::new(""));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+    use rocket::figment::providers::{Format, Toml};
+    use rocket::figment::Figment;
+    use rocket::local::blocking::Client;
+
+    #[get("/counter/test")]
+    fn public_route() -> &'static str {
+        "public"
+    }
+
+    #[get("/badges")]
+    fn admin_route() -> &'static str {
+        "admin"
+    }
+
+    fn client() -> Client {
+        let figment = Figment::from(Toml::string(r#"
+            storage = "memory"
+            [cors]
+            admin_origins = ["https://admin.example.com"]
+        "#));
+        let settings = Settings::from_figment(&figment).expect("valid settings");
+
+        let rocket = rocket::build()
+            .attach(Cors::from_settings(&settings.cors))
+            .mount("/api", routes![public_route])
+            .mount("/api/admin", routes![admin_route]);
+        Client::tracked(rocket).expect("valid rocket instance")
+    }
+
+    #[test]
+    fn public_routes_allow_any_origin_without_credentials() {
+        let client = client();
+        let response = client.get("/api/counter/test")
+            .header(Header::new("Origin", "https://blog.example.org"))
+            .dispatch();
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.headers().get_one("Access-Control-Allow-Origin"), Some("*"));
+        assert_eq!(response.headers().get_one("Access-Control-Allow-Credentials"), None);
+    }
+
+    #[test]
+    fn admin_routes_reject_unknown_origins() {
+        let client = client();
+        let response = client.get("/api/admin/badges")
+            .header(Header::new("Origin", "https://evil.example.net"))
+            .dispatch();
+
+        assert_eq!(response.headers().get_one("Access-Control-Allow-Origin"), None);
+        assert_eq!(response.headers().get_one("Access-Control-Allow-Credentials"), None);
+    }
+
+    #[test]
+    fn admin_routes_allow_configured_origins_with_credentials() {
+        let client = client();
+        let response = client.get("/api/admin/badges")
+            .header(Header::new("Origin", "https://admin.example.com"))
+            .dispatch();
+
+        assert_eq!(response.headers().get_one("Access-Control-Allow-Origin"), Some("https://admin.example.com"));
+        assert_eq!(response.headers().get_one("Access-Control-Allow-Credentials"), Some("true"));
+        assert_eq!(response.headers().get_one("Vary"), Some("Origin"));
+    }
+
+    #[test]
+    fn preflight_requests_are_answered() {
+        let client = client();
+        let response = client.options("/api/admin/badges")
+            .header(Header::new("Origin", "https://admin.example.com"))
+            .header(Header::new("Access-Control-Request-Method", "DELETE"))
+            .dispatch();
+
+        assert_eq!(response.status(), Status::NoContent);
+        assert_eq!(response.headers().get_one("Access-Control-Allow-Methods"), Some(ALLOWED_METHODS));
+
+        let response = client.options("/api/admin/badges")
+            .header(Header::new("Origin", "https://evil.example.net"))
+            .header(Header::new("Access-Control-Request-Method", "DELETE"))
+            .dispatch();
+
+        assert_ne!(response.status(), Status::NoContent);
+        assert_eq!(response.headers().get_one("Access-Control-Allow-Origin"), None);
+    }
+
+    #[test]
+    fn requests_without_origin_are_untouched() {
+        let client = client();
+        let response = client.get("/api/counter/test").dispatch();
+
+        assert_eq!(response.headers().get_one("Access-Control-Allow-Origin"), None);
+    }
+}
@@ -0,0 +1,123 @@
+// backend_visit_counter/src/counter_name.rs
+use std::fmt;
+use std::ops::Deref;
+
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::Request;
+
+use crate::settings::{NameSettings, Settings};
+
+/// Why a counter name was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameError {
+    Empty,
+    TooLong { max: usize },
+    InvalidChar(char),
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameError::Empty => write!(f, "counter name must not be empty"),
+            NameError::TooLong { max } => write!(f, "counter name must be at most {} characters", max),
+            NameError::InvalidChar(c) => write!(
+                f,
+                "counter name contains '{}'; only ASCII letters, digits, '-' and '_' are allowed",
+                c.escape_debug()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NameError {}
+
+/// A counter name that follows the configured policy: ASCII letters, digits, `-` and `_`,
+/// at most `names.max_length` characters, lowercased when `names.case_insensitive` is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CounterName(String);
+
+impl CounterName {
+    /// Checks `raw` against the name `policy` of the server.
+    pub fn parse(raw: &str, policy: &NameSettings) -> Result<Self, NameError> {
+        if raw.is_empty() {
+            return Err(NameError::Empty);
+        }
+        if raw.len() > policy.max_length {
+            return Err(NameError::TooLong { max: policy.max_length });
+        }
+        if let Some(c) = raw.chars().find(|c| !c.is_ascii_alphanumeric() && !matches!(c, '-' | '_')) {
+            return Err(NameError::InvalidChar(c));
+        }
+
+        if policy.case_insensitive {
+            Ok(CounterName(raw.to_ascii_lowercase()))
+        } else {
+            Ok(CounterName(raw.to_string()))
+        }
+    }
+}
+
+impl Deref for CounterName {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for CounterName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Routes leave the counter name as the ignored segment `<_>` and take it as a `CounterName`,
+/// checked against the name policy of the server. Invalid names get 400 `invalid_counter_name`.
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CounterName {
+    type Error = NameError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let (Some(raw), Some(settings)) = (name_segment(req), req.rocket().state::<Settings>()) else {
+            return Outcome::Forward(Status::InternalServerError);
+        };
+        match CounterName::parse(raw, &settings.names) {
+            Ok(name) => Outcome::Success(name),
+            Err(error) => Outcome::Error((reject(req, error.clone()), error)),
+        }
+    }
+}
+
+/// The counter name of the matched route: the segment declared as `<_>`, percent-decoded.
+pub fn name_segment<'r>(req: &'r Request<'_>) -> Option<&'r str> {
+    let index = req.route()?.uri.unmounted_origin.path().segments().position(|segment| segment == "<_>")?;
+    req.param::<&str>(index)?.ok()
+}
+
+/// Why a guard rejected the counter name of a request, for the 400 catcher.
+pub struct RejectedName(pub Option<NameError>);
+
+/// Keeps `error` for the 400 catcher and returns the status a guard fails with.
+pub fn reject(req: &Request<'_>, error: NameError) -> Status {
+    req.local_cache(|| RejectedName(Some(error)));
+    Status::BadRequest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_follow_the_policy() {
+        let policy = NameSettings { max_length: 8, case_insensitive: false };
+        assert_eq!(CounterName::parse("My-Repo_2", &policy), Err(NameError::TooLong { max: 8 }));
+        assert_eq!(CounterName::parse("My-Repo", &policy).unwrap().to_string(), "My-Repo");
+        assert_eq!(CounterName::parse("", &policy), Err(NameError::Empty));
+        assert_eq!(CounterName::parse("has space", &NameSettings::default()), Err(NameError::InvalidChar(' ')));
+        assert_eq!(CounterName::parse("docs/home", &NameSettings::default()), Err(NameError::InvalidChar('/')));
+
+        let policy = NameSettings { case_insensitive: true, ..NameSettings::default() };
+        assert_eq!(&*CounterName::parse("My-Repo", &policy).unwrap(), "my-repo");
+    }
+}
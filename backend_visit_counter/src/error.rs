@@ -0,0 +1,81 @@
+// backend_visit_counter/src/error.rs
+use rocket::http::Status;
+use rocket::response::{self, Responder};
+use rocket::serde::json::Json;
+use rocket::{Request, Response};
+
+use crate::counter_name::{NameError, RejectedName};
+use crate::models::ApiErrorResponse;
+use crate::persistent_counter::CounterError;
+
+/// An error returned as JSON: `{"error": "<code>", "message": "<details>"}`.
+#[derive(Debug)]
+pub struct ApiError {
+    status: Status,
+    code: String,
+    message: String,
+}
+
+impl ApiError {
+    pub fn new(status: Status, code: &str, message: &str) -> Self {
+        ApiError {
+            status,
+            code: code.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<NameError> for ApiError {
+    fn from(error: NameError) -> Self {
+        ApiError {
+            status: Status::BadRequest,
+            code: "invalid_counter_name".to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// The code is the status reason in snake case, e.g. `not_found`.
+impl From<Status> for ApiError {
+    fn from(status: Status) -> Self {
+        let reason = status.reason_lossy();
+        ApiError {
+            status,
+            code: reason.to_ascii_lowercase().replace(' ', "_"),
+            message: reason.to_string(),
+        }
+    }
+}
+
+impl From<CounterError> for ApiError {
+    fn from(error: CounterError) -> Self {
+        let (code, message) = match error {
+            CounterError::Unknown => ("unknown_counter", "this counter does not exist and cannot be created by a visit"),
+            CounterError::LimitReached => ("counter_limit_reached", "the maximum number of counters has been reached"),
+            error => return Status::from(error).into(),
+        };
+        ApiError::new(Status::from(error), code, message)
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let body = ApiErrorResponse {
+            error: self.code,
+            message: self.message,
+        };
+        Response::build_from(Json(body).respond_to(request)?)
+            .status(self.status)
+            .ok()
+    }
+}
+
+/// Renders 400 Bad Request as JSON, with the reason when a guard rejected the counter name.
+#[catch(400)]
+pub fn bad_request(req: &Request<'_>) -> ApiError {
+    match &req.local_cache(|| RejectedName(None)).0 {
+        Some(error) => error.clone().into(),
+        None => Status::BadRequest.into(),
+    }
+}
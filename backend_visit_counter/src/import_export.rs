@@ -0,0 +1,331 @@
+// backend_visit_counter/src/import_export.rs
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rocket::serde::json::Value;
+
+use crate::models::{Badge, BadgeMetadata, ExportDocument, ExportedCounter};
+
+/// Version written in JSON exports.
+pub const EXPORT_VERSION: u32 = 1;
+
+const CSV_HEADER: [&str; 9] = ["name", "count", "created_at", "last_accessed", "history", "description", "owner", "url", "tags"];
+
+/// JSON export document of `counters`, exported at `now`: the header, then one chunk per counter,
+/// so exports can be streamed.
+pub fn to_json(counters: Vec<ExportedCounter>, now: DateTime<Utc>) -> impl Iterator<Item = String> + Send {
+    let header = format!(
+        "{{\n  \"version\": {},\n  \"exported_at\": {},\n  \"counters\": [",
+        EXPORT_VERSION,
+        serde_json::to_string(&now).unwrap_or_default()
+    );
+    let counters = counters.into_iter().enumerate().map(|(index, counter)| {
+        let separator = if index == 0 { "\n    " } else { ",\n    " };
+        format!("{}{}", separator, serde_json::to_string(&counter).unwrap_or_default())
+    });
+    std::iter::once(header).chain(counters).chain(std::iter::once("\n  ]\n}\n".to_string()))
+}
+
+/// CSV export of `counters`: the header, then one line per counter. The history is written as
+/// `date:visits` pairs separated by `;`, and so are the tags.
+pub fn to_csv(counters: Vec<ExportedCounter>) -> impl Iterator<Item = String> + Send {
+    let header = format!("{}\n", CSV_HEADER.join(","));
+    std::iter::once(header).chain(counters.into_iter().map(|counter| csv_record(&counter)))
+}
+
+fn csv_record(counter: &ExportedCounter) -> String {
+    let history: Vec<String> = counter.history.iter()
+        .map(|(day, visits)| format!("{}:{}", day, visits))
+        .collect();
+    let fields = [
+        escape_csv(&counter.name),
+        counter.count.to_string(),
+        counter.created_at.map(|time| time.to_rfc3339()).unwrap_or_default(),
+        counter.last_accessed.map(|time| time.to_rfc3339()).unwrap_or_default(),
+        history.join(";"),
+        escape_csv(counter.metadata.description.as_deref().unwrap_or_default()),
+        escape_csv(counter.metadata.owner.as_deref().unwrap_or_default()),
+        escape_csv(counter.metadata.url.as_deref().unwrap_or_default()),
+        escape_csv(&counter.metadata.tags.iter().cloned().collect::<Vec<_>>().join(";")),
+    ];
+    format!("{}\n", fields.join(","))
+}
+
+/// Reads the counters of an import: a JSON export, a CSV export, or the legacy layout,
+/// i.e. `counters.json`, `counters_badges.json`, or both as `{"counters": ..., "badges": ...}`.
+pub fn parse(body: &str) -> Result<Vec<ExportedCounter>, String> {
+    let body = body.trim_start_matches('\u{feff}').trim();
+    if body.starts_with('{') || body.starts_with('[') {
+        let value: Value = serde_json::from_str(body).map_err(|e| format!("invalid JSON: {}", e))?;
+        parse_json(value)
+    } else {
+        parse_csv(body)
+    }
+}
+
+fn parse_json(value: Value) -> Result<Vec<ExportedCounter>, String> {
+    let invalid = |e: serde_json::Error| format!("invalid import: {}", e);
+
+    let Value::Object(object) = value else {
+        return serde_json::from_value(value).map_err(invalid);
+    };
+    if object.contains_key("version") {
+        let document: ExportDocument = serde_json::from_value(Value::Object(object)).map_err(invalid)?;
+        return Ok(document.counters);
+    }
+
+    // `{"counters": {...}, "badges": {...}}` holds both legacy files: maps of names, not badges.
+    let is_pair = !object.is_empty()
+        && object.keys().all(|key| key == "counters" || key == "badges")
+        && object.values().all(|value| value.as_object().is_some_and(|map| !map.contains_key("count")));
+    if is_pair {
+        let counters = match object.get("counters") {
+            Some(counters) => serde_json::from_value(counters.clone()).map_err(invalid)?,
+            None => HashMap::new(),
+        };
+        let badges = match object.get("badges") {
+            Some(badges) => serde_json::from_value(badges.clone()).map_err(invalid)?,
+            None => HashMap::new(),
+        };
+        return Ok(from_legacy(counters, badges));
+    }
+
+    if object.values().all(Value::is_number) {
+        let counters = serde_json::from_value(Value::Object(object)).map_err(invalid)?;
+        Ok(from_legacy(counters, HashMap::new()))
+    } else {
+        let badges = serde_json::from_value(Value::Object(object)).map_err(invalid)?;
+        Ok(from_legacy(HashMap::new(), badges))
+    }
+}
+
+/// Combines the counts of `counters.json` with the metadata of `counters_badges.json`.
+fn from_legacy(counters: HashMap<String, u64>, mut badges: HashMap<String, Badge>) -> Vec<ExportedCounter> {
+    let mut imported: Vec<_> = counters.into_iter()
+        .map(|(name, count)| match badges.remove(&name) {
+            Some(badge) => ExportedCounter { count, ..from_badge(badge) },
+            None => ExportedCounter {
+                name,
+                count,
+                created_at: None,
+                last_accessed: None,
+                history: BTreeMap::new(),
+                metadata: BadgeMetadata::default(),
+            },
+        })
+        .collect();
+    imported.extend(badges.into_values().map(from_badge));
+    imported.sort_by(|a, b| a.name.cmp(&b.name));
+    imported
+}
+
+fn from_badge(badge: Badge) -> ExportedCounter {
+    ExportedCounter {
+        name: badge.name,
+        count: badge.count,
+        created_at: Some(badge.created_at),
+        last_accessed: Some(badge.last_accessed),
+        history: badge.history,
+        metadata: badge.metadata,
+    }
+}
+
+fn parse_csv(body: &str) -> Result<Vec<ExportedCounter>, String> {
+    let mut records = read_csv(body)?.into_iter();
+    let header = records.next().ok_or("the import is empty")?;
+    let column = |name: &str| header.iter().position(|field| field.trim() == name);
+    let (Some(name_column), Some(count_column)) = (column("name"), column("count")) else {
+        return Err("CSV imports need a header with `name` and `count` columns".to_string());
+    };
+    let created_column = column("created_at");
+    let accessed_column = column("last_accessed");
+    let history_column = column("history");
+    let description_column = column("description");
+    let owner_column = column("owner");
+    let url_column = column("url");
+    let tags_column = column("tags");
+
+    let mut counters = Vec::new();
+    for (index, record) in records.enumerate() {
+        let line = index + 2;
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let field = |column: Option<usize>| {
+            column.and_then(|column| record.get(column))
+                .map(|field| unescape_formula(field.trim()))
+                .filter(|field| !field.is_empty())
+        };
+
+        let name = field(Some(name_column)).ok_or(format!("line {}: missing name", line))?;
+        let count = field(Some(count_column))
+            .and_then(|count| count.parse().ok())
+            .ok_or(format!("line {}: invalid count", line))?;
+        let time = |column| {
+            field(column).map(|value| {
+                DateTime::parse_from_rfc3339(value)
+                    .map(|time| time.with_timezone(&Utc))
+                    .map_err(|_| format!("line {}: invalid timestamp `{}`", line, value))
+            }).transpose()
+        };
+
+        counters.push(ExportedCounter {
+            name: name.to_string(),
+            count,
+            created_at: time(created_column)?,
+            last_accessed: time(accessed_column)?,
+            history: parse_history(field(history_column).unwrap_or_default())
+                .ok_or(format!("line {}: invalid history", line))?,
+            metadata: BadgeMetadata {
+                description: field(description_column).map(str::to_string),
+                owner: field(owner_column).map(str::to_string),
+                url: field(url_column).map(str::to_string),
+                tags: field(tags_column).unwrap_or_default().split(';').map(str::to_string).collect(),
+            }
+            .normalized(),
+        });
+    }
+    Ok(counters)
+}
+
+/// Parses `date:visits` pairs separated by `;`.
+fn parse_history(value: &str) -> Option<BTreeMap<NaiveDate, u64>> {
+    value.split(';')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let (day, visits) = entry.trim().split_once(':')?;
+            Some((day.parse().ok()?, visits.parse().ok()?))
+        })
+        .collect()
+}
+
+/// Splits CSV text into records, following RFC 4180 quoting.
+pub fn read_csv(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quoted field".to_string());
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// First characters that make spreadsheets read a field as a formula.
+const FORMULA_PREFIXES: [char; 4] = ['=', '+', '-', '@'];
+
+/// Quotes fields containing separators, and prefixes those starting like a formula with `'`
+/// so spreadsheets opening the export show them as text.
+fn escape_csv(value: &str) -> String {
+    let value = if value.starts_with(FORMULA_PREFIXES) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Removes the `'` that `escape_csv` adds before formulas, so exports import back unchanged.
+fn unescape_formula(field: &str) -> &str {
+    field.strip_prefix('\'').filter(|rest| rest.starts_with(FORMULA_PREFIXES)).unwrap_or(field)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counter(name: &str, count: u64) -> ExportedCounter {
+        ExportedCounter {
+            name: name.to_string(),
+            count,
+            created_at: None,
+            last_accessed: None,
+            history: BTreeMap::new(),
+            metadata: BadgeMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn fields_starting_like_formulas_are_prefixed() {
+        assert_eq!(escape_csv("home"), "home");
+        assert_eq!(escape_csv("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(escape_csv("+1"), "'+1");
+        assert_eq!(escape_csv("-home"), "'-home");
+        assert_eq!(escape_csv("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(escape_csv("a,b"), "\"a,b\"");
+        assert_eq!(escape_csv("'quoted"), "'quoted");
+    }
+
+    #[test]
+    fn csv_exports_import_back_unchanged() {
+        let mut home = counter("-home", 4);
+        home.metadata.description = Some("=1+1, really".to_string());
+        home.metadata.owner = Some("@team".to_string());
+        home.history.insert("2024-06-01".parse().unwrap(), 4);
+        let csv: String = to_csv(vec![home.clone(), counter("about", 0)]).collect();
+        assert!(csv.contains("\n'-home,4,"));
+
+        let imported = parse(&csv).unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].name, "-home");
+        assert_eq!(imported[0].history, home.history);
+        assert_eq!(imported[0].metadata, home.metadata);
+        assert_eq!(imported[1].name, "about");
+    }
+
+    #[test]
+    fn json_exports_are_valid_documents() {
+        let now = DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        for counters in [Vec::new(), vec![counter("home", 4), counter("about", 1)]] {
+            let json: String = to_json(counters.clone(), now).collect();
+            let document: ExportDocument = serde_json::from_str(&json).unwrap();
+            assert_eq!(document.version, EXPORT_VERSION);
+            assert_eq!(document.exported_at, now);
+            assert_eq!(document.counters.len(), counters.len());
+        }
+    }
+
+    #[test]
+    fn legacy_files_are_combined() {
+        let counters = parse(r#"{"counters": {"home": 4, "about": 1}, "badges": {}}"#).unwrap();
+        let counts: Vec<_> = counters.iter().map(|counter| (counter.name.as_str(), counter.count)).collect();
+        assert_eq!(counts, vec![("about", 1), ("home", 4)]);
+        assert_eq!(parse(r#"{"home": 4}"#).unwrap()[0].count, 4);
+    }
+
+    #[test]
+    fn malformed_csv_is_rejected_with_its_line() {
+        assert!(parse("name,count\nhome,many").unwrap_err().starts_with("line 2"));
+        assert!(parse("home,4").is_err());
+        assert!(parse("name,count,created_at\nhome,4,yesterday").unwrap_err().contains("invalid timestamp"));
+        assert!(read_csv("\"open").is_err());
+    }
+}
@@ -0,0 +1,565 @@
+// backend_visit_counter/src/importers.rs
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Read;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+use rocket::form::{FromFormField, ValueField};
+use rocket::serde::json::Value;
+
+use crate::audit::{Actor, AuditLog};
+use crate::clock;
+use crate::counter_name::CounterName;
+use crate::import_export;
+use crate::models::{AuditAction, BadgeMetadata, ExportedCounter, ImportChange, ImportFormat, ImportOutcome, ImportReport, ImportStrategy};
+use crate::namespaces::{Scope, DEFAULT_NAMESPACE};
+use crate::persistent_counter::PersistentCounterMap;
+use crate::settings::{self, NameSettings, Settings, StorageBackend};
+
+const USAGE: &str = "Usage: visit_counter import [--format FORMAT] [--strategy STRATEGY] [--namespace NAMESPACE] [--data-dir DIR] [--dry-run] FILE";
+
+/// A counter read from an import, with the old names it keeps as aliases.
+pub struct ImportedCounter {
+    pub counter: ExportedCounter,
+    pub aliases: Vec<String>,
+}
+
+impl From<ExportedCounter> for ImportedCounter {
+    fn from(counter: ExportedCounter) -> Self {
+        ImportedCounter {
+            counter,
+            aliases: Vec::new(),
+        }
+    }
+}
+
+/// A counter of another service: its identifier there, the name it should get here, if given, and its count.
+struct SourceCounter {
+    id: String,
+    name: Option<String>,
+    count: u64,
+}
+
+/// Reads the counters of an import in `format`, naming those of other services with the name `policy`.
+pub fn parse(format: ImportFormat, body: &str, policy: &NameSettings) -> Result<Vec<ImportedCounter>, String> {
+    let body = body.trim_start_matches('\u{feff}').trim();
+    let sources = match format {
+        ImportFormat::Auto => return Ok(import_export::parse(body)?.into_iter().map(ImportedCounter::from).collect()),
+        ImportFormat::Csv => parse_csv(body)?,
+        ImportFormat::CountApi => parse_countapi(body)?,
+        ImportFormat::MoeCounter => parse_moe_counter(body)?,
+        ImportFormat::Mapping => parse_mapping(body)?,
+    };
+    Ok(consolidate(sources, policy))
+}
+
+/// `name,count` lines. A first line without a numeric count is a header.
+fn parse_csv(body: &str) -> Result<Vec<SourceCounter>, String> {
+    let mut sources = Vec::new();
+    for (index, record) in import_export::read_csv(body)?.into_iter().enumerate() {
+        let field = |column: usize| record.get(column).map_or("", |field| field.trim());
+        if field(0).is_empty() {
+            continue;
+        }
+        let Some(count) = count_of(&Value::String(field(1).to_string())) else {
+            if index == 0 {
+                continue;
+            }
+            return Err(format!("line {}: invalid count", index + 1));
+        };
+        sources.push(SourceCounter { id: field(0).to_string(), name: None, count });
+    }
+    Ok(sources)
+}
+
+/// CountAPI dump. Keys outside the `default` namespace are identified as `<namespace>/<key>`.
+fn parse_countapi(body: &str) -> Result<Vec<SourceCounter>, String> {
+    let entries: Vec<Value> = serde_json::from_str(body).map_err(invalid_json)?;
+    entries.iter().enumerate().map(|(index, entry)| {
+        let key = string_field(entry, "key", index)?;
+        let id = match entry.get("namespace").and_then(Value::as_str) {
+            Some(namespace) if !namespace.is_empty() && namespace != DEFAULT_NAMESPACE => format!("{}/{}", namespace, key),
+            _ => key.to_string(),
+        };
+        Ok(SourceCounter { id, name: None, count: count_field(entry, "value", index)? })
+    }).collect()
+}
+
+/// Moe-Counter records, as a JSON array or one document per line.
+fn parse_moe_counter(body: &str) -> Result<Vec<SourceCounter>, String> {
+    let records: Vec<Value> = if body.starts_with('[') {
+        serde_json::from_str(body).map_err(invalid_json)?
+    } else {
+        body.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(invalid_json)?
+    };
+    records.iter().enumerate().map(|(index, record)| {
+        Ok(SourceCounter {
+            id: string_field(record, "name", index)?.to_string(),
+            name: None,
+            count: count_field(record, "num", index)?,
+        })
+    }).collect()
+}
+
+/// Generic mapping: the old identifier in `from`, an optional new `name`, and an optional `count`.
+/// Entries without a count only add an alias.
+fn parse_mapping(body: &str) -> Result<Vec<SourceCounter>, String> {
+    let entries: Vec<Value> = serde_json::from_str(body).map_err(invalid_json)?;
+    entries.iter().enumerate().map(|(index, entry)| {
+        let count = match entry.get("count") {
+            Some(_) => count_field(entry, "count", index)?,
+            None => 0,
+        };
+        Ok(SourceCounter {
+            id: string_field(entry, "from", index)?.to_string(),
+            name: entry.get("name").and_then(Value::as_str).map(str::to_string),
+            count,
+        })
+    }).collect()
+}
+
+fn invalid_json(error: serde_json::Error) -> String {
+    format!("invalid JSON: {}", error)
+}
+
+fn string_field<'a>(entry: &'a Value, field: &str, index: usize) -> Result<&'a str, String> {
+    entry.get(field)
+        .and_then(Value::as_str)
+        .ok_or(format!("entry {}: missing `{}`", index + 1, field))
+}
+
+fn count_field(entry: &Value, field: &str, index: usize) -> Result<u64, String> {
+    entry.get(field)
+        .and_then(count_of)
+        .ok_or(format!("entry {}: invalid `{}`", index + 1, field))
+}
+
+/// A count written as a number, a numeric string, or in MongoDB extended JSON (`{"$numberLong": "12"}`).
+/// Negative counts become 0.
+fn count_of(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(number) => number.as_u64()
+            .or_else(|| number.as_f64().filter(|number| number.is_finite()).map(|number| number.max(0.0) as u64)),
+        Value::String(text) => text.trim().parse().ok()
+            .or_else(|| text.trim().parse::<f64>().ok().filter(|number| number.is_finite()).map(|number| number.max(0.0) as u64)),
+        Value::Object(object) => ["$numberInt", "$numberLong", "$numberDouble"].iter()
+            .find_map(|key| object.get(*key))
+            .and_then(count_of),
+        _ => None,
+    }
+}
+
+/// Names the counters of another service, adds up those ending with the same name,
+/// and keeps their identifiers as aliases when they are valid names.
+fn consolidate(sources: Vec<SourceCounter>, policy: &NameSettings) -> Vec<ImportedCounter> {
+    let mut counters: Vec<ImportedCounter> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for source in sources {
+        let name = source.name.or_else(|| derive_name(&source.id)).unwrap_or_else(|| source.id.clone());
+        let name = CounterName::parse(&name, policy).map_or(name, |name| name.to_string());
+        let position = *positions.entry(name.clone()).or_insert_with(|| {
+            counters.push(ImportedCounter::from(ExportedCounter {
+                name,
+                count: 0,
+                created_at: None,
+                last_accessed: None,
+                history: BTreeMap::new(),
+                metadata: BadgeMetadata::default(),
+            }));
+            counters.len() - 1
+        });
+
+        let imported = &mut counters[position];
+        imported.counter.count = imported.counter.count.saturating_add(source.count);
+        if let Ok(alias) = CounterName::parse(&source.id, policy) {
+            if *alias != imported.counter.name && !imported.aliases.iter().any(|known| *known == *alias) {
+                imported.aliases.push(alias.to_string());
+            }
+        }
+    }
+    counters
+}
+
+/// Name for an identifier of another service: URLs lose their scheme, and every run of characters
+/// not allowed in names becomes a `-`, e.g. `https://example.com/blog` gives `example-com-blog`.
+fn derive_name(id: &str) -> Option<String> {
+    let id = id.trim().trim_start_matches("https://").trim_start_matches("http://");
+    let mut name = String::new();
+    for c in id.chars() {
+        if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+            name.push(c);
+        } else if !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    let name = name.trim_matches('-');
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Imports counters into the namespace of `scope` and adds the aliases of those it did not reject.
+/// New names must follow the name `policy`; names already stored are accepted as they are.
+/// Counters with invalid metadata are rejected.
+/// With `dry_run`, only reports what would change.
+pub fn apply(
+    counters: &PersistentCounterMap,
+    scope: &Scope,
+    imported: Vec<ImportedCounter>,
+    format: ImportFormat,
+    strategy: ImportStrategy,
+    dry_run: bool,
+    policy: &NameSettings,
+) -> ImportReport {
+    let mut valid = Vec::new();
+    let mut aliases = Vec::new();
+    let mut changes = Vec::new();
+    for ImportedCounter { mut counter, aliases: old_names } in imported {
+        counter.metadata = counter.metadata.normalized();
+        let name = match CounterName::parse(&counter.name, policy) {
+            Ok(name) => Ok(name.to_string()),
+            Err(_) if counters.name_in_use(&scope.key(&counter.name)) => Ok(counter.name.clone()),
+            Err(error) => Err(error.to_string()),
+        };
+        let name = match name.and_then(|name| counter.metadata.validate().map(|_| name)) {
+            Ok(name) => name,
+            Err(error) => {
+                changes.push(ImportChange {
+                    name: counter.name,
+                    outcome: ImportOutcome::Failed,
+                    old_count: None,
+                    new_count: None,
+                    aliases: Vec::new(),
+                    error: Some(error),
+                });
+                continue;
+            }
+        };
+        valid.push(ExportedCounter { name: scope.key(&name), ..counter });
+        aliases.push(old_names);
+    }
+
+    let keys: HashSet<String> = valid.iter().map(|counter| counter.name.clone()).collect();
+    let mut imported_changes = counters.import(valid, strategy, dry_run);
+
+    // `import` reports the counters in order, so the aliases line up with their changes.
+    let mut pairs = Vec::new();
+    for (change, old_names) in imported_changes.iter_mut().zip(aliases) {
+        if change.outcome == ImportOutcome::Failed {
+            continue;
+        }
+        let target = scope.key(&change.name);
+        for alias in old_names {
+            let key = scope.key(&alias);
+            if dry_run {
+                if !counters.name_in_use(&key) && !keys.contains(&key) {
+                    change.aliases.push(alias);
+                }
+            } else {
+                pairs.push((key, target.clone()));
+                change.aliases.push(alias);
+            }
+        }
+    }
+    if !pairs.is_empty() {
+        // Drop the aliases that were taken after all, e.g. by another counter of the import.
+        let mut added = counters.add_aliases(&pairs).into_iter().map(|result| result.is_ok());
+        for change in &mut imported_changes {
+            change.aliases.retain(|_| added.next().unwrap_or(false));
+        }
+    }
+    changes.extend(imported_changes);
+
+    let count = |outcome| changes.iter().filter(|change| change.outcome == outcome).count();
+    ImportReport {
+        dry_run,
+        format,
+        strategy,
+        created: count(ImportOutcome::Created),
+        updated: count(ImportOutcome::Updated),
+        unchanged: count(ImportOutcome::Unchanged),
+        skipped: count(ImportOutcome::Skipped),
+        failed: count(ImportOutcome::Failed),
+        changes,
+    }
+}
+
+/// Records the counters an import created or updated, and the aliases it added.
+pub fn record(audit: &AuditLog, actor: Actor<'_>, scope: &Scope, report: &ImportReport, client_ip: Option<IpAddr>) {
+    if report.dry_run {
+        return;
+    }
+    for change in &report.changes {
+        let key = scope.key(&change.name);
+        if matches!(change.outcome, ImportOutcome::Created | ImportOutcome::Updated) {
+            audit.record(actor, AuditAction::Import, &key, change.old_count, change.new_count, client_ip);
+        }
+        for alias in &change.aliases {
+            audit.record_move(actor, AuditAction::Alias, &scope.key(alias), &key, None, None, client_ip);
+        }
+    }
+}
+
+/// `import` command: imports FILE (`-` for stdin) into the counters of the configured data directory,
+/// or of `--data-dir`. The server must be stopped, since it keeps the counters in memory
+/// and would overwrite the import on its next write.
+pub fn import_command(args: &[String]) -> Result<(), String> {
+    let mut format = <ImportFormat as Default>::default();
+    let mut strategy = <ImportStrategy as Default>::default();
+    let mut namespace = DEFAULT_NAMESPACE.to_string();
+    let mut data_dir = None;
+    let mut dry_run = false;
+    let mut file = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value. {}", arg, USAGE));
+        match arg.as_str() {
+            "--format" => format = form_value(value()?)?,
+            "--strategy" => strategy = form_value(value()?)?,
+            "--namespace" => namespace = value()?.clone(),
+            "--data-dir" => data_dir = Some(PathBuf::from(value()?)),
+            "--dry-run" => dry_run = true,
+            _ if file.is_none() && (arg == "-" || !arg.starts_with('-')) => file = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument `{}`. {}", arg, USAGE)),
+        }
+    }
+    let file = file.ok_or(USAGE)?;
+
+    let mut figment = settings::figment();
+    if let Some(data_dir) = data_dir {
+        let file_name = figment.extract_inner::<PathBuf>("data_path").ok()
+            .and_then(|path| path.file_name().map(PathBuf::from))
+            .unwrap_or_else(|| "counters.json".into());
+        figment = figment.merge(("data_path", data_dir.join(file_name))).merge(("storage", "json"));
+    }
+    let settings = Settings::from_figment(&figment).map_err(|e| format!("invalid configuration: {}", e))?;
+    if settings.storage == StorageBackend::Memory {
+        return Err("the memory storage keeps no counters to import into; pass --data-dir".to_string());
+    }
+    if settings.namespace(&namespace).is_none() {
+        return Err(format!("unknown namespace `{}`", namespace));
+    }
+
+    let body = if file == "-" {
+        let mut body = String::new();
+        std::io::stdin().read_to_string(&mut body).map_err(|e| e.to_string())?;
+        body
+    } else {
+        std::fs::read_to_string(&file).map_err(|e| format!("cannot read {}: {}", file, e))?
+    };
+    let imported = parse(format, &body, &settings.names)?;
+
+    let clock = clock::system();
+    let counters = PersistentCounterMap::from_settings(&settings, clock.clone());
+    let scope = Scope::new(&namespace);
+    let report = apply(&counters, &scope, imported, format, strategy, dry_run, &settings.names);
+    if let Some(error) = counters.flush_stats().last_error {
+        return Err(format!("failed to write {}: {}", settings.data_path.display(), error));
+    }
+    record(&AuditLog::new(settings.log_file("audit"), clock), Actor::Cli, &scope, &report, None);
+
+    for change in &report.changes {
+        let counts = match (change.old_count, change.new_count) {
+            (Some(old), Some(new)) if old != new => format!("{} -> {}", old, new),
+            (_, Some(new)) => new.to_string(),
+            _ => change.error.clone().unwrap_or_default(),
+        };
+        let aliases = if change.aliases.is_empty() {
+            String::new()
+        } else {
+            format!(" (aliases: {})", change.aliases.join(", "))
+        };
+        println!("{:<9}  {}  {}{}", format!("{:?}", change.outcome).to_lowercase(), change.name, counts, aliases);
+    }
+    println!(
+        "{} created, {} updated, {} unchanged, {} skipped, {} failed{}",
+        report.created, report.updated, report.unchanged, report.skipped, report.failed,
+        if dry_run { " (dry run, nothing was written)" } else { "" }
+    );
+    Ok(())
+}
+
+/// Parses a command line value like the query parameter of the same name.
+fn form_value<'v, T: FromFormField<'v>>(value: &'v str) -> Result<T, String> {
+    T::from_value(ValueField::from_value(value)).map_err(|_| format!("invalid value `{}`. {}", value, USAGE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Identifier, name and count of a source counter.
+    type Read = (String, Option<String>, u64);
+
+    fn ids_and_counts(sources: Vec<SourceCounter>) -> Vec<Read> {
+        sources.into_iter().map(|source| (source.id, source.name, source.count)).collect()
+    }
+
+    fn source(id: &str, name: Option<&str>, count: u64) -> SourceCounter {
+        SourceCounter { id: id.to_string(), name: name.map(str::to_string), count }
+    }
+
+    #[test]
+    fn counts_are_read_from_numbers_strings_and_extended_json() {
+        let cases = [
+            (r#"12"#, Some(12)),
+            (r#"12.7"#, Some(12)),
+            (r#"-3"#, Some(0)),
+            (r#"" 12 ""#, Some(12)),
+            (r#""-3.5""#, Some(0)),
+            (r#"{"$numberLong": "18446744073709551615"}"#, Some(u64::MAX)),
+            (r#"{"$numberInt": 7}"#, Some(7)),
+            (r#"{"$numberDouble": "2.0"}"#, Some(2)),
+            (r#""many""#, None),
+            (r#""NaN""#, None),
+            (r#"null"#, None),
+            (r#"{"count": 3}"#, None),
+        ];
+        for (json, expected) in cases {
+            let value: Value = serde_json::from_str(json).unwrap();
+            assert_eq!(count_of(&value), expected, "{}", json);
+        }
+    }
+
+    #[test]
+    fn csv_lines_are_read_with_or_without_a_header() {
+        let cases: [(&str, Result<Vec<Read>, &str>); 6] = [
+            ("home,4\nabout,1", Ok(vec![("home".into(), None, 4), ("about".into(), None, 1)])),
+            ("name,count\nhome,4", Ok(vec![("home".into(), None, 4)])),
+            ("name,count\n\n ,\nhome, 4 \r\n", Ok(vec![("home".into(), None, 4)])),
+            ("\"a,b\",2", Ok(vec![("a,b".into(), None, 2)])),
+            ("home,4\nabout,many", Err("line 2: invalid count")),
+            ("home,4\nabout", Err("line 2: invalid count")),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(parse_csv(body).map(ids_and_counts), expected.map_err(str::to_string), "{}", body);
+        }
+        assert!(parse_csv("\"home,4").is_err());
+    }
+
+    #[test]
+    fn countapi_keys_keep_their_namespace() {
+        let body = r#"[
+            {"namespace": "default", "key": "home", "value": 4},
+            {"key": "about", "value": "1"},
+            {"namespace": "example.com", "key": "blog", "value": 2}
+        ]"#;
+        assert_eq!(ids_and_counts(parse_countapi(body).unwrap()), vec![
+            ("home".into(), None, 4),
+            ("about".into(), None, 1),
+            ("example.com/blog".into(), None, 2),
+        ]);
+
+        let errors = [
+            (r#"{"key": "home"}"#, "invalid JSON"),
+            (r#"[{"value": 4}]"#, "entry 1: missing `key`"),
+            (r#"[{"key": "home", "value": 1}, {"key": "about", "value": true}]"#, "entry 2: invalid `value`"),
+        ];
+        for (body, error) in errors {
+            assert!(parse_countapi(body).map(ids_and_counts).unwrap_err().starts_with(error), "{}", body);
+        }
+    }
+
+    #[test]
+    fn moe_counter_records_are_read_as_an_array_or_lines() {
+        let array = r#"[{"name": "home", "num": 4}, {"name": "about", "num": {"$numberLong": "1"}}]"#;
+        let lines = "{\"name\": \"home\", \"num\": 4}\n\n{\"name\": \"about\", \"num\": {\"$numberLong\": \"1\"}}\n";
+        for body in [array, lines] {
+            assert_eq!(ids_and_counts(parse_moe_counter(body).unwrap()), vec![
+                ("home".into(), None, 4),
+                ("about".into(), None, 1),
+            ]);
+        }
+
+        let errors = [
+            ("{\"name\": \"home\", \"num\": 4}\nnot json", "invalid JSON"),
+            (r#"[{"num": 4}]"#, "entry 1: missing `name`"),
+            (r#"[{"name": "home"}]"#, "entry 1: invalid `num`"),
+        ];
+        for (body, error) in errors {
+            assert!(parse_moe_counter(body).map(ids_and_counts).unwrap_err().starts_with(error), "{}", body);
+        }
+    }
+
+    #[test]
+    fn mappings_may_leave_out_the_name_and_count() {
+        let body = r#"[
+            {"from": "old-home", "name": "home", "count": 4},
+            {"from": "legacy-home", "name": "home"},
+            {"from": "about"}
+        ]"#;
+        assert_eq!(ids_and_counts(parse_mapping(body).unwrap()), vec![
+            ("old-home".into(), Some("home".into()), 4),
+            ("legacy-home".into(), Some("home".into()), 0),
+            ("about".into(), None, 0),
+        ]);
+
+        let errors = [
+            (r#"[{"name": "home"}]"#, "entry 1: missing `from`"),
+            (r#"[{"from": "home", "count": "many"}]"#, "entry 1: invalid `count`"),
+            (r#"{"from": "home"}"#, "invalid JSON"),
+        ];
+        for (body, error) in errors {
+            assert!(parse_mapping(body).map(ids_and_counts).unwrap_err().starts_with(error), "{}", body);
+        }
+    }
+
+    #[test]
+    fn names_are_derived_from_identifiers() {
+        let cases = [
+            ("home", Some("home")),
+            ("https://example.com/blog", Some("example-com-blog")),
+            ("http://example.com/blog/", Some("example-com-blog")),
+            ("example.com//a b", Some("example-com-a-b")),
+            ("  docs_v2 ", Some("docs_v2")),
+            ("héllo", Some("h-llo")),
+            ("https://", None),
+            ("///", None),
+            ("", None),
+        ];
+        for (id, expected) in cases {
+            assert_eq!(derive_name(id).as_deref(), expected, "{}", id);
+        }
+    }
+
+    #[test]
+    fn consolidation_sums_counters_and_keeps_valid_identifiers_as_aliases() {
+        let policy = NameSettings::default();
+        let sources = vec![
+            source("https://example.com/blog", None, 3),
+            source("example.com/blog", None, 2),
+            source("old-home", Some("home"), 4),
+            source("legacy_home", Some("home"), 0),
+            source("home", None, 1),
+            source("about", None, u64::MAX),
+            source("about", None, 1),
+        ];
+        let counters: Vec<_> = consolidate(sources, &policy).into_iter()
+            .map(|imported| (imported.counter.name, imported.counter.count, imported.aliases))
+            .collect();
+        assert_eq!(counters, vec![
+            ("example-com-blog".to_string(), 5, Vec::<String>::new()),
+            ("home".to_string(), 5, vec!["old-home".to_string(), "legacy_home".to_string()]),
+            ("about".to_string(), u64::MAX, Vec::new()),
+        ]);
+    }
+
+    #[test]
+    fn consolidation_follows_the_name_policy() {
+        let policy = NameSettings { case_insensitive: true, ..NameSettings::default() };
+        let counters = consolidate(vec![source("Home", None, 1), source("HOME", None, 2)], &policy);
+        assert_eq!(counters.len(), 1);
+        assert_eq!(counters[0].counter.name, "home");
+        assert_eq!(counters[0].counter.count, 3);
+        assert!(counters[0].aliases.is_empty());
+
+        // Names breaking the policy are kept as they are; `apply` reports them as failed.
+        let policy = NameSettings { max_length: 4, ..NameSettings::default() };
+        let counters = consolidate(vec![source("about", None, 1)], &policy);
+        assert_eq!(counters[0].counter.name, "about");
+    }
+}
@@ -0,0 +1,1192 @@
+// backend_visit_counter/src/lib.rs
+#[macro_use]
+extern crate rocket;
+
+mod api_keys;
+mod audit;
+pub mod auth;
+pub mod clock;
+mod counter_name;
+mod cors;
+mod error;
+mod import_export;
+pub mod importers;
+mod metrics;
+pub mod models;
+mod namespaces;
+mod periods;
+mod persistent_counter;
+mod rate_limit;
+pub mod settings;
+mod signing;
+pub mod svg_generator;
+mod users;
+
+use std::io::Cursor;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Instant;
+
+use api_keys::{ApiKey, ApiKeyError, ApiKeyStore};
+use audit::{Actor, AuditFilter, AuditLog};
+use auth::{AdminCredentials, AuthGuard, EditorGuard, LoginError, LoginThrottle, OwnerGuard, SessionStore};
+use clock::SharedClock;
+use cors::Cors;
+use counter_name::CounterName;
+use error::ApiError;
+use metrics::{Metrics, MetricsAccess, MetricsFairing};
+use models::{CounterResponse, CounterSetRequest, SvgOptions, SvgResponse,
+           BadgeCreateRequest, BadgeMetadata, PeriodRequest, PeriodView, BadgeQuery, BadgeSort, SortOrder, BadgeRenameRequest, BadgeMergeRequest, BadgeResponse, BadgeListResponse,
+           BulkRequest, BulkFilter, BulkOperation, BulkResponse, Role,
+           AliasCreateRequest, AliasResponse, AliasListResponse, SigningSecretResponse, TrashListResponse,
+           HealthCheck, HealthResponse, ReadinessResponse,
+           ApiKeyScope, ApiKeyCreateRequest, ApiKeyCreatedResponse, ApiKeyListResponse,
+           LoginRequest, TokenResponse, MeResponse,
+           UserCreateRequest, UserUpdateRequest, UserResponse, UserListResponse,
+           NamespaceResponse, NamespaceListResponse,
+           ExportFormat, ExportResponse, ImportFormat, ImportStrategy, ImportReport,
+           AuditAction, AuditQuery, AuditListResponse};
+use namespaces::{Localize, Scope, ScopedKey, DEFAULT_NAMESPACE};
+use persistent_counter::{BadgeFilter, CounterError, PersistentCounterMap};
+use rate_limit::{RateLimited, RateLimiter};
+use settings::Settings;
+use users::{UserStore, BOOTSTRAP_USERNAME};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use rocket::http::uri::Origin;
+use rocket::data::{Data, Limits, ToByteUnit};
+use rocket::http::{ContentType, Header, Status};
+use rocket::serde::json::Json;
+use rocket::figment::Figment;
+use rocket::futures::stream::{self, StreamExt};
+use rocket::response::stream::TextStream;
+use rocket::{Build, Response, Rocket, State, fs::FileServer};
+
+use svg_generator::build_custom_css;
+
+// Optionally load environment variables from .env.
+pub fn init_env() {
+    dotenv::dotenv().ok();
+}
+
+/// Storage key of the counter `name` in `namespace`, which must be configured.
+fn namespaced_key(settings: &Settings, namespace: &str, name: &str) -> Result<String, ApiError> {
+    if settings.namespace(namespace).is_none() {
+        return Err(ApiError::new(Status::NotFound, "unknown_namespace", "this namespace does not exist"));
+    }
+    Ok(namespaces::counter_key(namespace, name))
+}
+
+/// GET endpoint to return a counter as JSON (without incrementing)
+/// An "x-api-key" header is optional, but if present the key must grant the `read` scope.
+#[get("/counter/<_>")]
+async fn get_counter_json(
+    api_key: Result<ApiKey, ApiKeyError>,
+    name: CounterName,
+    counters: &State<PersistentCounterMap>,
+) -> Result<Json<CounterResponse>, ApiError> {
+    read_counter(&name, &name, api_key, counters)
+}
+
+/// GET endpoint to return a counter of a namespace as JSON (without incrementing)
+#[get("/counter/<namespace>/<_>")]
+async fn get_namespaced_counter_json(
+    namespace: &str,
+    api_key: Result<ApiKey, ApiKeyError>,
+    name: CounterName,
+    counters: &State<PersistentCounterMap>,
+    settings: &State<Settings>,
+) -> Result<Json<CounterResponse>, ApiError> {
+    let key = namespaced_key(settings, namespace, &name)?;
+    read_counter(&key, &name, api_key, counters)
+}
+
+fn read_counter(
+    key: &str,
+    name: &str,
+    api_key: Result<ApiKey, ApiKeyError>,
+    counters: &PersistentCounterMap,
+) -> Result<Json<CounterResponse>, ApiError> {
+    api_keys::check_optional(api_key, ApiKeyScope::Read, key)?;
+
+    let count = counters.get(key);
+    Ok(Json(CounterResponse {
+        name: name.to_string(),
+        count,
+    }))
+}
+
+/// POST endpoint to increment a counter (returns the new count)
+/// An "x-api-key" header is optional, but if present the key must grant the `increment` scope.
+#[post("/counter/<_>/increment")]
+async fn increment_counter_json(
+    api_key: Result<ApiKey, ApiKeyError>,
+    _rate_limited: RateLimited,
+    name: CounterName,
+    counters: &State<PersistentCounterMap>,
+) -> Result<Json<CounterResponse>, ApiError> {
+    increment_counter(&name, &name, api_key, counters)
+}
+
+/// POST endpoint to increment a counter of a namespace (returns the new count)
+#[post("/counter/<namespace>/<_>/increment")]
+async fn increment_namespaced_counter_json(
+    namespace: &str,
+    api_key: Result<ApiKey, ApiKeyError>,
+    _rate_limited: RateLimited,
+    name: CounterName,
+    counters: &State<PersistentCounterMap>,
+    settings: &State<Settings>,
+) -> Result<Json<CounterResponse>, ApiError> {
+    let key = namespaced_key(settings, namespace, &name)?;
+    increment_counter(&key, &name, api_key, counters)
+}
+
+fn increment_counter(
+    key: &str,
+    name: &str,
+    api_key: Result<ApiKey, ApiKeyError>,
+    counters: &PersistentCounterMap,
+) -> Result<Json<CounterResponse>, ApiError> {
+    // Signed counters can only be incremented by signed badge URLs or with an API key.
+    if matches!(api_key, Err(ApiKeyError::Missing)) && counters.signing_keys(key).is_some() {
+        return Err(ApiError::new(Status::Forbidden, "signature_required", "this counter only counts signed badge URLs or requests with an API key"));
+    }
+    api_keys::check_optional(api_key, ApiKeyScope::Increment, key)?;
+
+    let count = counters.increment(key)?;
+    Ok(Json(CounterResponse {
+        name: name.to_string(),
+        count,
+    }))
+}
+
+/// PUT endpoint to set a counter to a given value (for administration)
+/// The caller must include an API key with the `set` scope in the "x-api-key" header.
+#[put("/counter/<_>", data = "<new_value>")]
+async fn set_counter_json(
+    new_value: Json<CounterSetRequest>,
+    api_key: ApiKey,
+    name: CounterName,
+    client_ip: Option<IpAddr>,
+    counters: &State<PersistentCounterMap>,
+    audit: &State<AuditLog>,
+) -> Result<Json<CounterResponse>, ApiError> {
+    set_counter(&name, &name, new_value.count, &api_key, client_ip, counters, audit)
+}
+
+/// PUT endpoint to set a counter of a namespace to a given value (for administration)
+#[allow(clippy::too_many_arguments)]
+#[put("/counter/<namespace>/<_>", data = "<new_value>")]
+async fn set_namespaced_counter_json(
+    namespace: &str,
+    new_value: Json<CounterSetRequest>,
+    api_key: ApiKey,
+    name: CounterName,
+    client_ip: Option<IpAddr>,
+    counters: &State<PersistentCounterMap>,
+    audit: &State<AuditLog>,
+    settings: &State<Settings>,
+) -> Result<Json<CounterResponse>, ApiError> {
+    let key = namespaced_key(settings, namespace, &name)?;
+    set_counter(&key, &name, new_value.count, &api_key, client_ip, counters, audit)
+}
+
+fn set_counter(
+    key: &str,
+    name: &str,
+    count: u64,
+    api_key: &ApiKey,
+    client_ip: Option<IpAddr>,
+    counters: &PersistentCounterMap,
+    audit: &AuditLog,
+) -> Result<Json<CounterResponse>, ApiError> {
+    api_key.require(ApiKeyScope::Set, key)?;
+
+    let old_value = counters.get_badge(key).map(|badge| badge.count);
+    counters.set(key, count);
+    audit.record(Actor::ApiKey(api_key), AuditAction::Set, key, old_value, Some(count), client_ip);
+    Ok(Json(CounterResponse {
+        name: name.to_string(),
+        count,
+    }))
+}
+
+/// GET endpoint to return an SVG counter image.
+/// Each time the image is requested, the counter is incremented.
+/// Query parameters allow for customization (label, color, style...).
+/// Counters with a signing secret are only incremented by URLs carrying a valid `sig`;
+/// other requests render the current count.
+#[get("/counter/<_>/svg?<options..>")]
+async fn svg_counter(
+    options: Option<SvgOptions>,
+    uri: &Origin<'_>,
+    _rate_limited: RateLimited,
+    name: CounterName,
+    counters: &State<PersistentCounterMap>,
+    metrics: &State<Metrics>,
+    settings: &State<Settings>,
+) -> Result<SvgResponse, ApiError> {
+    render_counter(&name, DEFAULT_NAMESPACE, options, uri, counters, metrics, settings)
+}
+
+/// GET endpoint to return an SVG counter image of a namespace, styled with its theme.
+#[allow(clippy::too_many_arguments)]
+#[get("/counter/<namespace>/<_>/svg?<options..>")]
+async fn svg_namespaced_counter(
+    namespace: &str,
+    options: Option<SvgOptions>,
+    uri: &Origin<'_>,
+    _rate_limited: RateLimited,
+    name: CounterName,
+    counters: &State<PersistentCounterMap>,
+    metrics: &State<Metrics>,
+    settings: &State<Settings>,
+) -> Result<SvgResponse, ApiError> {
+    let key = namespaced_key(settings, namespace, &name)?;
+    render_counter(&key, namespace, options, uri, counters, metrics, settings)
+}
+
+fn render_counter(
+    key: &str,
+    namespace: &str,
+    options: Option<SvgOptions>,
+    uri: &Origin<'_>,
+    counters: &PersistentCounterMap,
+    metrics: &Metrics,
+    settings: &Settings,
+) -> Result<SvgResponse, ApiError> {
+    // Fill in the namespace theme, then the configured defaults, for anything the query string leaves unset.
+    let mut options = options.unwrap_or_default();
+    if let Some(namespace) = settings.namespace(namespace) {
+        namespace.theme.apply(&mut options);
+    }
+    options.label.get_or_insert_with(|| settings.badge.label.clone());
+    options.width.get_or_insert(settings.badge.width);
+    options.height.get_or_insert(settings.badge.height);
+    let options = Some(options);
+
+    // Increment the counter. Counters that may not be created get a badge without a count, and no state.
+    let read_only = counters.signing_keys(key)
+        .is_some_and(|keys| !signing::verify(&keys, key, uri.query(), counters.now()));
+    let view = options.as_ref().and_then(|options| options.period).unwrap_or_default();
+    let value = if read_only {
+        counters.get_view(key, view).to_string()
+    } else {
+        match counters.increment(key) {
+            Ok(count) if view == PeriodView::All => count.to_string(),
+            Ok(_) => counters.get_view(key, view).to_string(),
+            Err(_) => settings.creation.unknown_text.clone(),
+        }
+    };
+
+    let render_started = Instant::now();
+
+    // Build custom CSS if parameters are provided.
+    let custom_css = build_custom_css(options.clone());
+
+    // Combine the base CSS from assets/style.css with the custom CSS.
+    let css = format!("{}\n{}", svg_generator::BASE_CSS, custom_css);
+
+    let label = options
+        .as_ref()
+        .and_then(|opts| opts.label.clone())
+        .unwrap_or_else(|| settings.badge.label.clone());
+
+    // Get width and height
+    let width = options.clone().unwrap_or_default().width.unwrap_or(settings.badge.width);
+    let height = options.clone().unwrap_or_default().height.unwrap_or(settings.badge.height);
+
+    // Generate the SVG
+    let svg = svg_generator::generate_svg(&label, &value, &css, width, height, options.as_ref());
+    metrics.observe_svg_render(render_started.elapsed());
+
+    // Build a response with  caching headers not to store the response
+    let response = Response::build()
+        .header(ContentType::new("image", "svg+xml"))
+        .raw_header("Cache-Control", "max-age=0, no-cache, no-store, must-revalidate")
+        .raw_header("Pragma", "no-cache")
+        .raw_header("Expires", "0")
+        .sized_body(svg.len(), Cursor::new(svg))
+        .finalize();
+
+    Ok(SvgResponse(response))
+}
+
+/// GET endpoint exposing server and counter metrics in the Prometheus text format.
+/// Requires a valid API key, or the bearer token configured in `METRICS_TOKEN`.
+#[get("/metrics")]
+fn metrics_endpoint(
+    _access: MetricsAccess,
+    metrics: &State<Metrics>,
+    counters: &State<PersistentCounterMap>,
+) -> (ContentType, String) {
+    let content_type = ContentType::new("text", "plain").with_params(("version", "0.0.4"));
+    (content_type, metrics.render(counters))
+}
+
+/// GET endpoint reporting that the process is alive
+#[get("/healthz")]
+fn healthz() -> Json<HealthResponse> {
+    Json(HealthResponse { status: "ok".to_string() })
+}
+
+/// GET endpoint reporting whether the server can serve traffic.
+/// Verifies the data directory can be written and read, and that the last flush succeeded.
+#[get("/readyz")]
+fn readyz(counters: &State<PersistentCounterMap>) -> (Status, Json<ReadinessResponse>) {
+    let storage = match counters.probe() {
+        Ok(()) => HealthCheck { ok: true, error: None },
+        Err(error) => HealthCheck { ok: false, error: Some(error) },
+    };
+
+    let flush = counters.flush_stats();
+    let last_flush = HealthCheck {
+        ok: flush.last_error.is_none(),
+        error: flush.last_error,
+    };
+
+    let ready = storage.ok && last_flush.ok;
+    let status = if ready { Status::Ok } else { Status::ServiceUnavailable };
+    (status, Json(ReadinessResponse {
+        status: if ready { "ok" } else { "unavailable" }.to_string(),
+        storage,
+        last_flush,
+        last_flush_at: flush.last_flush_at,
+    }))
+}
+
+/// Authentication endpoint: exchanges a username and password for a session token.
+/// Repeated failures from the same client IP are throttled.
+#[post("/login", format = "json", data = "<body>")]
+fn login(
+    body: Json<LoginRequest>,
+    client_ip: Option<IpAddr>,
+    users: &State<UserStore>,
+    throttle: &State<LoginThrottle>,
+    sessions: &State<SessionStore>,
+) -> Result<Json<TokenResponse>, LoginError> {
+    let client_ip = client_ip.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    if let Some(retry_after) = throttle.retry_after(client_ip) {
+        return Err(LoginError::too_many_attempts(retry_after));
+    }
+
+    let username = body.username.as_deref().unwrap_or(BOOTSTRAP_USERNAME);
+    let Some(role) = users.verify(username, &body.password) else {
+        throttle.record_failure(client_ip);
+        return Err(LoginError::InvalidCredentials("Invalid credentials"));
+    };
+
+    throttle.record_success(client_ip);
+    let namespace = users.access_of(username).and_then(|(_, namespace)| namespace);
+    let (token, expires_at) = sessions.create(username);
+    Ok(Json(TokenResponse { token, expires_at, username: username.to_string(), role, namespace }))
+}
+
+/// Authentication endpoint: replaces a valid session token with a new one.
+#[post("/refresh")]
+fn refresh(auth: AuthGuard, sessions: &State<SessionStore>) -> Json<TokenResponse> {
+    sessions.revoke(auth.token());
+    let (token, expires_at) = sessions.create(auth.username());
+    Json(TokenResponse {
+        token,
+        expires_at,
+        username: auth.username().to_string(),
+        role: auth.role(),
+        namespace: auth.namespace().map(str::to_string),
+    })
+}
+
+/// Authentication endpoint: returns the user and role of the session.
+#[get("/me")]
+fn me(auth: AuthGuard) -> Json<MeResponse> {
+    Json(MeResponse {
+        username: auth.username().to_string(),
+        role: auth.role(),
+        namespace: auth.namespace().map(str::to_string),
+    })
+}
+
+/// Authentication endpoint: revokes the session token of the request.
+#[post("/logout")]
+fn logout(auth: AuthGuard, sessions: &State<SessionStore>) -> Status {
+    sessions.revoke(auth.token());
+    Status::NoContent
+}
+
+/// Admin endpoint to list the namespaces the user can administer, with their usage
+#[get("/namespaces")]
+async fn admin_list_namespaces(
+    auth: AuthGuard,
+    counters: &State<PersistentCounterMap>,
+    settings: &State<Settings>,
+) -> Json<NamespaceListResponse> {
+    let mut names: Vec<&str> = settings.namespaces.keys().map(String::as_str).collect();
+    if !names.contains(&DEFAULT_NAMESPACE) {
+        names.insert(0, DEFAULT_NAMESPACE);
+    }
+
+    let namespaces: Vec<_> = names.into_iter()
+        .filter(|name| auth.namespace().is_none_or(|own| own == *name))
+        .filter_map(|name| settings.namespace(name).map(|namespace| NamespaceResponse {
+            name: name.to_string(),
+            counters: counters.namespace_len(name),
+            max_counters: namespace.max_counters,
+        }))
+        .collect();
+    Json(NamespaceListResponse {
+        total: namespaces.len(),
+        namespaces,
+    })
+}
+
+/// Parses an RFC 3339 timestamp, or a date meaning its midnight UTC.
+fn parse_time(value: &str) -> Result<DateTime<Utc>, Status> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(NaiveTime::MIN).and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|_| Status::BadRequest)
+}
+
+/// Filter of the badges of the namespace: names or descriptions containing `q`,
+/// and `inactive_since` as an RFC 3339 timestamp or date.
+fn badge_filter(
+    scope: &Scope,
+    q: Option<String>,
+    min_count: Option<u64>,
+    inactive_since: Option<&str>,
+    tag: Option<String>,
+    owner: Option<String>,
+) -> Result<BadgeFilter, Status> {
+    Ok(BadgeFilter {
+        namespace: Some(scope.namespace().to_string()),
+        query: q.filter(|q| !q.is_empty()),
+        min_count,
+        inactive_since: inactive_since.map(parse_time).transpose()?,
+        tag: tag.filter(|tag| !tag.is_empty()),
+        owner: owner.filter(|owner| !owner.is_empty()),
+    })
+}
+
+/// Admin endpoint to list the badges of the namespace, one page at a time.
+/// Searches names and descriptions with `q`, filters on `min_count`, `inactive_since`, `tag` and `owner`, and sorts on
+/// `sort` (name, count, created_at or last_accessed) in `order`; `per_page` is capped at 500.
+#[get("/badges?<query..>")]
+async fn admin_list_badges(
+    query: BadgeQuery,
+    scope: Scope,
+    counters: &State<PersistentCounterMap>,
+) -> Result<Json<BadgeListResponse>, Status> {
+    let filter = badge_filter(&scope, query.q, query.min_count, query.inactive_since.as_deref(), query.tag, query.owner)?;
+    let sort = query.sort.unwrap_or_default();
+    let order = query.order.unwrap_or(match sort {
+        BadgeSort::Name => SortOrder::Asc,
+        _ => SortOrder::Desc,
+    });
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(50).clamp(1, 500);
+
+    let found = counters.find_badges(&filter, sort, order);
+    let total = found.len();
+    let visits = found.iter().map(|badge| badge.count).fold(0u64, u64::saturating_add);
+    let badges = found.into_iter()
+        .skip((page - 1) * per_page)
+        .take(per_page)
+        .map(Localize::localized)
+        .collect();
+    Ok(Json(BadgeListResponse { badges, total, visits, page, per_page }))
+}
+
+/// Admin endpoint to get a specific badge
+#[get("/badges/<_>")]
+async fn admin_get_badge(
+    key: ScopedKey,
+    counters: &State<PersistentCounterMap>,
+) -> Result<Json<BadgeResponse>, ApiError> {
+    match counters.get_badge_with_history(&key) {
+        Some(badge) => Ok(Json(badge.localized())),
+        None => Err(Status::NotFound.into()),
+    }
+}
+
+/// Admin endpoint to create a new badge
+#[post("/badges", format = "json", data = "<request>")]
+async fn admin_create_badge(
+    request: Json<BadgeCreateRequest>,
+    scope: Scope,
+    auth: EditorGuard,
+    client_ip: Option<IpAddr>,
+    counters: &State<PersistentCounterMap>,
+    audit: &State<AuditLog>,
+    settings: &State<Settings>,
+) -> Result<Json<BadgeResponse>, ApiError> {
+    let key = scope.checked_key(&request.name, settings, counters)?;
+
+    // Check if badge or an alias with that name already exists
+    if counters.name_in_use(&key) {
+        return Err(Status::Conflict.into());
+    }
+    if !counters.namespace_has_room(&key) {
+        return Err(CounterError::LimitReached.into());
+    }
+
+    let badge = counters.create_badge(&key, request.count);
+    audit.record(Actor::User(&auth), AuditAction::Create, &badge.name, None, Some(badge.count), client_ip);
+    Ok(Json(badge.localized()))
+}
+
+/// Admin endpoint to update a badge's counter
+#[put("/badges/<_>", format = "json", data = "<request>")]
+async fn admin_update_badge(
+    request: Json<CounterSetRequest>,
+    auth: EditorGuard,
+    key: ScopedKey,
+    client_ip: Option<IpAddr>,
+    counters: &State<PersistentCounterMap>,
+    audit: &State<AuditLog>,
+) -> Result<Json<BadgeResponse>, ApiError> {
+
+    // Check if badge exists
+    let Some(old) = counters.get_badge(&key) else {
+        return Err(Status::NotFound.into());
+    };
+
+    counters.set(&key, request.count);
+    audit.record(Actor::User(&auth), AuditAction::Set, &key, Some(old.count), Some(request.count), client_ip);
+
+    match counters.get_badge(&key) {
+        Some(badge) => Ok(Json(badge.localized())),
+        None => Err(Status::InternalServerError.into()),
+    }
+}
+
+/// Admin endpoint to replace the description, owner, URL and tags of a badge.
+/// Empty fields are cleared; invalid ones are rejected with 400 `invalid_metadata`.
+#[put("/badges/<_>/metadata", format = "json", data = "<request>")]
+async fn admin_update_badge_metadata(
+    request: Json<BadgeMetadata>,
+    auth: EditorGuard,
+    key: ScopedKey,
+    client_ip: Option<IpAddr>,
+    counters: &State<PersistentCounterMap>,
+    audit: &State<AuditLog>,
+) -> Result<Json<BadgeResponse>, ApiError> {
+    let metadata = request.into_inner().normalized();
+    metadata.validate().map_err(|message| ApiError::new(Status::BadRequest, "invalid_metadata", &message))?;
+
+    let badge = counters.set_metadata(&key, metadata).ok_or(Status::NotFound)?;
+    audit.record(Actor::User(&auth), AuditAction::Metadata, &key, None, None, client_ip);
+    Ok(Json(badge.localized()))
+}
+
+/// Admin endpoint to make a counter start over every day, week, month or year in a time zone.
+/// Badges choose the window they show with `period=current|previous|all`.
+#[put("/badges/<_>/period", format = "json", data = "<request>")]
+async fn admin_set_badge_period(
+    request: Json<PeriodRequest>,
+    auth: EditorGuard,
+    key: ScopedKey,
+    client_ip: Option<IpAddr>,
+    counters: &State<PersistentCounterMap>,
+    audit: &State<AuditLog>,
+) -> Result<Json<BadgeResponse>, ApiError> {
+    let timezone = request.timezone.as_deref().unwrap_or("UTC");
+    if periods::parse_timezone(timezone).is_none() {
+        return Err(ApiError::new(Status::BadRequest, "invalid_timezone", "timezone must be an IANA time zone such as Europe/Berlin"));
+    }
+
+    let badge = counters.set_period(&key, request.period, timezone).ok_or(Status::NotFound)?;
+    let target = match &badge.period {
+        Some(period) => format!("{} {}", period.period, period.timezone),
+        None => "none".to_string(),
+    };
+    audit.record_move(Actor::User(&auth), AuditAction::Period, &key, &target, None, None, client_ip);
+    Ok(Json(badge.localized()))
+}
+
+/// Admin endpoint to delete a badge. The badge is moved to the trash and can be restored.
+#[delete("/badges/<_>")]
+async fn admin_delete_badge(
+    auth: OwnerGuard,
+    key: ScopedKey,
+    client_ip: Option<IpAddr>,
+    counters: &State<PersistentCounterMap>,
+    audit: &State<AuditLog>,
+) -> Result<Status, ApiError> {
+    let old_value = counters.get_badge(&key).map(|badge| badge.count);
+    if counters.delete_badge(&key) {
+        audit.record(Actor::User(&auth), AuditAction::Delete, &key, old_value, None, client_ip);
+        Ok(Status::NoContent)
+    } else {
+        Err(Status::NotFound.into())
+    }
+}
+
+/// Admin endpoint to rename a badge, leaving the old name as an alias
+#[post("/badges/<_>/rename", format = "json", data = "<request>")]
+async fn admin_rename_badge(
+    request: Json<BadgeRenameRequest>,
+    auth: EditorGuard,
+    key: ScopedKey,
+    client_ip: Option<IpAddr>,
+    counters: &State<PersistentCounterMap>,
+    audit: &State<AuditLog>,
+    settings: &State<Settings>,
+) -> Result<Json<BadgeResponse>, ApiError> {
+    let new_key = key.scope().checked_key(&request.new_name, settings, counters)?;
+    let badge = counters.rename_badge(&key, &new_key)?;
+    audit.record_move(Actor::User(&auth), AuditAction::Rename, &key, &badge.name, Some(badge.count), Some(badge.count), client_ip);
+    Ok(Json(badge.localized()))
+}
+
+/// Admin endpoint to merge badges into one, summing their counts and histories
+#[post("/badges/merge", format = "json", data = "<request>")]
+async fn admin_merge_badges(
+    request: Json<BadgeMergeRequest>,
+    scope: Scope,
+    auth: EditorGuard,
+    client_ip: Option<IpAddr>,
+    counters: &State<PersistentCounterMap>,
+    audit: &State<AuditLog>,
+    settings: &State<Settings>,
+) -> Result<Json<BadgeResponse>, ApiError> {
+    let target = scope.checked_key(&request.target, settings, counters)?;
+    let sources = request.sources.iter()
+        .map(|source| scope.checked_key(source, settings, counters))
+        .collect::<Result<Vec<_>, _>>()?;
+    let old_values: Vec<_> = sources.iter()
+        .map(|source| counters.get_badge(source).map(|badge| badge.count))
+        .collect();
+    let badge = counters.merge_badges(&sources, &target)?;
+    for (source, old_value) in sources.iter().zip(old_values) {
+        audit.record_move(Actor::User(&auth), AuditAction::Merge, source, &badge.name, old_value, Some(badge.count), client_ip);
+    }
+    Ok(Json(badge.localized()))
+}
+
+/// Admin endpoint to apply one operation to many badges of the namespace: the listed `names`, or every
+/// badge matching `filter`. Nothing changes unless every badge succeeds, otherwise the response is
+/// 409 Conflict; it has one result per badge either way. Deleting needs the owner role.
+#[post("/badges/bulk", format = "json", data = "<request>")]
+async fn admin_bulk_badges(
+    request: Json<BulkRequest>,
+    scope: Scope,
+    auth: EditorGuard,
+    client_ip: Option<IpAddr>,
+    settings: &State<Settings>,
+    counters: &State<PersistentCounterMap>,
+    audit: &State<AuditLog>,
+) -> Result<(Status, Json<BulkResponse>), ApiError> {
+    let BulkRequest { names, filter, operation } = request.into_inner();
+    match &operation {
+        BulkOperation::Delete if auth.role() < Role::Owner => return Err(Status::Forbidden.into()),
+        BulkOperation::Tag { tags } | BulkOperation::Untag { tags } => {
+            if tags.is_empty() {
+                return Err(ApiError::new(Status::BadRequest, "invalid_tag", "no tags given"));
+            }
+            if let Some(tag) = tags.iter().find(|tag| !models::is_valid_tag(tag)) {
+                let message = format!(
+                    "tag `{}` must be 1 to {} ASCII letters, digits, '-' or '_'", tag, models::MAX_TAG_LENGTH
+                );
+                return Err(ApiError::new(Status::BadRequest, "invalid_tag", &message));
+            }
+        }
+        BulkOperation::Move { namespace } => {
+            // Users of one namespace cannot move badges out of it
+            if auth.namespace().is_some() {
+                return Err(Status::Forbidden.into());
+            }
+            if settings.namespace(namespace).is_none() {
+                return Err(ApiError::new(Status::NotFound, "unknown_namespace", "this namespace does not exist"));
+            }
+        }
+        _ => {}
+    }
+
+    let keys: Vec<String> = match filter {
+        Some(BulkFilter { q, min_count, inactive_since, tag, owner }) if names.is_empty() => {
+            let filter = badge_filter(&scope, q, min_count, inactive_since.as_deref(), tag, owner)?;
+            counters.find_badges(&filter, BadgeSort::Name, SortOrder::Asc)
+                .into_iter()
+                .map(|badge| badge.name)
+                .collect()
+        }
+        None if !names.is_empty() => names.iter().map(|name| scope.checked_key(name, settings, counters)).collect::<Result<_, _>>()?,
+        _ => return Err(ApiError::new(Status::BadRequest, "invalid_bulk_request", "give either `names` or a `filter`")),
+    };
+
+    let (applied, results) = counters.bulk(&keys, &operation);
+    if applied {
+        let actor = Actor::User(&auth);
+        for result in &results {
+            match &operation {
+                BulkOperation::Delete => audit.record(actor, AuditAction::Delete, &result.name, result.old_count, None, client_ip),
+                BulkOperation::Reset | BulkOperation::AddOffset { .. } => {
+                    audit.record(actor, AuditAction::Set, &result.name, result.old_count, result.new_count, client_ip)
+                }
+                BulkOperation::Tag { tags } => audit.record_move(actor, AuditAction::Tag, &result.name, &tags.join(","), None, None, client_ip),
+                BulkOperation::Untag { tags } => audit.record_move(actor, AuditAction::Untag, &result.name, &tags.join(","), None, None, client_ip),
+                BulkOperation::Move { namespace } => {
+                    audit.record_move(actor, AuditAction::Move, &result.name, namespace, result.old_count, result.new_count, client_ip)
+                }
+            }
+        }
+    }
+
+    let succeeded = results.iter().filter(|result| result.ok).count();
+    let response = BulkResponse {
+        applied,
+        succeeded,
+        failed: results.len() - succeeded,
+        results: results.into_iter().map(Localize::localized).collect(),
+    };
+    Ok((if applied { Status::Ok } else { Status::Conflict }, Json(response)))
+}
+
+/// Admin endpoint to list the badges of the namespace in the trash
+#[get("/trash")]
+async fn admin_list_trash(
+    scope: Scope,
+    counters: &State<PersistentCounterMap>,
+) -> Json<TrashListResponse> {
+    let badges: Vec<_> = counters.get_trash()
+        .into_iter()
+        .filter(|badge| scope.contains(&badge.name))
+        .map(Localize::localized)
+        .collect();
+    Json(TrashListResponse {
+        total: badges.len(),
+        badges,
+    })
+}
+
+/// Admin endpoint to restore a badge from the trash
+#[post("/trash/<_>/restore")]
+async fn admin_restore_badge(
+    auth: EditorGuard,
+    key: ScopedKey,
+    client_ip: Option<IpAddr>,
+    counters: &State<PersistentCounterMap>,
+    audit: &State<AuditLog>,
+) -> Result<Json<BadgeResponse>, ApiError> {
+    let old_value = counters.get_badge(&key).map(|badge| badge.count);
+    let badge = counters.restore_badge(&key).ok_or(Status::NotFound)?;
+    audit.record(Actor::User(&auth), AuditAction::Restore, &key, old_value, Some(badge.count), client_ip);
+    Ok(Json(badge.localized()))
+}
+
+/// Admin endpoint to permanently delete a badge from the trash
+#[delete("/trash/<_>")]
+async fn admin_purge_badge(
+    auth: OwnerGuard,
+    key: ScopedKey,
+    client_ip: Option<IpAddr>,
+    counters: &State<PersistentCounterMap>,
+    audit: &State<AuditLog>,
+) -> Result<Status, ApiError> {
+    match counters.purge_badge(&key) {
+        Some(count) => {
+            audit.record(Actor::User(&auth), AuditAction::Purge, &key, Some(count), None, client_ip);
+            Ok(Status::NoContent)
+        }
+        None => Err(Status::NotFound.into()),
+    }
+}
+
+/// Admin endpoint to query the audit log of the namespace, newest entries first.
+/// Filters on counter, actor, action and a time range; `per_page` is capped at 500.
+#[get("/audit?<query..>")]
+async fn admin_audit_log(
+    query: AuditQuery,
+    scope: Scope,
+    audit: &State<AuditLog>,
+    settings: &State<Settings>,
+) -> Result<Json<AuditListResponse>, Status> {
+    let filter = AuditFilter {
+        since: query.since.as_deref().map(parse_time).transpose()?,
+        until: query.until.as_deref().map(parse_time).transpose()?,
+        namespace: Some(scope.namespace().to_string()),
+        // Normalized like counter names, but names of purged counters that no longer follow the policy still match
+        counter: query.counter.map(|counter| {
+            scope.key(&CounterName::parse(&counter, &settings.names).map_or(counter, |name| name.to_string()))
+        }),
+        actor: query.actor,
+        action: query.action,
+    };
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(50).clamp(1, 500);
+
+    let (entries, total) = audit.query(&filter, page, per_page);
+    let entries = entries.into_iter().map(Localize::localized).collect();
+    Ok(Json(AuditListResponse { entries, total, page, per_page }))
+}
+
+/// Admin endpoint to show the signing secret of a badge
+#[get("/badges/<_>/secret")]
+async fn admin_get_signing_secret(
+    _auth: EditorGuard,
+    key: ScopedKey,
+    counters: &State<PersistentCounterMap>,
+) -> Result<Json<SigningSecretResponse>, ApiError> {
+    counters.get_signing_secret(&key).map(|secret| Json(localized_secret(secret))).ok_or(Status::NotFound.into())
+}
+
+/// Admin endpoint to require signed URLs for a badge, or rotate its secret.
+/// URLs signed with the previous secret keep counting for `signature_grace_hours`.
+#[post("/badges/<_>/secret")]
+async fn admin_rotate_signing_secret(
+    _auth: EditorGuard,
+    key: ScopedKey,
+    counters: &State<PersistentCounterMap>,
+    settings: &State<Settings>,
+) -> Result<Json<SigningSecretResponse>, ApiError> {
+    let grace = chrono::Duration::hours(settings.signature_grace_hours as i64);
+    counters.rotate_signing_secret(&key, grace).map(|secret| Json(localized_secret(secret))).ok_or(Status::NotFound.into())
+}
+
+/// Signing secrets name the badge within its namespace; signatures still cover `<namespace>/<name>`.
+fn localized_secret(mut secret: SigningSecretResponse) -> SigningSecretResponse {
+    secret.name = namespaces::local_name(&secret.name).to_string();
+    secret
+}
+
+/// Admin endpoint to stop requiring signed URLs for a badge
+#[delete("/badges/<_>/secret")]
+async fn admin_disable_signing(
+    _auth: OwnerGuard,
+    key: ScopedKey,
+    counters: &State<PersistentCounterMap>,
+) -> Result<Status, ApiError> {
+    if counters.disable_signing(&key) {
+        Ok(Status::NoContent)
+    } else {
+        Err(Status::NotFound.into())
+    }
+}
+
+/// Admin endpoint to list the aliases of the namespace
+#[get("/aliases")]
+async fn admin_list_aliases(
+    scope: Scope,
+    counters: &State<PersistentCounterMap>,
+) -> Json<AliasListResponse> {
+    let aliases: Vec<_> = counters.get_aliases()
+        .into_iter()
+        .filter(|alias| scope.contains(&alias.alias))
+        .map(Localize::localized)
+        .collect();
+    Json(AliasListResponse {
+        total: aliases.len(),
+        aliases,
+    })
+}
+
+/// Admin endpoint to add an alias for an existing counter
+#[post("/aliases", format = "json", data = "<request>")]
+async fn admin_create_alias(
+    request: Json<AliasCreateRequest>,
+    scope: Scope,
+    auth: EditorGuard,
+    client_ip: Option<IpAddr>,
+    counters: &State<PersistentCounterMap>,
+    audit: &State<AuditLog>,
+    settings: &State<Settings>,
+) -> Result<Json<AliasResponse>, ApiError> {
+    let alias = scope.checked_key(&request.alias, settings, counters)?;
+    let target = scope.checked_key(&request.target, settings, counters)?;
+    let alias = counters.add_alias(&alias, &target)?;
+    audit.record_move(Actor::User(&auth), AuditAction::Alias, &alias.alias, &alias.target, None, None, client_ip);
+    Ok(Json(alias.localized()))
+}
+
+/// Admin endpoint to remove an alias
+#[delete("/aliases/<_>")]
+async fn admin_delete_alias(
+    auth: EditorGuard,
+    key: ScopedKey,
+    client_ip: Option<IpAddr>,
+    counters: &State<PersistentCounterMap>,
+    audit: &State<AuditLog>,
+) -> Result<Status, ApiError> {
+    match counters.remove_alias(&key) {
+        Some(target) => {
+            audit.record_move(Actor::User(&auth), AuditAction::Unalias, &key, &target, None, None, client_ip);
+            Ok(Status::NoContent)
+        }
+        None => Err(Status::NotFound.into()),
+    }
+}
+
+/// Admin endpoint to download every counter of the namespace with its metadata and history,
+/// as JSON (the default) or CSV
+#[get("/export?<format>")]
+async fn admin_export(
+    format: Option<ExportFormat>,
+    scope: Scope,
+    counters: &State<PersistentCounterMap>,
+    clock: &State<SharedClock>,
+) -> ExportResponse {
+    let now = clock.now();
+    let counters = counters.export(scope.namespace());
+    let (body, content_type, extension) = match format.unwrap_or_default() {
+        ExportFormat::Json => (stream::iter(import_export::to_json(counters, now)).boxed(), ContentType::JSON, "json"),
+        ExportFormat::Csv => (stream::iter(import_export::to_csv(counters)).boxed(), ContentType::CSV, "csv"),
+    };
+    let filename = format!("counters-{}-{}.{}", scope.namespace(), now.format("%Y%m%d"), extension);
+    ExportResponse {
+        body: TextStream(body),
+        content_type,
+        disposition: Header::new("Content-Disposition", format!("attachment; filename=\"{}\"", filename)),
+    }
+}
+
+/// Admin endpoint to import counters from a JSON or CSV export, the legacy counter files,
+/// or the data of another visit counter (`format`). Existing counters are combined according to
+/// `strategy` (keep-max by default); `dry_run=true` only reports what would change.
+/// The body is limited by `limits.import` (16 MiB).
+#[allow(clippy::too_many_arguments)]
+#[post("/import?<format>&<strategy>&<dry_run>", data = "<body>")]
+async fn admin_import(
+    body: Data<'_>,
+    format: Option<ImportFormat>,
+    strategy: Option<ImportStrategy>,
+    dry_run: Option<bool>,
+    scope: Scope,
+    auth: EditorGuard,
+    client_ip: Option<IpAddr>,
+    limits: &Limits,
+    counters: &State<PersistentCounterMap>,
+    audit: &State<AuditLog>,
+    settings: &State<Settings>,
+) -> Result<Json<ImportReport>, ApiError> {
+    let body = body.open(limits.get("import").unwrap_or(16.mebibytes()))
+        .into_string()
+        .await
+        .map_err(|_| Status::BadRequest)?;
+    if !body.is_complete() {
+        return Err(Status::PayloadTooLarge.into());
+    }
+    let format = format.unwrap_or_default();
+    let imported = importers::parse(format, &body, &settings.names)
+        .map_err(|message| ApiError::new(Status::BadRequest, "invalid_import", &message))?;
+
+    let report = importers::apply(counters, &scope, imported, format, strategy.unwrap_or_default(), dry_run.unwrap_or(false), &settings.names);
+    importers::record(audit, Actor::User(&auth), &scope, &report, client_ip);
+    Ok(Json(report))
+}
+
+/// Admin endpoint to list the API keys of the namespace
+#[get("/api-keys")]
+async fn admin_list_api_keys(
+    scope: Scope,
+    api_keys: &State<ApiKeyStore>,
+) -> Json<ApiKeyListResponse> {
+    let keys = api_keys.list(scope.namespace());
+    Json(ApiKeyListResponse {
+        total: keys.len(),
+        keys,
+    })
+}
+
+/// Admin endpoint to create an API key for the namespace. The key is only returned in this response.
+/// Keys with the `admin` scope can only be created by owners.
+#[post("/api-keys", format = "json", data = "<request>")]
+async fn admin_create_api_key(
+    request: Json<ApiKeyCreateRequest>,
+    scope: Scope,
+    auth: EditorGuard,
+    api_keys: &State<ApiKeyStore>,
+) -> Result<Json<ApiKeyCreatedResponse>, Status> {
+    let request = request.into_inner();
+    if request.name.trim().is_empty() || request.scopes.is_empty() {
+        return Err(Status::BadRequest);
+    }
+    if request.scopes.contains(&ApiKeyScope::Admin) && auth.role() < Role::Owner {
+        return Err(Status::Forbidden);
+    }
+
+    // Only owners of every namespace create keys that see the metrics of every namespace.
+    let global = auth.role() == Role::Owner
+        && auth.namespace().is_none()
+        && scope.namespace() == DEFAULT_NAMESPACE
+        && request.scopes.contains(&ApiKeyScope::Admin)
+        && request.prefixes.is_empty();
+    let (key, details) = api_keys.create(scope.namespace(), request.name.trim(), request.scopes, request.prefixes, global);
+    Ok(Json(ApiKeyCreatedResponse { key, details }))
+}
+
+/// Admin endpoint to revoke an API key of the namespace
+#[delete("/api-keys/<id>")]
+async fn admin_revoke_api_key(
+    id: &str,
+    scope: Scope,
+    _auth: OwnerGuard,
+    api_keys: &State<ApiKeyStore>,
+) -> Status {
+    if api_keys.revoke(scope.namespace(), id) {
+        Status::NoContent
+    } else {
+        Status::NotFound
+    }
+}
+
+/// Checks that an owner may manage `username`: owners of a namespace only manage its users.
+fn require_managed_user(auth: &AuthGuard, users: &UserStore, username: &str) -> Result<(), Status> {
+    let Some(own) = auth.namespace() else {
+        return Ok(());
+    };
+    match users.get(username) {
+        Some(user) if user.namespace.as_deref() == Some(own) => Ok(()),
+        _ => Err(Status::NotFound),
+    }
+}
+
+/// Admin endpoint to list users. Owners of a namespace only see its users.
+#[get("/users")]
+async fn admin_list_users(
+    auth: OwnerGuard,
+    users: &State<UserStore>,
+) -> Json<UserListResponse> {
+    let users = users.list(auth.namespace());
+    Json(UserListResponse {
+        total: users.len(),
+        users,
+    })
+}
+
+/// Admin endpoint to create a user. Owners of a namespace can only create users of that namespace.
+#[post("/users", format = "json", data = "<request>")]
+async fn admin_create_user(
+    request: Json<UserCreateRequest>,
+    auth: OwnerGuard,
+    users: &State<UserStore>,
+    settings: &State<Settings>,
+) -> Result<Json<UserResponse>, Status> {
+    let request = request.into_inner();
+    let namespace = match (auth.namespace(), request.namespace) {
+        (Some(own), Some(requested)) if own != requested => return Err(Status::Forbidden),
+        (Some(own), _) => Some(own.to_string()),
+        (None, requested) => requested,
+    };
+    if namespace.as_deref().is_some_and(|namespace| settings.namespace(namespace).is_none()) {
+        return Err(Status::BadRequest);
+    }
+
+    let user = users.create(&request.username, &request.password, request.role, namespace)?;
+    Ok(Json(user))
+}
+
+/// Admin endpoint to change the role or password of a user
+#[put("/users/<username>", format = "json", data = "<request>")]
+async fn admin_update_user(
+    username: &str,
+    request: Json<UserUpdateRequest>,
+    auth: OwnerGuard,
+    users: &State<UserStore>,
+) -> Result<Json<UserResponse>, Status> {
+    require_managed_user(&auth, users, username)?;
+    let user = users.update(username, request.role, request.password.as_deref())?;
+    Ok(Json(user))
+}
+
+/// Admin endpoint to delete a user. The last owner of every namespace cannot be deleted.
+#[delete("/users/<username>")]
+async fn admin_delete_user(
+    username: &str,
+    auth: OwnerGuard,
+    users: &State<UserStore>,
+) -> Result<Status, Status> {
+    require_managed_user(&auth, users, username)?;
+    users.delete(username)?;
+    Ok(Status::NoContent)
+}
+
+/// The server configured from the environment: `Rocket.toml`, `ROCKET_*` and `VC_*` variables and `.env`.
+/// Exits on invalid configuration.
+pub fn rocket() -> Rocket<Build> {
+    init_env();
+
+    let figment = settings::figment();
+    let settings = match Settings::from_figment(&figment) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let credentials = match AdminCredentials::from_env() {
+        Ok(credentials) => credentials,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    build(figment, settings, credentials, clock::system())
+}
+
+/// The server with every route and the state they share. `figment` is Rocket's own configuration
+/// (address, limits...), `settings` those of the counters, and `clock` the time everything reads.
+pub fn build(figment: Figment, settings: Settings, credentials: AdminCredentials, clock: SharedClock) -> Rocket<Build> {
+    let counters = PersistentCounterMap::from_settings(&settings, clock.clone());
+
+    rocket::custom(figment)
+        .attach(Cors::from_settings(&settings.cors))
+        .attach(MetricsFairing)
+        .manage(Metrics::new(settings.metrics_max_counters))
+        .manage(RateLimiter::new(settings.rate_limit.per_minute, clock.clone()))
+        .manage(counters)
+        .manage(ApiKeyStore::new(settings.data_file("api_keys"), clock.clone()))
+        .manage(UserStore::new(settings.data_file("users"), credentials, clock.clone()))
+        .manage(AuditLog::new(settings.log_file("audit"), clock.clone()))
+        .manage(LoginThrottle::new(settings.auth.clone(), clock.clone()))
+        .manage(SessionStore::new(settings.data_file("sessions"), settings.auth.session_ttl_minutes, clock.clone()))
+        .manage(clock)
+        .mount("/", FileServer::from(settings.static_dir()))
+        .register("/", catchers![error::bad_request])
+        .mount("/api/auth", routes![login, refresh, logout, me])
+        .mount("/", routes![svg_counter, svg_namespaced_counter, metrics_endpoint, healthz, readyz])
+        .mount("/api", routes![
+            get_counter_json,
+            get_namespaced_counter_json,
+            increment_counter_json,
+            increment_namespaced_counter_json,
+            set_counter_json,
+            set_namespaced_counter_json
+        ])
+        .mount("/api/admin", routes![
+            admin_list_namespaces,
+            admin_list_badges,
+            admin_get_badge,
+            admin_create_badge,
+            admin_update_badge,
+            admin_update_badge_metadata,
+            admin_set_badge_period,
+            admin_delete_badge,
+            admin_rename_badge,
+            admin_merge_badges,
+            admin_bulk_badges,
+            admin_get_signing_secret,
+            admin_rotate_signing_secret,
+            admin_disable_signing,
+            admin_list_aliases,
+            admin_create_alias,
+            admin_delete_alias,
+            admin_list_trash,
+            admin_restore_badge,
+            admin_purge_badge,
+            admin_audit_log,
+            admin_export,
+            admin_import,
+            admin_list_api_keys,
+            admin_create_api_key,
+            admin_revoke_api_key,
+            admin_list_users,
+            admin_create_user,
+            admin_update_user,
+            admin_delete_user
+        ])
+        .manage(settings)
+}
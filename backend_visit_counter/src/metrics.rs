@@ -0,0 +1,300 @@
+// backend_visit_counter/src/metrics.rs
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::{Request, Response};
+use subtle::ConstantTimeEq;
+
+use crate::api_keys::ApiKey;
+use crate::persistent_counter::PersistentCounterMap;
+
+/// Upper bounds (in seconds) of the histogram buckets used for every duration metric.
+const DURATION_BUCKETS: [f64; 12] = [
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+];
+
+/// Default number of counters exported with their own `counter` label.
+pub const DEFAULT_MAX_COUNTER_SERIES: usize = 100;
+
+/// A cumulative Prometheus-style histogram of durations.
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    buckets: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    /// Records one observation.
+    pub fn observe(&mut self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter_mut().zip(DURATION_BUCKETS.iter()) {
+            if secs <= *bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += secs;
+    }
+
+    /// Writes the `_bucket`, `_sum` and `_count` series of this histogram.
+    fn write(&self, out: &mut String, name: &str, labels: &str) {
+        let separator = if labels.is_empty() { "" } else { "," };
+        for (bucket, bound) in self.buckets.iter().zip(DURATION_BUCKETS.iter()) {
+            let _ = writeln!(out, "{}_bucket{{{}{}le=\"{}\"}} {}", name, labels, separator, bound, bucket);
+        }
+        let _ = writeln!(out, "{}_bucket{{{}{}le=\"+Inf\"}} {}", name, labels, separator, self.count);
+        let labels = if labels.is_empty() { String::new() } else { format!("{{{}}}", labels) };
+        let _ = writeln!(out, "{}_sum{} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{} {}", name, labels, self.count);
+    }
+}
+
+/// Per-route request statistics.
+#[derive(Default)]
+struct RouteStats {
+    responses: HashMap<u16, u64>,
+    latency: Histogram,
+}
+
+/// In-memory registry of the metrics exposed on `/metrics`.
+pub struct Metrics {
+    started_at: Instant,
+    max_counter_series: usize,
+    routes: Mutex<HashMap<(String, String), RouteStats>>,
+    svg_render: Mutex<Histogram>,
+}
+
+impl Metrics {
+    /// Creates an empty registry.
+    /// At most `max_counter_series` counters get their own series; the rest are aggregated.
+    pub fn new(max_counter_series: usize) -> Self {
+        Metrics {
+            started_at: Instant::now(),
+            max_counter_series,
+            routes: Mutex::new(HashMap::new()),
+            svg_render: Mutex::new(Histogram::default()),
+        }
+    }
+
+    /// Records a handled request for the given method and route name.
+    pub fn observe_request(&self, method: &str, route: &str, status: Status, duration: Duration) {
+        let mut routes = self.routes.lock().unwrap();
+        let stats = routes.entry((method.to_string(), route.to_string())).or_default();
+        *stats.responses.entry(status.code).or_insert(0) += 1;
+        stats.latency.observe(duration);
+    }
+
+    /// Records the time spent building the CSS and markup of an SVG badge.
+    pub fn observe_svg_render(&self, duration: Duration) {
+        self.svg_render.lock().unwrap().observe(duration);
+    }
+
+    /// Renders every metric in the Prometheus text exposition format.
+    pub fn render(&self, counters: &PersistentCounterMap) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# HELP visit_counter_uptime_seconds Seconds since the server started.");
+        let _ = writeln!(out, "# TYPE visit_counter_uptime_seconds gauge");
+        let _ = writeln!(out, "visit_counter_uptime_seconds {}", self.started_at.elapsed().as_secs_f64());
+
+        // Counter totals, highest first, capped to keep label cardinality bounded.
+        let mut badges = counters.get_all_badges();
+        badges.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+        let total = badges.iter().map(|b| b.count).fold(0u64, u64::saturating_add);
+
+        let _ = writeln!(out, "# HELP visit_counter_counters Number of counters stored.");
+        let _ = writeln!(out, "# TYPE visit_counter_counters gauge");
+        let _ = writeln!(out, "visit_counter_counters {}", badges.len());
+        let _ = writeln!(out, "# HELP visit_counter_visits_total Sum of all counter values.");
+        let _ = writeln!(out, "# TYPE visit_counter_visits_total gauge");
+        let _ = writeln!(out, "visit_counter_visits_total {}", total);
+
+        let _ = writeln!(out, "# HELP visit_counter_counter_value Current value of a counter. Counters beyond the series cap are summed under counter=\"__other__\".");
+        let _ = writeln!(out, "# TYPE visit_counter_counter_value gauge");
+        for badge in badges.iter().take(self.max_counter_series) {
+            let _ = writeln!(out, "visit_counter_counter_value{{counter=\"{}\"}} {}", escape_label(&badge.name), badge.count);
+        }
+        if badges.len() > self.max_counter_series {
+            let other = badges.iter().skip(self.max_counter_series).map(|b| b.count).fold(0u64, u64::saturating_add);
+            let _ = writeln!(out, "visit_counter_counter_value{{counter=\"__other__\"}} {}", other);
+        }
+
+        // HTTP requests per route.
+        {
+            let routes = self.routes.lock().unwrap();
+            let mut keys: Vec<_> = routes.keys().collect();
+            keys.sort();
+
+            let _ = writeln!(out, "# HELP visit_counter_http_requests_total HTTP responses by method, route and status.");
+            let _ = writeln!(out, "# TYPE visit_counter_http_requests_total counter");
+            for key in &keys {
+                let stats = &routes[*key];
+                let mut statuses: Vec<_> = stats.responses.iter().collect();
+                statuses.sort();
+                for (status, count) in statuses {
+                    let _ = writeln!(out, "visit_counter_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                        key.0, escape_label(&key.1), status, count);
+                }
+            }
+
+            let _ = writeln!(out, "# HELP visit_counter_rate_limited_total Requests rejected with 429 Too Many Requests.");
+            let _ = writeln!(out, "# TYPE visit_counter_rate_limited_total counter");
+            for key in &keys {
+                let rejected = routes[*key].responses.get(&Status::TooManyRequests.code).copied().unwrap_or(0);
+                let _ = writeln!(out, "visit_counter_rate_limited_total{{method=\"{}\",route=\"{}\"}} {}",
+                    key.0, escape_label(&key.1), rejected);
+            }
+
+            let _ = writeln!(out, "# HELP visit_counter_http_request_duration_seconds Time spent handling a request.");
+            let _ = writeln!(out, "# TYPE visit_counter_http_request_duration_seconds histogram");
+            for key in &keys {
+                let labels = format!("method=\"{}\",route=\"{}\"", key.0, escape_label(&key.1));
+                routes[*key].latency.write(&mut out, "visit_counter_http_request_duration_seconds", &labels);
+            }
+        }
+
+        let _ = writeln!(out, "# HELP visit_counter_svg_render_seconds Time spent rendering SVG badges.");
+        let _ = writeln!(out, "# TYPE visit_counter_svg_render_seconds histogram");
+        self.svg_render.lock().unwrap().write(&mut out, "visit_counter_svg_render_seconds", "");
+
+        // Persistence flushes.
+        let flush = counters.flush_stats();
+        let _ = writeln!(out, "# HELP visit_counter_persistence_flush_seconds Time spent writing counters to storage.");
+        let _ = writeln!(out, "# TYPE visit_counter_persistence_flush_seconds histogram");
+        flush.durations.write(&mut out, "visit_counter_persistence_flush_seconds", "");
+        let _ = writeln!(out, "# HELP visit_counter_persistence_flush_failures_total Failed writes to storage.");
+        let _ = writeln!(out, "# TYPE visit_counter_persistence_flush_failures_total counter");
+        let _ = writeln!(out, "visit_counter_persistence_flush_failures_total {}", flush.failures);
+        let _ = writeln!(out, "# HELP visit_counter_persistence_last_flush_success Whether the last write to storage succeeded.");
+        let _ = writeln!(out, "# TYPE visit_counter_persistence_last_flush_success gauge");
+        let _ = writeln!(out, "visit_counter_persistence_last_flush_success {}", u8::from(flush.last_error.is_none()));
+        if let Some(at) = flush.last_flush_at {
+            let _ = writeln!(out, "# HELP visit_counter_persistence_last_flush_timestamp_seconds Unix time of the last write to storage.");
+            let _ = writeln!(out, "# TYPE visit_counter_persistence_last_flush_timestamp_seconds gauge");
+            let _ = writeln!(out, "visit_counter_persistence_last_flush_timestamp_seconds {}", at.timestamp());
+        }
+
+        out
+    }
+}
+
+/// Escapes a Prometheus label value.
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Start time of a request, stored in the request-local cache.
+struct RequestStart(Instant);
+
+/// Fairing that feeds request counts and latencies into the managed `Metrics`.
+pub struct MetricsFairing;
+
+#[rocket::async_trait]
+impl Fairing for MetricsFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request metrics",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut rocket::Data<'_>) {
+        req.local_cache(|| RequestStart(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let Some(metrics) = req.rocket().state::<Metrics>() else {
+            return;
+        };
+        let started = req.local_cache(|| RequestStart(Instant::now()));
+        // Route names are bounded by the mounted routes, unlike raw paths.
+        let route = req.route()
+            .and_then(|route| route.name.as_deref())
+            .unwrap_or("unmatched");
+        metrics.observe_request(req.method().as_str(), route, res.status(), started.0.elapsed());
+    }
+}
+
+/// Grants access to `/metrics` with either a global API key (see `ApiKey::is_global`) or,
+/// when `METRICS_TOKEN` is set, an `Authorization: Bearer <METRICS_TOKEN>` header.
+/// Keys limited to a namespace are refused: the metrics cover every namespace.
+pub struct MetricsAccess;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for MetricsAccess {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        if let Ok(token) = std::env::var("METRICS_TOKEN") {
+            let bearer = req.headers()
+                .get_one("Authorization")
+                .and_then(|value| value.strip_prefix("Bearer "));
+            if token_matches(&token, bearer) {
+                return Outcome::Success(MetricsAccess);
+            }
+        }
+        match req.guard::<ApiKey>().await {
+            Outcome::Success(key) if key.is_global() => Outcome::Success(MetricsAccess),
+            Outcome::Success(_) => Outcome::Error((Status::Forbidden, ())),
+            Outcome::Error((status, _)) => Outcome::Error((status, ())),
+            Outcome::Forward(status) => Outcome::Forward(status),
+        }
+    }
+}
+
+/// Compares a bearer token with `METRICS_TOKEN` in constant time. An empty token matches nothing.
+fn token_matches(token: &str, bearer: Option<&str>) -> bool {
+    !token.is_empty() && bearer.is_some_and(|bearer| bool::from(token.as_bytes().ct_eq(bearer.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histograms_are_cumulative() {
+        let mut histogram = Histogram::default();
+        histogram.observe(Duration::from_micros(800));
+        histogram.observe(Duration::from_secs(3));
+        let mut out = String::new();
+        histogram.write(&mut out, "latency", "route=\"home\"");
+
+        assert!(out.contains("latency_bucket{route=\"home\",le=\"0.0005\"} 0\n"));
+        assert!(out.contains("latency_bucket{route=\"home\",le=\"0.001\"} 1\n"));
+        assert!(out.contains("latency_bucket{route=\"home\",le=\"2.5\"} 1\n"));
+        assert!(out.contains("latency_bucket{route=\"home\",le=\"+Inf\"} 2\n"));
+        assert!(out.contains("latency_count{route=\"home\"} 2\n"));
+    }
+
+    #[test]
+    fn labels_are_escaped() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    #[test]
+    fn only_the_exact_token_is_accepted() {
+        assert!(token_matches("s3cret", Some("s3cret")));
+        assert!(!token_matches("s3cret", Some("s3cre")));
+        assert!(!token_matches("s3cret", Some("s3cret ")));
+        assert!(!token_matches("s3cret", None));
+        assert!(!token_matches("", Some("")));
+    }
+
+    #[test]
+    fn totals_saturate_instead_of_overflowing() {
+        let counters = PersistentCounterMap::in_memory();
+        counters.create_badge("about", Some(u64::MAX));
+        counters.create_badge("home", Some(u64::MAX));
+        counters.create_badge("blog", Some(u64::MAX));
+        let out = Metrics::new(1).render(&counters);
+
+        assert!(out.contains(&format!("visit_counter_visits_total {}\n", u64::MAX)));
+        assert!(out.contains(&format!("visit_counter_counter_value{{counter=\"__other__\"}} {}\n", u64::MAX)));
+    }
+}
@@ -1,126 +1,192 @@
 // backend_visit_counter/src/models.rs
 use rocket::serde::{Deserialize, Serialize};
-use rocket::form::FromForm;
 use rocket::{Request, Response};
+use rocket::http::{ContentType, Header};
+use rocket::futures::stream::BoxStream;
+use rocket::response::stream::TextStream;
 use rocket::response::{Responder, Result as RocketResult};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::BTreeMap;
 
-/// JSON response structure for counter endpoints.
-#[derive(Serialize)]
+/// The bodies of the JSON API and the badge parameters, shared with the frontend.
+pub use visit_counter_types::*;
+
+pub struct SvgResponse(
+    pub Response<'static>
+);
+
+impl<'r> Responder<'r, 'static> for SvgResponse {
+    fn respond_to(self, _req: &'r Request<'_>) -> RocketResult<'static> {
+        Ok(self.0)
+    }
+}
+
+/// Badge data structure for admin management
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(crate = "rocket::serde")]
-pub struct CounterResponse {
+pub struct Badge {
     pub name: String,
     pub count: u64,
+    pub created_at: DateTime<Utc>,
+    pub last_accessed: DateTime<Utc>,
+    /// Visits per day (UTC)
+    #[serde(default)]
+    pub history: BTreeMap<NaiveDate, u64>,
+    /// When set, only signed badge URLs count visits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing: Option<SigningKeys>,
+    #[serde(flatten)]
+    pub metadata: BadgeMetadata,
+    /// Visits per day, week, month or year, for counters that start over
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub period: Option<PeriodCounter>,
 }
 
-/// JSON request structure for setting a counter.
-#[derive(Deserialize)]
+/// Visits of the active window of a periodic counter, and the totals of the earlier ones
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 #[serde(crate = "rocket::serde")]
-pub struct CounterSetRequest {
-    pub count: u64,
+pub struct PeriodCounter {
+    pub period: Period,
+    /// IANA time zone the windows follow, e.g. `Europe/Berlin`
+    pub timezone: String,
+    /// First day of the active window, in the time zone
+    pub start: NaiveDate,
+    /// Visits since `start`
+    pub current: u64,
+    /// Totals of earlier windows, by their first day
+    #[serde(default)]
+    pub archive: BTreeMap<NaiveDate, u64>,
 }
 
-/// Query parameters for the SVG endpoint. Derives `FromForm` so Rocket can parse query parameters into this struct.
-#[derive(FromForm, Clone, Default)]
-pub struct SvgOptions {
-    pub label: Option<String>,
-    pub style: Option<String>,
-    // SVG Dimensions
-    pub width: Option<u32>,
-    pub height: Option<u32>,
-    pub label_width: Option<u32>,
-    pub counter_width: Option<u32>,
-    pub radius: Option<u32>,
-    // Gradient Settings
-    pub grad_stop1_color: Option<String>,
-    pub grad_stop1_opacity: Option<f32>,
-    pub grad_stop2_opacity: Option<f32>,
-    // Text Settings
-    pub font_family: Option<String>,
-    pub font_size: Option<u32>,
-    pub label_offset_x: Option<u32>,
-    pub label_offset_y: Option<u32>,
-    pub counter_offset_x: Option<u32>,
-    pub counter_offset_y: Option<u32>,
-    pub shadow_fill: Option<String>,
-    pub shadow_opacity: Option<f32>,
-    // Color Settings
-    pub background_label: Option<String>,
-    pub background_counter: Option<String>,
-    pub label_color: Option<String>,
-    pub counter_color: Option<String>,
-    // Extended customization options
-    pub font_weight: Option<String>,
-    pub text_color: Option<String>,
-    pub background_color: Option<String>,
-    pub border_width: Option<u32>,
-    pub border_color: Option<String>,
-    pub border_radius: Option<u32>,
-    pub logo_url: Option<String>,
-    pub logo_width: Option<u32>,
-    pub element_positions: Option<String>, // "label,logo,counter" or similar format
+/// Secrets signing the badge URLs of a counter
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct SigningKeys {
+    pub secret: String,
+    /// Secret replaced by the last rotation, still accepted until `previous_expires_at`
+    pub previous: Option<String>,
+    pub previous_expires_at: Option<DateTime<Utc>>,
 }
 
-pub struct SvgResponse(
-    pub Response<'static>
-);
-
-impl<'r> Responder<'r, 'static> for SvgResponse {
-    fn respond_to(self, _req: &'r Request<'_>) -> RocketResult<'static> {
-        Ok(self.0)
+impl SigningKeys {
+    /// Secrets a signature may be checked against at `now`.
+    pub fn valid_secrets(&self, now: DateTime<Utc>) -> impl Iterator<Item = &str> {
+        let previous = self.previous.as_deref()
+            .filter(|_| self.previous_expires_at.is_some_and(|expires_at| now < expires_at));
+        std::iter::once(self.secret.as_str()).chain(previous)
     }
 }
 
-/// Verify that the "x-api-key" header matches the API_KEY environment variable.
-pub struct ApiKey(String);
-
-#[rocket::async_trait]
-impl<'r> rocket::request::FromRequest<'r> for ApiKey {
-    type Error = ();
+impl Badge {
+    pub fn new(name: &str, count: u64, now: DateTime<Utc>) -> Self {
+        Badge {
+            name: name.to_string(),
+            count,
+            created_at: now,
+            last_accessed: now,
+            history: BTreeMap::new(),
+            signing: None,
+            metadata: BadgeMetadata::default(),
+            period: None,
+        }
+    }
 
-    async fn from_request(req: &'r rocket::request::Request<'_>) -> rocket::request::Outcome<Self, Self::Error> {
-        let api_key = req.headers().get_one("x-api-key");
-        if let Some(api_key) = api_key {
-            if api_key == std::env::var("API_KEY").expect("API_KEY must be set") {
-                return rocket::request::Outcome::Success(ApiKey(api_key.to_string()));
-            }
+    /// Adds the count and history of `other` to this badge, keeping the earliest creation date.
+    /// Counts saturate at `u64::MAX` instead of overflowing.
+    pub fn absorb(&mut self, other: Badge) {
+        self.count = self.count.saturating_add(other.count);
+        self.created_at = self.created_at.min(other.created_at);
+        self.last_accessed = self.last_accessed.max(other.last_accessed);
+        for (day, visits) in other.history {
+            let entry = self.history.entry(day).or_insert(0);
+            *entry = entry.saturating_add(visits);
         }
-        rocket::request::Outcome::Error((rocket::http::Status::Unauthorized, ()))
     }
 }
 
-/// Badge data structure for admin management
+/// A counter with its metadata, as exported and imported
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(crate = "rocket::serde")]
-pub struct Badge {
+pub struct ExportedCounter {
     pub name: String,
     pub count: u64,
-    pub created_at: DateTime<Utc>,
-    pub last_accessed: DateTime<Utc>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_accessed: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub history: BTreeMap<NaiveDate, u64>,
+    #[serde(flatten)]
+    pub metadata: BadgeMetadata,
 }
 
-/// Request to create/update a badge
-#[derive(Deserialize)]
+/// JSON export of every counter of a namespace
+#[derive(Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
-pub struct BadgeCreateRequest {
-    pub name: String,
-    pub count: Option<u64>,
+pub struct ExportDocument {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub counters: Vec<ExportedCounter>,
+}
+
+/// An export, downloaded as a file and streamed in chunks
+pub struct ExportResponse {
+    pub body: TextStream<BoxStream<'static, String>>,
+    pub content_type: ContentType,
+    pub disposition: Header<'static>,
+}
+
+impl<'r> Responder<'r, 'r> for ExportResponse {
+    fn respond_to(self, req: &'r Request<'_>) -> RocketResult<'r> {
+        Response::build_from(self.body.respond_to(req)?)
+            .header(self.content_type)
+            .header(self.disposition)
+            .ok()
+    }
 }
 
-/// Response for badge operations
-#[derive(Serialize)]
+/// A deleted badge kept in the trash until it is restored or purged
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct TrashedBadge {
+    pub badge: Badge,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// API key as stored on disk. Only the SHA-256 hash of the key is kept.
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(crate = "rocket::serde")]
-pub struct BadgeResponse {
+pub struct ApiKeyRecord {
+    pub id: String,
     pub name: String,
-    pub count: u64,
+    pub hash: String,
+    pub scopes: Vec<ApiKeyScope>,
+    /// Counter-name prefixes the key is restricted to. Empty means every counter.
+    pub prefixes: Vec<String>,
+    /// Namespace whose counters the key applies to
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+    /// Set on `admin` keys of the default namespace without prefixes created by an owner of every namespace:
+    /// such keys may read `/metrics`, which covers every namespace.
+    #[serde(default)]
+    pub global: bool,
     pub created_at: DateTime<Utc>,
-    pub last_accessed: DateTime<Utc>,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+/// Keys created before namespaces existed belong to the default one.
+fn default_namespace() -> String {
+    crate::namespaces::DEFAULT_NAMESPACE.to_string()
 }
 
-/// List all badges response
-#[derive(Serialize)]
+/// Admin user as stored on disk
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(crate = "rocket::serde")]
-pub struct BadgeListResponse {
-    pub badges: Vec<BadgeResponse>,
-    pub total: usize,
-}
\ No newline at end of file
+pub struct UserRecord {
+    pub username: String,
+    /// Argon2 PHC string
+    pub password_hash: String,
+    pub role: Role,
+    /// Namespace the user administers. `None` gives access to every namespace.
+    #[serde(default)]
+    pub namespace: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
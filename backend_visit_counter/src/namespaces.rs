@@ -0,0 +1,228 @@
+// backend_visit_counter/src/namespaces.rs
+use std::ops::Deref;
+
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::Request;
+
+use crate::auth::AuthGuard;
+use crate::counter_name::{self, CounterName, NameError};
+use crate::models::{AliasResponse, AuditAction, AuditEntry, BadgeResponse, BulkItemResult, TrashedBadgeResponse};
+use crate::persistent_counter::PersistentCounterMap;
+use crate::settings::Settings;
+
+/// Namespace of the un-namespaced routes, and of counters created before namespaces existed.
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// Storage key of a counter: the bare name in the default namespace, `<namespace>/<name>` elsewhere.
+/// Counter names cannot contain `/`, so keys of different namespaces never collide.
+pub fn counter_key(namespace: &str, name: &str) -> String {
+    if namespace == DEFAULT_NAMESPACE {
+        name.to_string()
+    } else {
+        format!("{}/{}", namespace, name)
+    }
+}
+
+/// Namespace of a storage key.
+pub fn namespace_of(key: &str) -> &str {
+    key.split_once('/').map_or(DEFAULT_NAMESPACE, |(namespace, _)| namespace)
+}
+
+/// Name of a storage key within its namespace.
+pub fn local_name(key: &str) -> &str {
+    key.split_once('/').map_or(key, |(_, name)| name)
+}
+
+/// The namespace an admin request acts on: the `ns` query parameter, or the user's own namespace,
+/// or the default one. Users restricted to a namespace get 403 Forbidden for any other,
+/// and namespaces missing from the configuration get 404 Not Found.
+pub struct Scope {
+    namespace: String,
+}
+
+impl Scope {
+    /// Scope of `namespace`, which must be configured. Requests get theirs from the guard.
+    pub fn new(namespace: &str) -> Self {
+        Scope {
+            namespace: namespace.to_string(),
+        }
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Storage key of the counter `name` in this namespace.
+    /// Names containing a `/` (e.g. `%2F` in a path) always get the prefix, so they stay in this namespace.
+    pub fn key(&self, name: &str) -> String {
+        if name.contains('/') {
+            format!("{}/{}", self.namespace, name)
+        } else {
+            counter_key(&self.namespace, name)
+        }
+    }
+
+    /// Whether a storage key belongs to this namespace.
+    pub fn contains(&self, key: &str) -> bool {
+        namespace_of(key) == self.namespace
+    }
+
+    /// Storage key of the counter `name` in this namespace. Names must follow the name policy, except those
+    /// of stored counters, aliases and trashed badges, so counters created before the policy can be renamed.
+    pub fn checked_key(&self, name: &str, settings: &Settings, counters: &PersistentCounterMap) -> Result<String, NameError> {
+        let key = self.key(name);
+        if counters.name_in_use(&key) || counters.in_trash(&key) {
+            return Ok(key);
+        }
+        Ok(self.key(&CounterName::parse(name, &settings.names)?))
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Scope {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let auth = match req.guard::<AuthGuard>().await {
+            Outcome::Success(auth) => auth,
+            Outcome::Error(e) => return Outcome::Error(e),
+            Outcome::Forward(status) => return Outcome::Forward(status),
+        };
+        let requested = req.query_value::<&str>("ns").and_then(Result::ok);
+
+        let namespace = match (auth.namespace(), requested) {
+            (Some(own), Some(requested)) if own != requested => return Outcome::Error((Status::Forbidden, ())),
+            (Some(own), _) => own.to_string(),
+            (None, Some(requested)) => requested.to_string(),
+            (None, None) => DEFAULT_NAMESPACE.to_string(),
+        };
+
+        let known = req.rocket().state::<Settings>().is_some_and(|settings| settings.namespace(&namespace).is_some());
+        if !known {
+            return Outcome::Error((Status::NotFound, ()));
+        }
+        Outcome::Success(Scope { namespace })
+    }
+}
+
+/// Storage key of the counter an admin route names with its `<_>` segment, checked like [`Scope::checked_key`].
+pub struct ScopedKey {
+    scope: Scope,
+    key: String,
+}
+
+impl ScopedKey {
+    /// Scope of the request, for names it gives in its body.
+    pub fn scope(&self) -> &Scope {
+        &self.scope
+    }
+}
+
+impl Deref for ScopedKey {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.key
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ScopedKey {
+    type Error = Option<NameError>;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let scope = match req.guard::<Scope>().await {
+            Outcome::Success(scope) => scope,
+            Outcome::Error((status, ())) => return Outcome::Error((status, None)),
+            Outcome::Forward(status) => return Outcome::Forward(status),
+        };
+        let (Some(name), Some(settings), Some(counters)) = (
+            counter_name::name_segment(req),
+            req.rocket().state::<Settings>(),
+            req.rocket().state::<PersistentCounterMap>(),
+        ) else {
+            return Outcome::Forward(Status::InternalServerError);
+        };
+
+        match scope.checked_key(name, settings, counters) {
+            Ok(key) => Outcome::Success(ScopedKey { scope, key }),
+            Err(error) => Outcome::Error((counter_name::reject(req, error.clone()), Some(error))),
+        }
+    }
+}
+
+/// Admin responses name counters within the namespace of the request, without the `<namespace>/` prefix.
+pub trait Localize {
+    fn localized(self) -> Self;
+}
+
+impl Localize for BadgeResponse {
+    fn localized(mut self) -> Self {
+        self.name = local_name(&self.name).to_string();
+        for alias in &mut self.aliases {
+            *alias = local_name(alias).to_string();
+        }
+        self
+    }
+}
+
+impl Localize for AliasResponse {
+    fn localized(mut self) -> Self {
+        self.alias = local_name(&self.alias).to_string();
+        self.target = local_name(&self.target).to_string();
+        self
+    }
+}
+
+impl Localize for TrashedBadgeResponse {
+    fn localized(mut self) -> Self {
+        self.name = local_name(&self.name).to_string();
+        self
+    }
+}
+
+impl Localize for BulkItemResult {
+    fn localized(mut self) -> Self {
+        self.name = local_name(&self.name).to_string();
+        self
+    }
+}
+
+impl Localize for AuditEntry {
+    fn localized(mut self) -> Self {
+        self.counter = local_name(&self.counter).to_string();
+        // Other targets are tags, namespaces or periods, not counters
+        if matches!(self.action, AuditAction::Rename | AuditAction::Merge | AuditAction::Alias | AuditAction::Unalias) {
+            self.target = self.target.map(|target| local_name(&target).to_string());
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_round_trip_through_their_namespace() {
+        for (namespace, name, key) in [("default", "home", "home"), ("docs", "home", "docs/home")] {
+            assert_eq!(counter_key(namespace, name), key);
+            assert_eq!(namespace_of(key), namespace);
+            assert_eq!(local_name(key), name);
+        }
+    }
+
+    #[test]
+    fn names_with_a_slash_stay_in_the_scope() {
+        let default = Scope::new(DEFAULT_NAMESPACE);
+        assert_eq!(default.key("docs/home"), "default/docs/home");
+        assert!(default.contains(&default.key("docs/home")));
+        assert!(!default.contains("docs/home"));
+
+        let docs = Scope::new("docs");
+        assert_eq!(docs.key("home"), "docs/home");
+        assert!(docs.contains("docs/home"));
+        assert!(!docs.contains("home"));
+    }
+}
@@ -0,0 +1,198 @@
+// backend_visit_counter/src/periods.rs
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, Utc};
+use chrono_tz::Tz;
+
+use crate::models::{Period, PeriodCounter, PeriodResponse, PeriodView};
+
+/// Earlier windows kept per counter, a little over a year of days.
+pub const MAX_ARCHIVED_PERIODS: usize = 400;
+
+/// Parses an IANA time zone name such as `Europe/Berlin`.
+pub fn parse_timezone(name: &str) -> Option<Tz> {
+    name.parse().ok()
+}
+
+/// First day of the window of `period` containing `now`, in the time zone `tz`.
+pub fn window_start(period: Period, tz: Tz, now: DateTime<Utc>) -> NaiveDate {
+    let today = now.with_timezone(&tz).date_naive();
+    match period {
+        Period::None | Period::Daily => today,
+        Period::Weekly => today - Days::new(today.weekday().num_days_from_monday() as u64),
+        Period::Monthly => today.with_day(1).unwrap_or(today),
+        Period::Yearly => today.with_ordinal(1).unwrap_or(today),
+    }
+}
+
+/// First day of the window before the one starting on `start`.
+fn previous_start(period: Period, start: NaiveDate) -> NaiveDate {
+    match period {
+        Period::None | Period::Daily => start - Days::new(1),
+        Period::Weekly => start - Days::new(7),
+        Period::Monthly => start - Months::new(1),
+        Period::Yearly => start - Months::new(12),
+    }
+}
+
+impl PeriodCounter {
+    /// Counter of the window containing `now`. Its count starts from the visits of the days of `history`
+    /// (UTC days) since the window started, so enabling a period mid-month shows the month so far.
+    pub fn new(period: Period, timezone: &str, now: DateTime<Utc>, history: &BTreeMap<NaiveDate, u64>) -> Self {
+        let start = window_start(period, parse_timezone(timezone).unwrap_or(Tz::UTC), now);
+        PeriodCounter {
+            period,
+            timezone: timezone.to_string(),
+            start,
+            current: history.range(start..).map(|(_, visits)| *visits).fold(0u64, u64::saturating_add),
+            archive: BTreeMap::new(),
+        }
+    }
+
+    /// Archives the active window if `now` is past it, and starts the window containing `now`.
+    /// A clock going backwards never reopens an archived window.
+    pub fn roll(&mut self, now: DateTime<Utc>) {
+        let tz = parse_timezone(&self.timezone).unwrap_or(Tz::UTC);
+        let start = window_start(self.period, tz, now);
+        if start <= self.start {
+            return;
+        }
+
+        self.archive.insert(self.start, self.current);
+        while self.archive.len() > MAX_ARCHIVED_PERIODS {
+            self.archive.pop_first();
+        }
+        self.start = start;
+        self.current = 0;
+    }
+
+    /// Counts a visit at `now`.
+    pub fn record(&mut self, now: DateTime<Utc>) {
+        self.roll(now);
+        self.current = self.current.saturating_add(1);
+    }
+
+    /// Starts the window containing `now` without visits and drops the archive.
+    pub fn clear(&mut self, now: DateTime<Utc>) {
+        let tz = parse_timezone(&self.timezone).unwrap_or(Tz::UTC);
+        self.start = window_start(self.period, tz, now);
+        self.current = 0;
+        self.archive.clear();
+    }
+
+    /// Visits of the window before the active one; windows without visits count zero.
+    pub fn previous(&self) -> u64 {
+        self.archive.get(&previous_start(self.period, self.start)).copied().unwrap_or(0)
+    }
+
+    /// This counter as it reads at `now`, without changing the stored one.
+    pub fn rolled(&self, now: DateTime<Utc>) -> PeriodCounter {
+        let mut counter = self.clone();
+        counter.roll(now);
+        counter
+    }
+
+    /// The value `view` shows at `now`; `all` is the all-time `count` of the badge.
+    pub fn value(&self, view: PeriodView, count: u64, now: DateTime<Utc>) -> u64 {
+        let counter = self.rolled(now);
+        match view {
+            PeriodView::Current => counter.current,
+            PeriodView::Previous => counter.previous(),
+            PeriodView::All => count,
+        }
+    }
+
+    pub fn to_response(&self, now: DateTime<Utc>) -> PeriodResponse {
+        let counter = self.rolled(now);
+        PeriodResponse {
+            period: counter.period,
+            previous: counter.previous(),
+            timezone: counter.timezone,
+            start: counter.start,
+            current: counter.current,
+            archive: counter.archive,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc)
+    }
+
+    fn date(date: &str) -> NaiveDate {
+        date.parse().unwrap()
+    }
+
+    #[test]
+    fn windows_start_on_the_first_day_in_the_time_zone() {
+        let now = at("2024-03-31T23:30:00Z");
+        assert_eq!(window_start(Period::Daily, Tz::UTC, now), date("2024-03-31"));
+        assert_eq!(window_start(Period::Daily, Tz::Europe__Berlin, now), date("2024-04-01"));
+        assert_eq!(window_start(Period::Weekly, Tz::UTC, now), date("2024-03-25"));
+        assert_eq!(window_start(Period::Monthly, Tz::UTC, now), date("2024-03-01"));
+        assert_eq!(window_start(Period::Monthly, Tz::Europe__Berlin, now), date("2024-04-01"));
+        assert_eq!(window_start(Period::Yearly, Tz::America__New_York, at("2025-01-01T03:00:00Z")), date("2024-01-01"));
+    }
+
+    #[test]
+    fn rollover_archives_the_active_window() {
+        let mut counter = PeriodCounter::new(Period::Monthly, "UTC", at("2024-01-10T12:00:00Z"), &BTreeMap::new());
+        counter.record(at("2024-01-10T12:00:00Z"));
+        counter.record(at("2024-01-31T23:59:59Z"));
+        counter.record(at("2024-02-01T00:00:00Z"));
+
+        assert_eq!(counter.start, date("2024-02-01"));
+        assert_eq!(counter.current, 1);
+        assert_eq!(counter.previous(), 2);
+        assert_eq!(counter.archive, BTreeMap::from([(date("2024-01-01"), 2)]));
+    }
+
+    #[test]
+    fn previous_is_zero_after_a_window_without_visits() {
+        let mut counter = PeriodCounter::new(Period::Daily, "UTC", at("2024-05-01T08:00:00Z"), &BTreeMap::new());
+        counter.record(at("2024-05-01T08:00:00Z"));
+
+        let now = at("2024-05-03T08:00:00Z");
+        assert_eq!(counter.value(PeriodView::Current, 1, now), 0);
+        assert_eq!(counter.value(PeriodView::Previous, 1, now), 0);
+        assert_eq!(counter.value(PeriodView::All, 1, now), 1);
+        assert_eq!(counter.value(PeriodView::Previous, 1, at("2024-05-02T08:00:00Z")), 1);
+        // Reading does not roll the stored counter
+        assert_eq!(counter.start, date("2024-05-01"));
+    }
+
+    #[test]
+    fn clock_going_backwards_keeps_the_active_window() {
+        let mut counter = PeriodCounter::new(Period::Weekly, "UTC", at("2024-05-08T08:00:00Z"), &BTreeMap::new());
+        counter.record(at("2024-05-08T08:00:00Z"));
+        counter.record(at("2024-05-01T08:00:00Z"));
+
+        assert_eq!(counter.start, date("2024-05-06"));
+        assert_eq!(counter.current, 2);
+        assert!(counter.archive.is_empty());
+    }
+
+    #[test]
+    fn new_counters_start_from_the_history_of_the_window() {
+        let history = BTreeMap::from([(date("2024-04-30"), 5), (date("2024-05-01"), 2), (date("2024-05-02"), 3)]);
+        let counter = PeriodCounter::new(Period::Monthly, "UTC", at("2024-05-02T10:00:00Z"), &history);
+        assert_eq!(counter.current, 5);
+    }
+
+    #[test]
+    fn archive_keeps_the_latest_windows() {
+        let mut counter = PeriodCounter::new(Period::Daily, "UTC", at("2020-01-01T00:00:00Z"), &BTreeMap::new());
+        let mut now = at("2020-01-01T00:00:00Z");
+        for _ in 0..MAX_ARCHIVED_PERIODS + 10 {
+            counter.record(now);
+            now += chrono::Duration::days(1);
+        }
+
+        assert_eq!(counter.archive.len(), MAX_ARCHIVED_PERIODS);
+        assert_eq!(counter.archive.keys().next(), Some(&date("2020-01-10")));
+    }
+}
@@ -1,19 +1,114 @@
 // backend_visit_counter/src/persistent_counter.rs
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Mutex;
 use std::path::Path;
-use chrono::Utc;
-use crate::models::{Badge, BadgeResponse};
+use std::time::Instant;
+use chrono::{DateTime, Duration, Utc};
+use rocket::http::Status;
+use crate::clock::{self, SharedClock};
+use crate::metrics::Histogram;
+use crate::namespaces;
+use crate::settings::{CreationSettings, Settings, StorageBackend};
+use crate::models::{AliasResponse, Badge, BadgeMetadata, BadgeResponse, BadgeSort, Period, PeriodCounter, PeriodView, SortOrder, BulkItemResult, BulkOperation, ExportedCounter, ImportChange, ImportOutcome, ImportStrategy, SigningKeys, SigningSecretResponse, TrashedBadge, TrashedBadgeResponse};
+use crate::signing;
+
+/// Why a rename or merge was refused.
+#[derive(Debug, Clone, Copy)]
+pub enum CounterError {
+    NotFound,
+    /// The new name is already used by a counter or an alias.
+    NameTaken,
+    Invalid,
+    /// The counter does not exist and the creation policy does not allow creating it.
+    Unknown,
+    /// The counter does not exist and `creation.max_counters` or the quota of its namespace has been reached.
+    LimitReached,
+}
+
+impl From<CounterError> for Status {
+    fn from(error: CounterError) -> Self {
+        match error {
+            CounterError::NotFound | CounterError::Unknown => Status::NotFound,
+            CounterError::LimitReached => Status::Forbidden,
+            CounterError::NameTaken => Status::Conflict,
+            CounterError::Invalid => Status::BadRequest,
+        }
+    }
+}
+
+/// Filters of the admin badge list. `None` matches everything.
+#[derive(Default)]
+pub struct BadgeFilter {
+    pub namespace: Option<String>,
+    /// Case-insensitive part of the name or description
+    pub query: Option<String>,
+    pub min_count: Option<u64>,
+    /// Only badges last accessed before this time
+    pub inactive_since: Option<DateTime<Utc>>,
+    pub tag: Option<String>,
+    /// Owner, ignoring case
+    pub owner: Option<String>,
+}
+
+impl BadgeFilter {
+    fn matches(&self, badge: &Badge) -> bool {
+        self.namespace.as_deref().is_none_or(|namespace| namespaces::namespace_of(&badge.name) == namespace)
+            && self.query.as_deref().is_none_or(|query| {
+                let query = query.to_lowercase();
+                namespaces::local_name(&badge.name).to_lowercase().contains(&query)
+                    || badge.metadata.description.as_ref().is_some_and(|description| description.to_lowercase().contains(&query))
+            })
+            && self.min_count.is_none_or(|min_count| badge.count >= min_count)
+            && self.inactive_since.is_none_or(|since| badge.last_accessed < since)
+            && self.tag.as_ref().is_none_or(|tag| badge.metadata.tags.contains(tag))
+            && self.owner.as_ref().is_none_or(|owner| {
+                badge.metadata.owner.as_ref().is_some_and(|badge_owner| badge_owner.eq_ignore_ascii_case(owner))
+            })
+    }
+}
+
+/// One of the JSON files of the storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum StoreFile {
+    Data,
+    Badges,
+    Trash,
+    Aliases,
+}
+
+/// Outcome of the writes made to the JSON files.
+#[derive(Debug, Clone, Default)]
+pub struct FlushStats {
+    pub durations: Histogram,
+    pub failures: u64,
+    pub last_flush_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
 
 /// A simple file‐based persistent storage for counters.
 /// It loads data from a JSON file on initialization and writes changes
-/// back to that file.
+/// back to that file, unless it was created with `in_memory`.
 #[derive(Debug)]
 pub struct PersistentCounterMap {
     data: Mutex<HashMap<String, u64>>,
     badges: Mutex<HashMap<String, Badge>>,
+    trash: Mutex<HashMap<String, TrashedBadge>>,
+    /// Old names of renamed or merged counters, mapped to the counter they now count into.
+    aliases: Mutex<HashMap<String, String>>,
     path: String,
     badges_path: String,
+    trash_path: String,
+    aliases_path: String,
+    /// How long deleted badges stay in the trash. `None` keeps them until purged by hand.
+    trash_retention: Option<Duration>,
+    creation: CreationSettings,
+    /// Maximum number of counters per namespace. Namespaces without an entry are unlimited.
+    namespace_quotas: HashMap<String, usize>,
+    persist: bool,
+    /// Files changed since they were last written, including the ones whose write failed.
+    dirty: Mutex<HashSet<StoreFile>>,
+    flush_stats: Mutex<FlushStats>,
+    clock: SharedClock,
 }
 
 impl PersistentCounterMap {
@@ -36,56 +131,184 @@ impl PersistentCounterMap {
             HashMap::new()
         };
 
+        let trash_path = path.replace(".json", "_trash.json");
+        let trash = if Path::new(&trash_path).exists() {
+            let content = std::fs::read_to_string(&trash_path).unwrap_or_else(|_| "{}".to_string());
+            serde_json::from_str(&content).unwrap_or_default()
+        } else {
+            HashMap::new()
+        };
+
+        let aliases_path = path.replace(".json", "_aliases.json");
+        let aliases = if Path::new(&aliases_path).exists() {
+            let content = std::fs::read_to_string(&aliases_path).unwrap_or_else(|_| "{}".to_string());
+            serde_json::from_str(&content).unwrap_or_default()
+        } else {
+            HashMap::new()
+        };
+
         PersistentCounterMap {
             data: Mutex::new(data),
             badges: Mutex::new(badges),
+            trash: Mutex::new(trash),
+            aliases: Mutex::new(aliases),
             path: path.to_string(),
             badges_path,
+            trash_path,
+            aliases_path,
+            trash_retention: None,
+            creation: CreationSettings::default(),
+            namespace_quotas: HashMap::new(),
+            persist: true,
+            dirty: Mutex::new(HashSet::new()),
+            flush_stats: Mutex::new(FlushStats::default()),
+            clock: clock::system(),
         }
     }
 
+    /// Creates an empty PersistentCounterMap that never touches the filesystem.
+    pub fn in_memory() -> Self {
+        PersistentCounterMap {
+            data: Mutex::new(HashMap::new()),
+            badges: Mutex::new(HashMap::new()),
+            trash: Mutex::new(HashMap::new()),
+            aliases: Mutex::new(HashMap::new()),
+            path: String::new(),
+            badges_path: String::new(),
+            trash_path: String::new(),
+            aliases_path: String::new(),
+            trash_retention: None,
+            creation: CreationSettings::default(),
+            namespace_quotas: HashMap::new(),
+            persist: false,
+            dirty: Mutex::new(HashSet::new()),
+            flush_stats: Mutex::new(FlushStats::default()),
+            clock: clock::system(),
+        }
+    }
+
+    /// Opens the storage configured in `settings`, with its trash retention, creation policy and namespace quotas.
+    pub fn from_settings(settings: &Settings, clock: SharedClock) -> Self {
+        match settings.storage {
+            StorageBackend::Json => PersistentCounterMap::new(&settings.data_path.to_string_lossy()),
+            StorageBackend::Memory => PersistentCounterMap::in_memory(),
+        }
+        .with_clock(clock)
+        .with_trash_retention(settings.trash_retention_days)
+        .with_creation(settings.creation.clone())
+        .with_namespace_quotas(settings.namespaces.iter()
+            .map(|(name, namespace)| (name.clone(), namespace.max_counters))
+            .collect())
+    }
+
+    /// Reads the time from `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The time of the storage clock.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// Purges deleted badges automatically once they have been in the trash for `days` days.
+    /// `0` keeps them until purged by hand.
+    pub fn with_trash_retention(mut self, days: u32) -> Self {
+        self.trash_retention = (days > 0).then(|| Duration::days(days as i64));
+        self.purge_expired_trash();
+        self
+    }
+
+    /// Restricts which counters `increment` may create on their first hit.
+    pub fn with_creation(mut self, creation: CreationSettings) -> Self {
+        self.creation = creation;
+        self
+    }
+
+    /// Limits how many counters each namespace may hold. `0` means no limit.
+    pub fn with_namespace_quotas(mut self, quotas: HashMap<String, usize>) -> Self {
+        self.namespace_quotas = quotas.into_iter().filter(|(_, max)| *max > 0).collect();
+        self
+    }
+
+    /// Number of counters in a namespace.
+    pub fn namespace_len(&self, namespace: &str) -> usize {
+        let data = self.data.lock().unwrap();
+        data.keys().filter(|key| namespaces::namespace_of(key) == namespace).count()
+    }
+
+    /// Whether the namespace of `key` may hold one more counter.
+    pub fn namespace_has_room(&self, key: &str) -> bool {
+        let data = self.data.lock().unwrap();
+        self.namespace_room(&data, key)
+    }
+
+    fn namespace_room(&self, data: &HashMap<String, u64>, key: &str) -> bool {
+        let namespace = namespaces::namespace_of(key);
+        self.namespace_quotas.get(namespace).is_none_or(|max| {
+            data.keys().filter(|key| namespaces::namespace_of(key) == namespace).count() < *max
+        })
+    }
+
+    /// Returns the counter an alias points to, or `name` itself.
+    pub fn resolve(&self, name: &str) -> String {
+        let aliases = self.aliases.lock().unwrap();
+        aliases.get(name).cloned().unwrap_or_else(|| name.to_string())
+    }
+
     /// Gets the counter value for a given key.
     pub fn get(&self, key: &str) -> u64 {
+        let key = self.resolve(key);
         let data = self.data.lock().unwrap();
-        *data.get(key).unwrap_or(&0)
+        *data.get(&key).unwrap_or(&0)
     }
 
     /// Increments the counter for a given key, saves the change, and returns the new value.
-    pub fn increment(&self, key: &str) -> u64 {
-        let now = Utc::now();
+    pub fn increment(&self, key: &str) -> Result<u64, CounterError> {
+        let key = &self.resolve(key);
+        let now = self.clock.now();
 
         // Update the counter and capture the new value.
         let new_count = {
             let mut data = self.data.lock().unwrap();
+            if !data.contains_key(key) {
+                if !self.creation.permits(key) {
+                    return Err(CounterError::Unknown);
+                }
+                if !self.creation.has_room(data.len()) || !self.namespace_room(&data, key) {
+                    return Err(CounterError::LimitReached);
+                }
+            }
             let count = data.entry(key.to_string()).or_insert(0);
-            *count += 1;
+            *count = count.saturating_add(1);
             *count
         };
 
         // Update badge metadata
         {
             let mut badges = self.badges.lock().unwrap();
-            badges.entry(key.to_string()).and_modify(|badge| {
-                badge.count = new_count;
-                badge.last_accessed = now;
-            }).or_insert_with(|| Badge {
-                name: key.to_string(),
-                count: new_count,
-                created_at: now,
-                last_accessed: now,
-            });
+            let badge = badges.entry(key.to_string()).or_insert_with(|| Badge::new(key, 0, now));
+            badge.count = new_count;
+            badge.last_accessed = now;
+            let visits = badge.history.entry(now.date_naive()).or_insert(0);
+            *visits = visits.saturating_add(1);
+            if let Some(period) = &mut badge.period {
+                period.record(now);
+            }
         }
 
         // Save both files
-        self.save_data();
-        self.save_badges();
+        self.flush(&[StoreFile::Data, StoreFile::Badges]);
 
-        new_count
+        Ok(new_count)
     }
     
     /// Sets the counter for a given key to the specified value and saves the change.
+    /// Its period windows start over, as the visits they counted no longer add up to the new value.
     pub fn set(&self, key: &str, value: u64) {
-        let now = Utc::now();
+        let key = &self.resolve(key);
+        let now = self.clock.now();
 
         {
             let mut data = self.data.lock().unwrap();
@@ -98,105 +321,1194 @@ impl PersistentCounterMap {
             badges.entry(key.to_string()).and_modify(|badge| {
                 badge.count = value;
                 badge.last_accessed = now;
-            }).or_insert_with(|| Badge {
-                name: key.to_string(),
-                count: value,
-                created_at: now,
-                last_accessed: now,
-            });
+                if let Some(period) = &mut badge.period {
+                    period.clear(now);
+                }
+            }).or_insert_with(|| Badge::new(key, value, now));
         }
 
-        self.save_data();
-        self.save_badges();
+        self.flush(&[StoreFile::Data, StoreFile::Badges]);
     }
 
-    /// Helper method to save counter data
-    fn save_data(&self) {
-        let data = self.data.lock().unwrap();
-        if let Ok(content) = serde_json::to_string_pretty(&*data) {
-            let _ = std::fs::write(&self.path, content);
+    /// The value `view` of a counter or of the counter an alias points to.
+    /// Counters without a period always show every visit.
+    pub fn get_view(&self, key: &str, view: PeriodView) -> u64 {
+        let key = self.resolve(key);
+        let count = self.data.lock().unwrap().get(&key).copied().unwrap_or(0);
+        let badges = self.badges.lock().unwrap();
+        match badges.get(&key).and_then(|badge| badge.period.as_ref()) {
+            Some(period) => period.value(view, count, self.clock.now()),
+            None => count,
         }
     }
 
-    /// Helper method to save badge metadata
-    fn save_badges(&self) {
-        let badges = self.badges.lock().unwrap();
-        if let Ok(content) = serde_json::to_string_pretty(&*badges) {
-            let _ = std::fs::write(&self.badges_path, content);
+    /// Saves the files of `changed`, and the ones a previous flush failed to save,
+    /// and records how long it took and whether it failed.
+    fn flush(&self, changed: &[StoreFile]) {
+        let started = Instant::now();
+        let mut dirty = self.dirty.lock().unwrap();
+        dirty.extend(changed);
+
+        let mut result = Ok(());
+        for file in [StoreFile::Data, StoreFile::Badges, StoreFile::Trash, StoreFile::Aliases] {
+            if !dirty.contains(&file) {
+                continue;
+            }
+            match self.save(file) {
+                Ok(()) => {
+                    dirty.remove(&file);
+                }
+                Err(e) => result = Err(e),
+            }
+        }
+        drop(dirty);
+
+        let mut stats = self.flush_stats.lock().unwrap();
+        stats.durations.observe(started.elapsed());
+        stats.last_flush_at = Some(self.clock.now());
+        match result {
+            Ok(()) => stats.last_error = None,
+            Err(e) => {
+                eprintln!("Failed to save counters: {}", e);
+                stats.failures += 1;
+                stats.last_error = Some(e.to_string());
+            }
         }
     }
 
+    /// Writes one of the files
+    fn save(&self, file: StoreFile) -> std::io::Result<()> {
+        if !self.persist {
+            return Ok(());
+        }
+        let (content, path) = match file {
+            StoreFile::Data => (serde_json::to_string_pretty(&*self.data.lock().unwrap())?, &self.path),
+            StoreFile::Badges => (serde_json::to_string_pretty(&*self.badges.lock().unwrap())?, &self.badges_path),
+            StoreFile::Trash => (serde_json::to_string_pretty(&*self.trash.lock().unwrap())?, &self.trash_path),
+            StoreFile::Aliases => (serde_json::to_string_pretty(&*self.aliases.lock().unwrap())?, &self.aliases_path),
+        };
+        std::fs::write(path, content)
+    }
+
+    /// Writes a probe file next to the counters file, reads it back and removes it.
+    /// Used by the readiness check to verify the data directory is usable.
+    pub fn probe(&self) -> Result<(), String> {
+        if !self.persist {
+            return Ok(());
+        }
+        let probe_path = self.path.replace(".json", "_probe.json");
+        let token = self.clock.now().to_rfc3339();
+
+        std::fs::write(&probe_path, &token)
+            .map_err(|e| format!("Cannot write {}: {}", probe_path, e))?;
+        let content = std::fs::read_to_string(&probe_path)
+            .map_err(|e| format!("Cannot read {}: {}", probe_path, e))?;
+        std::fs::remove_file(&probe_path)
+            .map_err(|e| format!("Cannot remove {}: {}", probe_path, e))?;
+
+        if content == token {
+            Ok(())
+        } else {
+            Err(format!("Probe file {} returned unexpected content", probe_path))
+        }
+    }
+
+    /// Statistics about the writes made so far.
+    pub fn flush_stats(&self) -> FlushStats {
+        self.flush_stats.lock().unwrap().clone()
+    }
+
     /// Get all badges for admin interface
     pub fn get_all_badges(&self) -> Vec<BadgeResponse> {
         let badges = self.badges.lock().unwrap();
-        badges.values().map(|badge| BadgeResponse {
-            name: badge.name.clone(),
-            count: badge.count,
-            created_at: badge.created_at,
-            last_accessed: badge.last_accessed,
-        }).collect()
+        let aliases = self.aliases.lock().unwrap();
+        badges.values().map(|badge| with_aliases(to_response(badge, self.clock.now()), &aliases)).collect()
+    }
+
+    /// Badges matching `filter`, sorted. Ties are broken by name so pages stay stable.
+    pub fn find_badges(&self, filter: &BadgeFilter, sort: BadgeSort, order: SortOrder) -> Vec<BadgeResponse> {
+        let badges = self.badges.lock().unwrap();
+        let aliases = self.aliases.lock().unwrap();
+        let mut found: Vec<_> = badges.values().filter(|badge| filter.matches(badge)).collect();
+
+        found.sort_by(|a, b| {
+            let ordering = match sort {
+                BadgeSort::Name => a.name.cmp(&b.name),
+                BadgeSort::Count => a.count.cmp(&b.count),
+                BadgeSort::CreatedAt => a.created_at.cmp(&b.created_at),
+                BadgeSort::LastAccessed => a.last_accessed.cmp(&b.last_accessed),
+            };
+            let ordering = match order {
+                SortOrder::Asc => ordering,
+                SortOrder::Desc => ordering.reverse(),
+            };
+            ordering.then_with(|| a.name.cmp(&b.name))
+        });
+
+        found.into_iter().map(|badge| with_aliases(to_response(badge, self.clock.now()), &aliases)).collect()
+    }
+
+    /// Counters of a namespace with their metadata, sorted by name.
+    pub fn export(&self, namespace: &str) -> Vec<ExportedCounter> {
+        let data = self.data.lock().unwrap();
+        let badges = self.badges.lock().unwrap();
+        let mut counters: Vec<_> = data.iter()
+            .filter(|(key, _)| namespaces::namespace_of(key) == namespace)
+            .map(|(key, count)| {
+                let badge = badges.get(key);
+                ExportedCounter {
+                    name: namespaces::local_name(key).to_string(),
+                    count: *count,
+                    created_at: badge.map(|badge| badge.created_at),
+                    last_accessed: badge.map(|badge| badge.last_accessed),
+                    history: badge.map(|badge| badge.history.clone()).unwrap_or_default(),
+                    metadata: badge.map(|badge| badge.metadata.clone()).unwrap_or_default(),
+                }
+            })
+            .collect();
+        counters.sort_by(|a, b| a.name.cmp(&b.name));
+        counters
+    }
+
+    /// Imports counters named by their storage key, combining them with existing ones according to `strategy`.
+    /// Signing secrets of existing counters are kept, and their metadata is updated with the imported fields.
+    /// With `dry_run`, only reports what would change.
+    pub fn import(&self, counters: Vec<ExportedCounter>, strategy: ImportStrategy, dry_run: bool) -> Vec<ImportChange> {
+        let counters: Vec<_> = counters.into_iter()
+            .map(|counter| ExportedCounter { name: self.resolve(&counter.name), ..counter })
+            .collect();
+        let now = self.clock.now();
+
+        let mut data = self.data.lock().unwrap();
+        let mut badges = self.badges.lock().unwrap();
+        // Results so far, so repeated names in one import see each other.
+        let mut imported: HashMap<String, Badge> = HashMap::new();
+        let mut changes = Vec::new();
+
+        for counter in counters {
+            let key = counter.name.clone();
+            let existing = imported.get(&key).cloned().or_else(|| {
+                data.get(&key).map(|count| {
+                    let mut badge = badges.get(&key).cloned().unwrap_or_else(|| Badge::new(&key, *count, now));
+                    badge.count = *count;
+                    badge
+                })
+            });
+            let created_at = counter.created_at.unwrap_or(now);
+            let incoming = Badge {
+                name: key.clone(),
+                count: counter.count,
+                created_at,
+                last_accessed: counter.last_accessed.unwrap_or(created_at),
+                history: counter.history,
+                signing: None,
+                metadata: counter.metadata,
+                period: None,
+            };
+
+            let change = |outcome, old_count, new_count| ImportChange {
+                name: namespaces::local_name(&key).to_string(),
+                outcome,
+                old_count,
+                new_count,
+                aliases: Vec::new(),
+                error: None,
+            };
+            let Some(existing) = existing else {
+                let namespace = namespaces::namespace_of(&key);
+                let has_room = self.namespace_quotas.get(namespace).is_none_or(|max| {
+                    let stored = data.keys().filter(|name| namespaces::namespace_of(name) == namespace).count();
+                    let created = imported.keys()
+                        .filter(|name| namespaces::namespace_of(name) == namespace && !data.contains_key(*name))
+                        .count();
+                    stored + created < *max
+                });
+                if !has_room {
+                    changes.push(ImportChange {
+                        error: Some("the namespace has reached its counter quota".to_string()),
+                        ..change(ImportOutcome::Failed, None, None)
+                    });
+                    continue;
+                }
+                changes.push(change(ImportOutcome::Created, None, Some(incoming.count)));
+                imported.insert(key, incoming);
+                continue;
+            };
+
+            let mut merged = existing.clone();
+            match strategy {
+                ImportStrategy::SkipExisting => {
+                    changes.push(change(ImportOutcome::Skipped, Some(existing.count), Some(existing.count)));
+                    continue;
+                }
+                ImportStrategy::Overwrite => {
+                    let mut metadata = existing.metadata.clone();
+                    metadata.update(incoming.metadata.clone());
+                    merged = Badge { signing: existing.signing.clone(), metadata, period: existing.period.clone(), ..incoming };
+                }
+                ImportStrategy::KeepMax => {
+                    merged.metadata.update(incoming.metadata);
+                    merged.count = merged.count.max(incoming.count);
+                    merged.created_at = merged.created_at.min(incoming.created_at);
+                    merged.last_accessed = merged.last_accessed.max(incoming.last_accessed);
+                    for (day, visits) in incoming.history {
+                        let entry = merged.history.entry(day).or_insert(0);
+                        *entry = (*entry).max(visits);
+                    }
+                }
+                ImportStrategy::Sum => {
+                    merged.metadata.update(incoming.metadata.clone());
+                    merged.absorb(incoming);
+                }
+            }
+
+            let outcome = if merged.count == existing.count
+                && merged.history == existing.history
+                && merged.metadata == existing.metadata
+            {
+                ImportOutcome::Unchanged
+            } else {
+                ImportOutcome::Updated
+            };
+            changes.push(change(outcome, Some(existing.count), Some(merged.count)));
+            imported.insert(key, merged);
+        }
+
+        if !dry_run && !imported.is_empty() {
+            for (key, badge) in imported {
+                data.insert(key.clone(), badge.count);
+                badges.insert(key, badge);
+            }
+            drop(badges);
+            drop(data);
+            self.flush(&[StoreFile::Data, StoreFile::Badges]);
+        }
+        changes
     }
 
     /// Get a specific badge
     pub fn get_badge(&self, name: &str) -> Option<BadgeResponse> {
         let badges = self.badges.lock().unwrap();
+        let aliases = self.aliases.lock().unwrap();
+        badges.get(name).map(|badge| with_aliases(to_response(badge, self.clock.now()), &aliases))
+    }
+
+    /// Get a specific badge with its daily history
+    pub fn get_badge_with_history(&self, name: &str) -> Option<BadgeResponse> {
+        let badges = self.badges.lock().unwrap();
+        let aliases = self.aliases.lock().unwrap();
         badges.get(name).map(|badge| BadgeResponse {
-            name: badge.name.clone(),
-            count: badge.count,
-            created_at: badge.created_at,
-            last_accessed: badge.last_accessed,
+            history: Some(badge.history.clone()),
+            ..with_aliases(to_response(badge, self.clock.now()), &aliases)
         })
     }
 
-    /// Delete a badge and its counter
+    /// Signing secrets of a counter or of the counter an alias points to, if its URLs must be signed
+    pub fn signing_keys(&self, name: &str) -> Option<SigningKeys> {
+        let name = self.resolve(name);
+        let badges = self.badges.lock().unwrap();
+        badges.get(&name).and_then(|badge| badge.signing.clone())
+    }
+
+    /// Current signing secret of a badge
+    pub fn get_signing_secret(&self, name: &str) -> Option<SigningSecretResponse> {
+        let badges = self.badges.lock().unwrap();
+        let keys = badges.get(name)?.signing.as_ref()?;
+        Some(SigningSecretResponse {
+            name: name.to_string(),
+            secret: keys.secret.clone(),
+            previous_expires_at: keys.previous_expires_at.filter(|expires_at| *expires_at > self.clock.now()),
+        })
+    }
+
+    /// Enable URL signing for a badge, or replace its secret.
+    /// The replaced secret stays valid for `grace`, so embeds can be updated.
+    pub fn rotate_signing_secret(&self, name: &str, grace: Duration) -> Option<SigningSecretResponse> {
+        let response = {
+            let mut badges = self.badges.lock().unwrap();
+            let badge = badges.get_mut(name)?;
+            let now = self.clock.now();
+            let previous = badge.signing.take()
+                .map(|keys| keys.secret)
+                .filter(|_| grace > Duration::zero());
+            let keys = SigningKeys {
+                secret: signing::generate_secret(),
+                previous_expires_at: previous.as_ref().map(|_| now + grace),
+                previous,
+            };
+            let response = SigningSecretResponse {
+                name: name.to_string(),
+                secret: keys.secret.clone(),
+                previous_expires_at: keys.previous_expires_at,
+            };
+            badge.signing = Some(keys);
+            response
+        };
+
+        self.flush(&[StoreFile::Badges]);
+        Some(response)
+    }
+
+    /// Stop requiring signed URLs for a badge. Returns `false` if the badge does not exist.
+    pub fn disable_signing(&self, name: &str) -> bool {
+        {
+            let mut badges = self.badges.lock().unwrap();
+            let Some(badge) = badges.get_mut(name) else {
+                return false;
+            };
+            badge.signing = None;
+        }
+
+        self.flush(&[StoreFile::Badges]);
+        true
+    }
+
+    /// Replace the description, owner, URL and tags of a badge. Returns `None` if the badge does not exist.
+    pub fn set_metadata(&self, name: &str, metadata: BadgeMetadata) -> Option<BadgeResponse> {
+        let response = {
+            let mut badges = self.badges.lock().unwrap();
+            let aliases = self.aliases.lock().unwrap();
+            let badge = badges.get_mut(name)?;
+            badge.metadata = metadata;
+            with_aliases(to_response(badge, self.clock.now()), &aliases)
+        };
+
+        self.flush(&[StoreFile::Badges]);
+        Some(response)
+    }
+
+    /// Make a counter start over every `period` in `timezone`, or never with `Period::None`.
+    /// Changing the period or time zone starts a new window and drops the archived ones.
+    /// Returns `None` if the badge does not exist.
+    pub fn set_period(&self, name: &str, period: Period, timezone: &str) -> Option<BadgeResponse> {
+        let response = {
+            let mut badges = self.badges.lock().unwrap();
+            let aliases = self.aliases.lock().unwrap();
+            let badge = badges.get_mut(name)?;
+            let unchanged = badge.period.as_ref()
+                .is_some_and(|current| current.period == period && current.timezone == timezone);
+            if period == Period::None {
+                badge.period = None;
+            } else if !unchanged {
+                badge.period = Some(PeriodCounter::new(period, timezone, self.clock.now(), &badge.history));
+            }
+            with_aliases(to_response(badge, self.clock.now()), &aliases)
+        };
+
+        self.flush(&[StoreFile::Badges]);
+        Some(response)
+    }
+
+    /// List every alias with the counter it resolves to, sorted by alias
+    pub fn get_aliases(&self) -> Vec<AliasResponse> {
+        let aliases = self.aliases.lock().unwrap();
+        let mut list: Vec<_> = aliases.iter().map(|(alias, target)| AliasResponse {
+            alias: alias.clone(),
+            target: target.clone(),
+        }).collect();
+        list.sort_by(|a, b| a.alias.cmp(&b.alias));
+        list
+    }
+
+    /// Make `alias` count into `target`. An alias of an alias points to the final counter.
+    pub fn add_alias(&self, alias: &str, target: &str) -> Result<AliasResponse, CounterError> {
+        self.add_aliases(&[(alias.to_string(), target.to_string())]).remove(0)
+    }
+
+    /// Adds several `(alias, target)` pairs like `add_alias`, writing the files once.
+    pub fn add_aliases(&self, pairs: &[(String, String)]) -> Vec<Result<AliasResponse, CounterError>> {
+        let results: Vec<_> = {
+            let data = self.data.lock().unwrap();
+            let badges = self.badges.lock().unwrap();
+            let mut aliases = self.aliases.lock().unwrap();
+
+            pairs.iter().map(|(alias, target)| {
+                let target = aliases.get(target).cloned().unwrap_or_else(|| target.to_string());
+                if alias.is_empty() || *alias == target {
+                    return Err(CounterError::Invalid);
+                }
+                if !data.contains_key(&target) && !badges.contains_key(&target) {
+                    return Err(CounterError::NotFound);
+                }
+                if data.contains_key(alias) || badges.contains_key(alias) || aliases.contains_key(alias) {
+                    return Err(CounterError::NameTaken);
+                }
+
+                aliases.insert(alias.to_string(), target.clone());
+                Ok(AliasResponse {
+                    alias: alias.to_string(),
+                    target,
+                })
+            }).collect()
+        };
+
+        if results.iter().any(Result::is_ok) {
+            self.flush(&[StoreFile::Aliases]);
+        }
+        results
+    }
+
+    /// Remove an alias, returning the counter it pointed to
+    pub fn remove_alias(&self, alias: &str) -> Option<String> {
+        let target = self.aliases.lock().unwrap().remove(alias)?;
+        self.flush(&[StoreFile::Aliases]);
+        Some(target)
+    }
+
+    /// Drop aliases whose counter no longer exists, not even in the trash
+    fn prune_aliases(&self) {
+        let data = self.data.lock().unwrap();
+        let badges = self.badges.lock().unwrap();
+        let trash = self.trash.lock().unwrap();
+        let mut aliases = self.aliases.lock().unwrap();
+        aliases.retain(|_, target| data.contains_key(target) || badges.contains_key(target) || trash.contains_key(target));
+    }
+
+    /// Move a badge and its counter to the trash.
+    /// A badge already in the trash under the same name is replaced.
     pub fn delete_badge(&self, name: &str) -> bool {
+        let now = self.clock.now();
         let mut data = self.data.lock().unwrap();
         let mut badges = self.badges.lock().unwrap();
 
-        let removed_data = data.remove(name).is_some();
-        let removed_badge = badges.remove(name).is_some();
+        let removed_count = data.remove(name);
+        let removed_badge = badges.remove(name);
 
-        if removed_data || removed_badge {
-            drop(data);
-            drop(badges);
-            self.save_data();
-            self.save_badges();
-            true
-        } else {
-            false
+        if removed_count.is_none() && removed_badge.is_none() {
+            return false;
+        }
+
+        let badge = removed_badge.unwrap_or_else(|| Badge::new(name, 0, now));
+        let badge = Badge { count: removed_count.unwrap_or(badge.count), ..badge };
+        self.trash.lock().unwrap().insert(name.to_string(), TrashedBadge { badge, deleted_at: now });
+
+        drop(data);
+        drop(badges);
+        self.flush(&[StoreFile::Data, StoreFile::Badges, StoreFile::Trash]);
+        true
+    }
+
+    /// Restore a badge from the trash. If the counter was hit again since it was deleted,
+    /// the new visits are added to the restored count.
+    pub fn restore_badge(&self, name: &str) -> Option<BadgeResponse> {
+        let trashed = self.trash.lock().unwrap().remove(name)?;
+
+        let badge = {
+            let mut data = self.data.lock().unwrap();
+            let mut badges = self.badges.lock().unwrap();
+
+            let mut badge = trashed.badge;
+            if let Some(current) = badges.remove(name) {
+                badge.absorb(current);
+            }
+            data.insert(name.to_string(), badge.count);
+            badges.insert(name.to_string(), badge.clone());
+            badge
+        };
+
+        self.flush(&[StoreFile::Data, StoreFile::Badges, StoreFile::Trash]);
+
+        self.get_badge(&badge.name)
+    }
+
+    /// Permanently remove a badge from the trash, returning its count
+    pub fn purge_badge(&self, name: &str) -> Option<u64> {
+        let removed = self.trash.lock().unwrap().remove(name)?;
+        self.prune_aliases();
+        self.flush(&[StoreFile::Trash, StoreFile::Aliases]);
+        Some(removed.badge.count)
+    }
+
+    /// List the badges in the trash, most recently deleted first
+    pub fn get_trash(&self) -> Vec<TrashedBadgeResponse> {
+        self.purge_expired_trash();
+
+        let trash = self.trash.lock().unwrap();
+        let mut list: Vec<_> = trash.values().map(|trashed| TrashedBadgeResponse {
+            name: trashed.badge.name.clone(),
+            count: trashed.badge.count,
+            created_at: trashed.badge.created_at,
+            deleted_at: trashed.deleted_at,
+            purge_at: self.trash_retention.map(|retention| trashed.deleted_at + retention),
+        }).collect();
+        list.sort_by_key(|trashed| std::cmp::Reverse(trashed.deleted_at));
+        list
+    }
+
+    /// Drop badges that have been in the trash longer than the retention period
+    fn purge_expired_trash(&self) {
+        let Some(retention) = self.trash_retention else {
+            return;
+        };
+        let cutoff = self.clock.now() - retention;
+
+        let purged = {
+            let mut trash = self.trash.lock().unwrap();
+            let before = trash.len();
+            trash.retain(|_, trashed| trashed.deleted_at > cutoff);
+            trash.len() != before
+        };
+        if purged {
+            self.prune_aliases();
+            self.flush(&[StoreFile::Trash, StoreFile::Aliases]);
         }
     }
 
     /// Create a new badge with optional initial count
     pub fn create_badge(&self, name: &str, initial_count: Option<u64>) -> BadgeResponse {
-        let now = Utc::now();
         let count = initial_count.unwrap_or(0);
+        let badge = Badge::new(name, count, self.clock.now());
 
         {
             let mut data = self.data.lock().unwrap();
             data.insert(name.to_string(), count);
         }
 
-        let badge = Badge {
-            name: name.to_string(),
-            count,
-            created_at: now,
-            last_accessed: now,
-        };
-
         {
             let mut badges = self.badges.lock().unwrap();
             badges.insert(name.to_string(), badge.clone());
         }
 
-        self.save_data();
-        self.save_badges();
+        self.flush(&[StoreFile::Data, StoreFile::Badges]);
+
+        to_response(&badge, self.clock.now())
+    }
+
+    /// Whether `name` is taken by a counter, a badge or an alias
+    pub fn name_in_use(&self, name: &str) -> bool {
+        self.data.lock().unwrap().contains_key(name)
+            || self.badges.lock().unwrap().contains_key(name)
+            || self.aliases.lock().unwrap().contains_key(name)
+    }
+
+    /// Whether a badge named `name` is in the trash
+    pub fn in_trash(&self, name: &str) -> bool {
+        self.trash.lock().unwrap().contains_key(name)
+    }
+
+    /// Rename a badge, keeping its count and history.
+    /// The old name becomes an alias, so embeds using it keep counting into the new name.
+    pub fn rename_badge(&self, name: &str, new_name: &str) -> Result<BadgeResponse, CounterError> {
+        if new_name.is_empty() || new_name == name {
+            return Err(CounterError::Invalid);
+        }
+
+        let badge = {
+            let mut data = self.data.lock().unwrap();
+            let mut badges = self.badges.lock().unwrap();
+            let mut aliases = self.aliases.lock().unwrap();
+
+            if !data.contains_key(name) && !badges.contains_key(name) {
+                return Err(CounterError::NotFound);
+            }
+            // Renaming back to one of the badge's own aliases is fine, the alias goes away.
+            let alias_of_other = aliases.get(new_name).is_some_and(|target| target != name);
+            if data.contains_key(new_name) || badges.contains_key(new_name) || alias_of_other {
+                return Err(CounterError::NameTaken);
+            }
+
+            let now = self.clock.now();
+            let count = data.remove(name);
+            let mut badge = badges.remove(name).unwrap_or_else(|| Badge::new(name, count.unwrap_or(0), now));
+            badge.name = new_name.to_string();
+            badge.count = count.unwrap_or(badge.count);
+
+            data.insert(new_name.to_string(), badge.count);
+            badges.insert(new_name.to_string(), badge.clone());
+            point_aliases(&mut aliases, name, new_name);
+            with_aliases(to_response(&badge, self.clock.now()), &aliases)
+        };
+
+        self.flush(&[StoreFile::Data, StoreFile::Badges, StoreFile::Aliases]);
+        Ok(badge)
+    }
+
+    /// Merge badges into `target`, summing their counts and histories.
+    /// `target` may be an existing badge or a new name; each source becomes an alias of it.
+    pub fn merge_badges(&self, sources: &[String], target: &str) -> Result<BadgeResponse, CounterError> {
+        if target.is_empty() || sources.is_empty() || sources.iter().any(|source| source == target) {
+            return Err(CounterError::Invalid);
+        }
+
+        let badge = {
+            let mut data = self.data.lock().unwrap();
+            let mut badges = self.badges.lock().unwrap();
+            let mut aliases = self.aliases.lock().unwrap();
+
+            if sources.iter().any(|source| !data.contains_key(source) && !badges.contains_key(source)) {
+                return Err(CounterError::NotFound);
+            }
+            if aliases.contains_key(target) {
+                return Err(CounterError::NameTaken);
+            }
+
+            let now = self.clock.now();
+            let mut merged = badges.remove(target)
+                .unwrap_or_else(|| Badge::new(target, data.get(target).copied().unwrap_or(0), now));
+            for source in sources {
+                let count = data.remove(source);
+                if let Some(mut badge) = badges.remove(source).or_else(|| count.map(|count| Badge::new(source, count, now))) {
+                    badge.count = count.unwrap_or(badge.count);
+                    merged.absorb(badge);
+                }
+                point_aliases(&mut aliases, source, target);
+            }
+
+            data.insert(target.to_string(), merged.count);
+            badges.insert(target.to_string(), merged.clone());
+            with_aliases(to_response(&merged, self.clock.now()), &aliases)
+        };
+
+        self.flush(&[StoreFile::Data, StoreFile::Badges, StoreFile::Aliases]);
+        Ok(badge)
+    }
+
+    /// Applies `operation` to the counters `keys` (or aliases of them) in one step: every counter is
+    /// checked first, and nothing changes unless all of them can be changed. Returns whether the
+    /// operation was applied, and one result per counter, named by its key.
+    pub fn bulk(&self, keys: &[String], operation: &BulkOperation) -> (bool, Vec<BulkItemResult>) {
+        let now = self.clock.now();
+        let mut data = self.data.lock().unwrap();
+        let mut badges = self.badges.lock().unwrap();
+        let mut trash = self.trash.lock().unwrap();
+        let mut aliases = self.aliases.lock().unwrap();
+
+        let mut targets = Vec::new();
+        let mut results = Vec::new();
+        let mut seen = HashSet::new();
+        let mut moved = 0;
+        for key in keys {
+            let key = aliases.get(key).cloned().unwrap_or_else(|| key.clone());
+            if !seen.insert(key.clone()) {
+                continue;
+            }
+            let old_count = data.get(&key).copied().or_else(|| badges.get(&key).map(|badge| badge.count));
+            let error = match (old_count, operation) {
+                (None, _) => Some("badge not found"),
+                (Some(_), BulkOperation::Move { namespace }) => {
+                    let destination = namespaces::counter_key(namespace, namespaces::local_name(&key));
+                    let stored = data.keys().filter(|key| namespaces::namespace_of(key) == namespace).count();
+                    if namespaces::namespace_of(&key) == namespace {
+                        Some("the badge is already in this namespace")
+                    } else if data.contains_key(&destination) || badges.contains_key(&destination) || aliases.contains_key(&destination) {
+                        Some("a badge or alias with this name exists in the namespace")
+                    } else if self.namespace_quotas.get(namespace).is_some_and(|max| stored + moved >= *max) {
+                        Some("the namespace has reached its counter quota")
+                    } else {
+                        moved += 1;
+                        None
+                    }
+                }
+                _ => None,
+            };
+            results.push(BulkItemResult {
+                name: key.clone(),
+                ok: error.is_none(),
+                old_count,
+                new_count: None,
+                error: error.map(str::to_string),
+            });
+            targets.push(key);
+        }
+        if results.iter().any(|result| !result.ok) {
+            return (false, results);
+        }
+
+        for (key, result) in targets.into_iter().zip(&mut results) {
+            let count = result.old_count.unwrap_or(0);
+            result.new_count = match operation {
+                BulkOperation::Delete => {
+                    data.remove(&key);
+                    let badge = badges.remove(&key).unwrap_or_else(|| Badge::new(&key, count, now));
+                    trash.insert(key.clone(), TrashedBadge { badge: Badge { count, ..badge }, deleted_at: now });
+                    None
+                }
+                BulkOperation::Reset | BulkOperation::AddOffset { .. } => {
+                    let new_count = match operation {
+                        BulkOperation::AddOffset { offset } => count.saturating_add_signed(*offset),
+                        _ => 0,
+                    };
+                    data.insert(key.clone(), new_count);
+                    let badge = badges.entry(key.clone()).or_insert_with(|| Badge::new(&key, count, now));
+                    badge.count = new_count;
+                    badge.last_accessed = now;
+                    // Resets start the period windows over; offsets are not visits and leave them as they are
+                    if let (BulkOperation::Reset, Some(period)) = (operation, &mut badge.period) {
+                        period.clear(now);
+                    }
+                    Some(new_count)
+                }
+                BulkOperation::Tag { tags } | BulkOperation::Untag { tags } => {
+                    let badge = badges.entry(key.clone()).or_insert_with(|| Badge::new(&key, count, now));
+                    if matches!(operation, BulkOperation::Tag { .. }) {
+                        badge.metadata.tags.extend(tags.iter().cloned());
+                    } else {
+                        badge.metadata.tags.retain(|tag| !tags.contains(tag));
+                    }
+                    Some(count)
+                }
+                BulkOperation::Move { namespace } => {
+                    let destination = namespaces::counter_key(namespace, namespaces::local_name(&key));
+                    data.remove(&key);
+                    let badge = badges.remove(&key).unwrap_or_else(|| Badge::new(&key, count, now));
+                    data.insert(destination.clone(), count);
+                    badges.insert(destination.clone(), Badge { name: destination.clone(), count, ..badge });
+
+                    // Aliases follow the badge, unless their name is taken in the new namespace
+                    let moved_aliases: Vec<String> = aliases.iter()
+                        .filter(|(_, target)| **target == key)
+                        .map(|(alias, _)| alias.clone())
+                        .collect();
+                    for alias in moved_aliases {
+                        aliases.remove(&alias);
+                        let alias = namespaces::counter_key(namespace, namespaces::local_name(&alias));
+                        if !data.contains_key(&alias) && !badges.contains_key(&alias) && !aliases.contains_key(&alias) {
+                            aliases.insert(alias, destination.clone());
+                        }
+                    }
+                    Some(count)
+                }
+            };
+        }
+
+        drop(aliases);
+        drop(trash);
+        drop(badges);
+        drop(data);
+        self.flush(match operation {
+            BulkOperation::Delete => &[StoreFile::Data, StoreFile::Badges, StoreFile::Trash],
+            BulkOperation::Reset | BulkOperation::AddOffset { .. } => &[StoreFile::Data, StoreFile::Badges],
+            BulkOperation::Tag { .. } | BulkOperation::Untag { .. } => &[StoreFile::Badges],
+            BulkOperation::Move { .. } => &[StoreFile::Data, StoreFile::Badges, StoreFile::Aliases],
+        });
+        (true, results)
+    }
+}
+
+/// Makes `from` and every alias pointing to it resolve to `to`, so aliases never chain.
+fn point_aliases(aliases: &mut HashMap<String, String>, from: &str, to: &str) {
+    for target in aliases.values_mut() {
+        if target == from {
+            *target = to.to_string();
+        }
+    }
+    aliases.insert(from.to_string(), to.to_string());
+    aliases.remove(to);
+}
+
+/// Response for a badge, with its period rolled over to `now`.
+fn to_response(badge: &Badge, now: DateTime<Utc>) -> BadgeResponse {
+    BadgeResponse {
+        name: badge.name.clone(),
+        count: badge.count,
+        created_at: badge.created_at,
+        last_accessed: badge.last_accessed,
+        aliases: Vec::new(),
+        signed: badge.signing.is_some(),
+        description: badge.metadata.description.clone(),
+        owner: badge.metadata.owner.clone(),
+        url: badge.metadata.url.clone(),
+        tags: badge.metadata.tags.iter().cloned().collect(),
+        period: badge.period.as_ref().map(|period| period.to_response(now)),
+        history: None,
+    }
+}
+
+fn with_aliases(mut response: BadgeResponse, aliases: &HashMap<String, String>) -> BadgeResponse {
+    response.aliases = aliases.iter()
+        .filter(|(_, target)| **target == response.name)
+        .map(|(alias, _)| alias.clone())
+        .collect();
+    response.aliases.sort();
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::clock::ManualClock;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc)
+    }
+
+    fn date(date: &str) -> NaiveDate {
+        date.parse().unwrap()
+    }
+
+    #[test]
+    fn visits_set_last_accessed_and_fill_the_history_of_their_day() {
+        let clock = ManualClock::at("2024-06-30T23:59:00Z");
+        let counters = PersistentCounterMap::in_memory().with_clock(clock.clone());
+
+        counters.increment("home").unwrap();
+        counters.increment("home").unwrap();
+        clock.advance(Duration::minutes(2));
+        counters.increment("home").unwrap();
+
+        let badge = counters.get_badge_with_history("home").unwrap();
+        assert_eq!(badge.count, 3);
+        assert_eq!(badge.created_at, at("2024-06-30T23:59:00Z"));
+        assert_eq!(badge.last_accessed, at("2024-07-01T00:01:00Z"));
+        assert_eq!(badge.history, Some(BTreeMap::from([(date("2024-06-30"), 2), (date("2024-07-01"), 1)])));
+    }
+
+    #[test]
+    fn setting_a_counter_marks_it_accessed() {
+        let clock = ManualClock::at("2024-01-01T10:00:00Z");
+        let counters = PersistentCounterMap::in_memory().with_clock(clock.clone());
+        counters.create_badge("docs", None);
+
+        clock.set(at("2024-02-01T10:00:00Z"));
+        counters.set("docs", 42);
+
+        let badge = counters.get_badge("docs").unwrap();
+        assert_eq!(badge.count, 42);
+        assert_eq!(badge.created_at, at("2024-01-01T10:00:00Z"));
+        assert_eq!(badge.last_accessed, at("2024-02-01T10:00:00Z"));
+    }
+
+    #[test]
+    fn trash_is_purged_after_the_retention_period() {
+        let clock = ManualClock::at("2024-03-01T12:00:00Z");
+        let counters = PersistentCounterMap::in_memory().with_clock(clock.clone()).with_trash_retention(30);
+        counters.create_badge("old", Some(5));
+        counters.delete_badge("old");
+
+        clock.advance(Duration::days(30) - Duration::seconds(1));
+        let trash = counters.get_trash();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].purge_at, Some(at("2024-03-31T12:00:00Z")));
+
+        clock.advance(Duration::seconds(1));
+        assert!(counters.get_trash().is_empty());
+    }
+
+    #[test]
+    fn periodic_counters_roll_over_with_the_clock() {
+        let clock = ManualClock::at("2024-05-31T21:00:00Z");
+        let counters = PersistentCounterMap::in_memory().with_clock(clock.clone());
+        counters.create_badge("blog", None);
+        counters.set_period("blog", Period::Monthly, "Europe/Berlin").unwrap();
+
+        counters.increment("blog").unwrap();
+        // 00:30 in Berlin, a new month there
+        clock.advance(Duration::hours(1) + Duration::minutes(30));
+        counters.increment("blog").unwrap();
+
+        assert_eq!(counters.get_view("blog", PeriodView::Current), 1);
+        assert_eq!(counters.get_view("blog", PeriodView::Previous), 1);
+        assert_eq!(counters.get_view("blog", PeriodView::All), 2);
+
+        clock.set(at("2024-08-15T12:00:00Z"));
+        assert_eq!(counters.get_view("blog", PeriodView::Current), 0);
+        assert_eq!(counters.get_view("blog", PeriodView::Previous), 0);
+    }
+
+    #[test]
+    fn setting_or_resetting_a_counter_starts_its_periods_over() {
+        let clock = ManualClock::at("2024-05-31T12:00:00Z");
+        let counters = PersistentCounterMap::in_memory().with_clock(clock.clone());
+        let views = |name: &str| {
+            [PeriodView::Current, PeriodView::Previous, PeriodView::All].map(|view| counters.get_view(name, view))
+        };
+        for name in ["blog", "docs", "home"] {
+            counters.create_badge(name, None);
+            counters.set_period(name, Period::Daily, "UTC").unwrap();
+            counters.increment(name).unwrap();
+            clock.advance(Duration::days(1));
+            counters.increment(name).unwrap();
+            clock.advance(-Duration::days(1));
+        }
+        clock.advance(Duration::days(1));
+
+        counters.set("blog", 10);
+        assert_eq!(views("blog"), [0, 0, 10]);
+        counters.increment("blog").unwrap();
+        assert_eq!(views("blog"), [1, 0, 11]);
+
+        counters.bulk(&keys(&["docs"]), &BulkOperation::Reset);
+        assert_eq!(views("docs"), [0, 0, 0]);
+        counters.bulk(&keys(&["home"]), &BulkOperation::AddOffset { offset: 5 });
+        assert_eq!(views("home"), [1, 1, 7]);
+    }
+
+    #[test]
+    fn only_changed_files_are_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counters.json");
+        let file = |suffix: &str| dir.path().join(format!("counters{}.json", suffix));
+        let counters = PersistentCounterMap::new(&path.to_string_lossy());
+
+        counters.increment("home").unwrap();
+        assert!(file("").exists() && file("_badges").exists());
+        assert!(!file("_trash").exists() && !file("_aliases").exists());
+
+        counters.add_alias("start", "home").unwrap();
+        assert!(file("_aliases").exists() && !file("_trash").exists());
+
+        std::fs::remove_file(file("_aliases")).unwrap();
+        counters.increment("start").unwrap();
+        assert!(!file("_aliases").exists());
+        counters.delete_badge("home");
+        assert!(file("_trash").exists() && !file("_aliases").exists());
+    }
+
+    #[test]
+    fn restored_badges_keep_the_visits_made_while_in_the_trash() {
+        let clock = ManualClock::at("2024-03-01T12:00:00Z");
+        let counters = PersistentCounterMap::in_memory().with_clock(clock.clone());
+        counters.create_badge("home", Some(5));
+        counters.add_alias("start", "home").unwrap();
+        assert!(counters.delete_badge("home"));
+        assert!(!counters.delete_badge("home"));
+        assert!(counters.in_trash("home") && !counters.name_in_use("home"));
+        assert_eq!(counters.get("home"), 0);
+
+        // An embed still counting while the badge is in the trash
+        clock.advance(Duration::days(1));
+        counters.increment("start").unwrap();
+
+        let restored = counters.restore_badge("home").unwrap();
+        assert_eq!(restored.count, 6);
+        assert_eq!(restored.aliases, vec!["start"]);
+        assert_eq!(restored.created_at, at("2024-03-01T12:00:00Z"));
+        assert_eq!(restored.last_accessed, at("2024-03-02T12:00:00Z"));
+        assert!(counters.get_trash().is_empty());
+        assert!(counters.restore_badge("home").is_none());
+    }
+
+    #[test]
+    fn purged_badges_take_their_aliases_with_them() {
+        let counters = PersistentCounterMap::in_memory().with_clock(ManualClock::at("2024-03-01T12:00:00Z"));
+        counters.create_badge("home", Some(5));
+        counters.add_alias("start", "home").unwrap();
+        counters.delete_badge("home");
+        assert_eq!(counters.get_aliases().len(), 1);
+
+        assert_eq!(counters.purge_badge("home"), Some(5));
+        assert!(counters.get_aliases().is_empty());
+        assert_eq!(counters.purge_badge("home"), None);
+    }
 
-        BadgeResponse {
-            name: badge.name,
-            count: badge.count,
-            created_at: badge.created_at,
-            last_accessed: badge.last_accessed,
+    #[test]
+    fn renamed_badges_keep_their_history_and_old_names() {
+        let clock = ManualClock::at("2024-03-01T12:00:00Z");
+        let counters = PersistentCounterMap::in_memory().with_clock(clock.clone());
+        counters.increment("home").unwrap();
+        counters.add_alias("index", "home").unwrap();
+
+        let renamed = counters.rename_badge("home", "start").unwrap();
+        assert_eq!(renamed.count, 1);
+        assert_eq!(renamed.aliases, vec!["home", "index"]);
+        assert_eq!(counters.get_badge_with_history("start").unwrap().history, Some(BTreeMap::from([(date("2024-03-01"), 1)])));
+        counters.increment("home").unwrap();
+        counters.increment("index").unwrap();
+        assert_eq!(counters.get("start"), 3);
+
+        // Back to an own alias, which goes away
+        let renamed = counters.rename_badge("start", "home").unwrap();
+        assert_eq!(renamed.aliases, vec!["index", "start"]);
+    }
+
+    #[test]
+    fn renames_need_a_free_new_name() {
+        let counters = PersistentCounterMap::in_memory().with_clock(ManualClock::at("2024-03-01T12:00:00Z"));
+        counters.create_badge("home", None);
+        counters.create_badge("blog", None);
+        counters.add_alias("news", "blog").unwrap();
+        assert!(matches!(counters.rename_badge("home", "blog"), Err(CounterError::NameTaken)));
+        assert!(matches!(counters.rename_badge("home", "news"), Err(CounterError::NameTaken)));
+        assert!(matches!(counters.rename_badge("home", "home"), Err(CounterError::Invalid)));
+        assert!(matches!(counters.rename_badge("home", ""), Err(CounterError::Invalid)));
+        assert!(matches!(counters.rename_badge("missing", "other"), Err(CounterError::NotFound)));
+    }
+
+    #[test]
+    fn merges_sum_counts_and_histories() {
+        let clock = ManualClock::at("2024-03-01T12:00:00Z");
+        let counters = PersistentCounterMap::in_memory().with_clock(clock.clone());
+        counters.increment("docs").unwrap();
+        clock.advance(Duration::days(1));
+        counters.increment("docs").unwrap();
+        counters.increment("guide").unwrap();
+        counters.create_badge("manual", Some(u64::MAX));
+
+        let sources = vec!["docs".to_string(), "guide".to_string()];
+        let merged = counters.merge_badges(&sources, "handbook").unwrap();
+        assert_eq!(merged.count, 3);
+        assert_eq!(merged.created_at, at("2024-03-01T12:00:00Z"));
+        assert_eq!(merged.aliases, vec!["docs", "guide"]);
+        assert_eq!(
+            counters.get_badge_with_history("handbook").unwrap().history,
+            Some(BTreeMap::from([(date("2024-03-01"), 1), (date("2024-03-02"), 2)]))
+        );
+
+        let merged = counters.merge_badges(&["manual".to_string()], "handbook").unwrap();
+        assert_eq!(merged.count, u64::MAX);
+
+        assert!(matches!(counters.merge_badges(&["missing".to_string()], "handbook"), Err(CounterError::NotFound)));
+        assert!(matches!(counters.merge_badges(&["handbook".to_string()], "handbook"), Err(CounterError::Invalid)));
+        assert!(matches!(counters.merge_badges(&[], "handbook"), Err(CounterError::Invalid)));
+        counters.create_badge("faq", None);
+        assert!(matches!(counters.merge_badges(&["faq".to_string()], "docs"), Err(CounterError::NameTaken)));
+    }
+
+    #[test]
+    fn aliases_of_aliases_point_to_the_counter() {
+        let counters = PersistentCounterMap::in_memory().with_clock(ManualClock::at("2024-03-01T12:00:00Z"));
+        counters.create_badge("home", Some(1));
+        counters.add_alias("start", "home").unwrap();
+        let alias = counters.add_alias("index", "start").unwrap();
+        assert_eq!(alias.target, "home");
+        assert_eq!(counters.resolve("index"), "home");
+        assert_eq!(counters.resolve("other"), "other");
+
+        counters.increment("index").unwrap();
+        assert_eq!(counters.get("start"), 2);
+        assert_eq!(counters.get_badge("home").unwrap().aliases, vec!["index", "start"]);
+
+        assert_eq!(counters.remove_alias("start").as_deref(), Some("home"));
+        assert_eq!(counters.remove_alias("start"), None);
+        assert_eq!(counters.get_aliases().len(), 1);
+    }
+
+    #[test]
+    fn aliases_need_a_free_name_and_an_existing_counter() {
+        let counters = PersistentCounterMap::in_memory().with_clock(ManualClock::at("2024-03-01T12:00:00Z"));
+        counters.create_badge("home", None);
+        counters.create_badge("blog", None);
+        counters.add_alias("start", "home").unwrap();
+
+        let results = counters.add_aliases(&[
+            ("blog".to_string(), "home".to_string()),
+            ("start".to_string(), "blog".to_string()),
+            ("home".to_string(), "home".to_string()),
+            ("home".to_string(), "start".to_string()),
+            ("".to_string(), "home".to_string()),
+            ("other".to_string(), "missing".to_string()),
+            ("news".to_string(), "blog".to_string()),
+        ]);
+        let outcomes: Vec<_> = results.iter().map(|result| result.as_ref().map(|alias| alias.target.as_str()).map_err(|error| *error)).collect();
+        assert!(matches!(outcomes.as_slice(), [
+            Err(CounterError::NameTaken),
+            Err(CounterError::NameTaken),
+            Err(CounterError::Invalid),
+            Err(CounterError::Invalid),
+            Err(CounterError::Invalid),
+            Err(CounterError::NotFound),
+            Ok("blog"),
+        ]));
+    }
+
+    #[test]
+    fn badges_are_filtered_and_sorted_with_ties_broken_by_name() {
+        let clock = ManualClock::at("2024-03-01T12:00:00Z");
+        let counters = PersistentCounterMap::in_memory().with_clock(clock.clone());
+        counters.create_badge("home", Some(10));
+        clock.advance(Duration::days(1));
+        counters.create_badge("blog", Some(3));
+        counters.create_badge("docs/guide", Some(3));
+        counters.set_metadata("blog", BadgeMetadata {
+            description: Some("News of the Project".to_string()),
+            owner: Some("Web-Team".to_string()),
+            tags: ["public".to_string()].into(),
+            ..BadgeMetadata::default()
+        });
+
+        let names = |filter: BadgeFilter, sort: BadgeSort, order: SortOrder| -> Vec<String> {
+            counters.find_badges(&filter, sort, order).into_iter().map(|badge| badge.name).collect()
+        };
+        assert_eq!(names(BadgeFilter::default(), BadgeSort::Name, SortOrder::Asc), vec!["blog", "docs/guide", "home"]);
+        assert_eq!(names(BadgeFilter::default(), BadgeSort::Count, SortOrder::Desc), vec!["home", "blog", "docs/guide"]);
+        assert_eq!(names(BadgeFilter::default(), BadgeSort::Count, SortOrder::Asc), vec!["blog", "docs/guide", "home"]);
+        assert_eq!(names(BadgeFilter::default(), BadgeSort::CreatedAt, SortOrder::Asc), vec!["home", "blog", "docs/guide"]);
+
+        let filtered = |filter: BadgeFilter| names(filter, BadgeSort::Name, SortOrder::Asc);
+        assert_eq!(filtered(BadgeFilter { namespace: Some("default".to_string()), ..BadgeFilter::default() }), vec!["blog", "home"]);
+        assert_eq!(filtered(BadgeFilter { namespace: Some("docs".to_string()), ..BadgeFilter::default() }), vec!["docs/guide"]);
+        assert_eq!(filtered(BadgeFilter { query: Some("PROJECT".to_string()), ..BadgeFilter::default() }), vec!["blog"]);
+        assert_eq!(filtered(BadgeFilter { query: Some("docs".to_string()), ..BadgeFilter::default() }), Vec::<String>::new());
+        assert_eq!(filtered(BadgeFilter { min_count: Some(4), ..BadgeFilter::default() }), vec!["home"]);
+        assert_eq!(filtered(BadgeFilter { inactive_since: Some(at("2024-03-02T00:00:00Z")), ..BadgeFilter::default() }), vec!["home"]);
+        assert_eq!(filtered(BadgeFilter { tag: Some("public".to_string()), ..BadgeFilter::default() }), vec!["blog"]);
+        assert_eq!(filtered(BadgeFilter { owner: Some("web-team".to_string()), ..BadgeFilter::default() }), vec!["blog"]);
+    }
+
+    fn keys(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn bulk_operations_change_nothing_unless_every_badge_can_change() {
+        let counters = PersistentCounterMap::in_memory().with_clock(ManualClock::at("2024-03-01T12:00:00Z"));
+        counters.create_badge("home", Some(5));
+        counters.create_badge("blog", Some(2));
+
+        let (applied, results) = counters.bulk(&keys(&["home", "missing"]), &BulkOperation::Reset);
+        assert!(!applied);
+        assert!(results[0].ok && results[0].new_count.is_none());
+        assert_eq!(results[1].error.as_deref(), Some("badge not found"));
+        assert_eq!(counters.get("home"), 5);
+
+        let (applied, results) = counters.bulk(&keys(&["home", "blog", "home"]), &BulkOperation::AddOffset { offset: -3 });
+        assert!(applied);
+        let counts: Vec<_> = results.iter().map(|result| (result.name.as_str(), result.old_count, result.new_count)).collect();
+        assert_eq!(counts, vec![("home", Some(5), Some(2)), ("blog", Some(2), Some(0))]);
+
+        counters.bulk(&keys(&["home"]), &BulkOperation::AddOffset { offset: i64::MAX });
+        counters.bulk(&keys(&["home"]), &BulkOperation::AddOffset { offset: i64::MAX });
+        assert_eq!(counters.get("home"), u64::MAX);
+
+        let (applied, _) = counters.bulk(&keys(&["home", "blog"]), &BulkOperation::Delete);
+        assert!(applied);
+        assert_eq!(counters.get_trash().len(), 2);
+        assert!(counters.get_all_badges().is_empty());
+    }
+
+    #[test]
+    fn bulk_tags_are_added_and_removed() {
+        let counters = PersistentCounterMap::in_memory().with_clock(ManualClock::at("2024-03-01T12:00:00Z"));
+        counters.create_badge("home", None);
+        counters.add_alias("start", "home").unwrap();
+
+        counters.bulk(&keys(&["start"]), &BulkOperation::Tag { tags: keys(&["public", "docs"]) });
+        assert_eq!(counters.get_badge("home").unwrap().tags, vec!["docs", "public"]);
+        counters.bulk(&keys(&["home"]), &BulkOperation::Untag { tags: keys(&["docs", "other"]) });
+        assert_eq!(counters.get_badge("home").unwrap().tags, vec!["public"]);
+    }
+
+    #[test]
+    fn bulk_moves_take_aliases_along_and_respect_quotas() {
+        let quotas = HashMap::from([("docs".to_string(), 2)]);
+        let counters = PersistentCounterMap::in_memory()
+            .with_clock(ManualClock::at("2024-03-01T12:00:00Z"))
+            .with_namespace_quotas(quotas);
+        for name in ["home", "blog", "faq", "docs/faq"] {
+            counters.create_badge(name, Some(1));
         }
+        counters.add_alias("start", "home").unwrap();
+
+        let (applied, results) = counters.bulk(&keys(&["home", "faq"]), &BulkOperation::Move { namespace: "docs".to_string() });
+        assert!(!applied);
+        assert_eq!(results[1].error.as_deref(), Some("a badge or alias with this name exists in the namespace"));
+
+        let (applied, results) = counters.bulk(&keys(&["docs/faq"]), &BulkOperation::Move { namespace: "docs".to_string() });
+        assert!(!applied);
+        assert_eq!(results[0].error.as_deref(), Some("the badge is already in this namespace"));
+
+        let (applied, results) = counters.bulk(&keys(&["home", "blog"]), &BulkOperation::Move { namespace: "docs".to_string() });
+        assert!(!applied);
+        assert_eq!(results[1].error.as_deref(), Some("the namespace has reached its counter quota"));
+
+        let (applied, _) = counters.bulk(&keys(&["start"]), &BulkOperation::Move { namespace: "docs".to_string() });
+        assert!(applied);
+        assert!(!counters.name_in_use("home") && !counters.name_in_use("start"));
+        assert_eq!(counters.resolve("docs/start"), "docs/home");
+        assert_eq!(counters.get("docs/home"), 1);
     }
 }
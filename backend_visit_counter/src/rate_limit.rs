@@ -0,0 +1,100 @@
+// backend_visit_counter/src/rate_limit.rs
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::Request;
+
+use crate::clock::SharedClock;
+
+/// Length of a rate-limit window, in seconds.
+const WINDOW_SECS: u64 = 60;
+
+/// Fixed-window limiter counting requests per client IP.
+pub struct RateLimiter {
+    per_minute: u32,
+    clock: SharedClock,
+    windows: Mutex<HashMap<IpAddr, (u64, u32)>>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter allowing `per_minute` requests per client. `0` allows everything.
+    pub fn new(per_minute: u32, clock: SharedClock) -> Self {
+        RateLimiter {
+            per_minute,
+            clock,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a request from `ip` and returns whether it is within the limit.
+    pub fn check(&self, ip: IpAddr) -> bool {
+        if self.per_minute == 0 {
+            return true;
+        }
+
+        let window = self.clock.now().timestamp().max(0) as u64 / WINDOW_SECS;
+        let mut windows = self.windows.lock().unwrap();
+
+        // Forget clients from previous windows so the map does not grow without bound.
+        if windows.len() > 10_000 {
+            windows.retain(|_, (w, _)| *w == window);
+        }
+
+        let entry = windows.entry(ip).or_insert((window, 0));
+        if entry.0 != window {
+            *entry = (window, 0);
+        }
+        entry.1 += 1;
+        entry.1 <= self.per_minute
+    }
+}
+
+/// Request guard rejecting clients over the configured rate with 429 Too Many Requests.
+pub struct RateLimited;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RateLimited {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let (Some(limiter), Some(ip)) = (req.rocket().state::<RateLimiter>(), req.client_ip()) else {
+            return Outcome::Success(RateLimited);
+        };
+
+        if limiter.check(ip) {
+            Outcome::Success(RateLimited)
+        } else {
+            Outcome::Error((Status::TooManyRequests, ()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use chrono::Duration;
+
+    use super::*;
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn limit_resets_with_the_next_window() {
+        let clock = ManualClock::at("2024-01-01T00:00:00Z");
+        let limiter = RateLimiter::new(2, clock.clone());
+        let client = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let other = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+
+        assert!(limiter.check(client));
+        clock.advance(Duration::seconds(59));
+        assert!(limiter.check(client));
+        assert!(!limiter.check(client));
+        assert!(limiter.check(other));
+
+        clock.advance(Duration::seconds(1));
+        assert!(limiter.check(client));
+    }
+}
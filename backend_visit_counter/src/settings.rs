@@ -0,0 +1,439 @@
+// backend_visit_counter/src/settings.rs
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use rocket::figment::providers::Env;
+use rocket::figment::Figment;
+use rocket::serde::{Deserialize, Serialize};
+
+use crate::models::SvgOptions;
+
+/// Where counters are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// JSON files next to `data_path`.
+    Json,
+    /// In memory only, lost on restart.
+    Memory,
+}
+
+/// Which counters a first hit may create.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum CreationMode {
+    /// Any valid name is created on its first hit.
+    #[default]
+    Open,
+    /// Only counters created by an admin, or matching `creation.patterns`, are counted.
+    Allowlist,
+}
+
+/// Creation of counters by public routes.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct CreationSettings {
+    pub mode: CreationMode,
+    /// Names a first hit may still create in allowlist mode. `*` matches any run of characters.
+    pub patterns: Vec<String>,
+    /// Number of counters beyond which first hits no longer create new ones. `0` means no limit.
+    pub max_counters: usize,
+    /// Value shown by the SVG badge of a counter that does not exist and cannot be created.
+    pub unknown_text: String,
+}
+
+impl Default for CreationSettings {
+    fn default() -> Self {
+        CreationSettings {
+            mode: CreationMode::Open,
+            patterns: Vec::new(),
+            max_counters: 0,
+            unknown_text: "unknown".to_string(),
+        }
+    }
+}
+
+impl CreationSettings {
+    /// Whether the mode and patterns let a first hit create the counter `name`.
+    pub fn permits(&self, name: &str) -> bool {
+        match self.mode {
+            CreationMode::Open => true,
+            CreationMode::Allowlist => self.patterns.iter().any(|pattern| matches_pattern(pattern, name)),
+        }
+    }
+
+    /// Whether `existing` counters leave room for another one.
+    pub fn has_room(&self, existing: usize) -> bool {
+        self.max_counters == 0 || existing < self.max_counters
+    }
+}
+
+/// Matches `name` against a pattern where `*` stands for any run of characters.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<_> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No `*` in the pattern
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Per-client limits applied to the routes that increment counters.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct RateLimitSettings {
+    /// Maximum increments per client IP and minute. `0` disables the limit.
+    pub per_minute: u32,
+}
+
+/// Cross-origin access to the API.
+/// Badge and counter routes are always open to every origin, without credentials.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct CorsSettings {
+    /// Origins allowed to call `/api/admin` and `/api/auth` with credentials.
+    /// Empty restricts them to the origin serving the frontend.
+    pub admin_origins: Vec<String>,
+}
+
+/// Admin sessions and login throttling.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct AuthSettings {
+    /// Lifetime of an admin session token. Tokens can be refreshed before they expire.
+    pub session_ttl_minutes: u32,
+    /// Failed logins allowed per client IP before the backoff delay starts.
+    pub free_login_attempts: u32,
+    /// Failed logins after which a client IP is locked out.
+    pub lockout_attempts: u32,
+    /// Duration of a lockout, and upper bound of the backoff delay.
+    pub lockout_minutes: u32,
+}
+
+impl Default for AuthSettings {
+    fn default() -> Self {
+        AuthSettings {
+            session_ttl_minutes: 720,
+            free_login_attempts: 3,
+            lockout_attempts: 10,
+            lockout_minutes: 15,
+        }
+    }
+}
+
+/// Policy applied to counter names in URLs and requests.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct NameSettings {
+    /// Longest accepted counter name.
+    pub max_length: usize,
+    /// Lowercase names, so `My-Repo` and `my-repo` are the same counter.
+    pub case_insensitive: bool,
+}
+
+impl Default for NameSettings {
+    fn default() -> Self {
+        NameSettings {
+            max_length: 64,
+            case_insensitive: false,
+        }
+    }
+}
+
+/// Values used by the SVG endpoint when the query string does not set them.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct BadgeDefaults {
+    pub label: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for BadgeDefaults {
+    fn default() -> Self {
+        BadgeDefaults {
+            label: "Visits".to_string(),
+            width: 150,
+            height: 20,
+        }
+    }
+}
+
+/// Badge defaults of a namespace. Unset values fall back to `badge` and the built-in style.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct BadgeTheme {
+    pub label: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub font_family: Option<String>,
+    pub label_color: Option<String>,
+    pub counter_color: Option<String>,
+    pub background_label: Option<String>,
+    pub background_counter: Option<String>,
+}
+
+impl BadgeTheme {
+    /// Fills in the options the query string leaves unset.
+    pub fn apply(&self, options: &mut SvgOptions) {
+        fill(&mut options.label, &self.label);
+        fill(&mut options.width, &self.width);
+        fill(&mut options.height, &self.height);
+        fill(&mut options.font_family, &self.font_family);
+        fill(&mut options.label_color, &self.label_color);
+        fill(&mut options.counter_color, &self.counter_color);
+        fill(&mut options.background_label, &self.background_label);
+        fill(&mut options.background_counter, &self.background_counter);
+    }
+}
+
+fn fill<T: Clone>(option: &mut Option<T>, default: &Option<T>) {
+    if option.is_none() {
+        option.clone_from(default);
+    }
+}
+
+/// A group of counters with its own admins, API keys, quota and badge theme.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct NamespaceSettings {
+    /// Counters the namespace may hold. `0` means no limit.
+    pub max_counters: usize,
+    pub theme: BadgeTheme,
+}
+
+/// Runtime settings, read from `Rocket.toml` and `VC_*` environment variables.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct Settings {
+    /// Counters file. Badge metadata is stored next to it in `<name>_badges.json`.
+    pub data_path: PathBuf,
+    pub storage: StorageBackend,
+    /// Directory of the built frontend. When unset, `/app/frontend` is used if it exists,
+    /// otherwise `../frontend_visit_counter/dist`.
+    pub static_dir: Option<PathBuf>,
+    pub cors: CorsSettings,
+    pub rate_limit: RateLimitSettings,
+    pub auth: AuthSettings,
+    pub badge: BadgeDefaults,
+    pub names: NameSettings,
+    pub creation: CreationSettings,
+    /// Namespaces besides `default`, by name. `default` may be listed to give it a quota or theme.
+    pub namespaces: BTreeMap<String, NamespaceSettings>,
+    /// Days deleted badges stay in the trash before being purged. `0` keeps them until purged by hand.
+    pub trash_retention_days: u32,
+    /// Hours URLs signed with a replaced secret keep counting after a rotation.
+    pub signature_grace_hours: u32,
+    /// Number of counters exported with their own series on `/metrics`.
+    pub metrics_max_counters: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            data_path: PathBuf::from("/data/counters.json"),
+            storage: StorageBackend::Json,
+            static_dir: None,
+            cors: CorsSettings::default(),
+            rate_limit: RateLimitSettings::default(),
+            auth: AuthSettings::default(),
+            badge: BadgeDefaults::default(),
+            names: NameSettings::default(),
+            creation: CreationSettings::default(),
+            namespaces: BTreeMap::new(),
+            trash_retention_days: 30,
+            signature_grace_hours: 24,
+            metrics_max_counters: crate::metrics::DEFAULT_MAX_COUNTER_SERIES,
+        }
+    }
+}
+
+/// A configuration value that cannot be used.
+#[derive(Debug)]
+pub enum SettingsError {
+    /// The configuration sources could not be parsed.
+    Parse(Box<rocket::figment::Error>),
+    /// A setting has an unusable value.
+    Invalid { key: &'static str, reason: String },
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Parse(e) => write!(f, "cannot parse settings: {}", e),
+            SettingsError::Invalid { key, reason } => write!(f, "invalid `{}`: {}", key, reason),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+/// Rocket's own configuration (`Rocket.toml`, `ROCKET_*`) merged with `VC_*` environment variables.
+/// Nested keys use a double underscore, e.g. `VC_RATE_LIMIT__PER_MINUTE=60`.
+pub fn figment() -> Figment {
+    rocket::Config::figment().merge(Env::prefixed("VC_").split("__").global())
+}
+
+impl Settings {
+    /// Extracts and validates the settings from a figment.
+    pub fn from_figment(figment: &Figment) -> Result<Self, SettingsError> {
+        let settings: Settings = figment.extract().map_err(|e| SettingsError::Parse(Box::new(e)))?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Checks values that parse but cannot work.
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if self.storage == StorageBackend::Json {
+            if self.data_path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                return Err(invalid("data_path", format!("{} must be a .json file", self.data_path.display())));
+            }
+            let parent = self.data_path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            if !parent.is_dir() {
+                return Err(invalid("data_path", format!("directory {} does not exist", parent.display())));
+            }
+        }
+
+        if let Some(dir) = &self.static_dir {
+            if !dir.is_dir() {
+                return Err(invalid("static_dir", format!("directory {} does not exist", dir.display())));
+            }
+        }
+
+        for origin in &self.cors.admin_origins {
+            let valid = origin.starts_with("http://") || origin.starts_with("https://");
+            if !valid || origin.ends_with('/') {
+                return Err(invalid("cors.admin_origins", format!("{} must look like https://example.com", origin)));
+            }
+        }
+
+        if self.auth.session_ttl_minutes == 0 {
+            return Err(invalid("auth.session_ttl_minutes", "must be greater than 0".to_string()));
+        }
+        if self.auth.lockout_attempts <= self.auth.free_login_attempts {
+            return Err(invalid("auth.lockout_attempts", "must be greater than auth.free_login_attempts".to_string()));
+        }
+
+        if self.badge.width == 0 || self.badge.height == 0 {
+            return Err(invalid("badge", "width and height must be greater than 0".to_string()));
+        }
+
+        for namespace in self.namespaces.keys() {
+            let valid_chars = namespace.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+            if namespace.is_empty() || namespace.len() > 64 || !valid_chars {
+                return Err(invalid("namespaces", format!("{} must be 1-64 ASCII letters, digits, '-' or '_'", namespace)));
+            }
+        }
+
+        if !(1..=256).contains(&self.names.max_length) {
+            return Err(invalid("names.max_length", "must be between 1 and 256".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Settings of a namespace, or `None` if it is not configured. The default namespace always exists.
+    pub fn namespace(&self, name: &str) -> Option<NamespaceSettings> {
+        match self.namespaces.get(name) {
+            Some(namespace) => Some(namespace.clone()),
+            None if name == crate::namespaces::DEFAULT_NAMESPACE => Some(NamespaceSettings::default()),
+            None => None,
+        }
+    }
+
+    /// Path of an auxiliary JSON file stored next to `data_path`, e.g. `counters_api_keys.json`
+    /// for `suffix = "api_keys"`. `None` with the memory backend.
+    pub fn data_file(&self, suffix: &str) -> Option<String> {
+        self.sibling_file(suffix, "json")
+    }
+
+    /// Like `data_file`, for append-only logs with one JSON object per line (`counters_<suffix>.jsonl`).
+    pub fn log_file(&self, suffix: &str) -> Option<String> {
+        self.sibling_file(suffix, "jsonl")
+    }
+
+    fn sibling_file(&self, suffix: &str, extension: &str) -> Option<String> {
+        match self.storage {
+            StorageBackend::Json => Some(self.data_path.to_string_lossy().replace(".json", &format!("_{}.{}", suffix, extension))),
+            StorageBackend::Memory => None,
+        }
+    }
+
+    /// Directory served at `/`.
+    pub fn static_dir(&self) -> PathBuf {
+        match &self.static_dir {
+            Some(dir) => dir.clone(),
+            None if Path::new("/app/frontend").exists() => PathBuf::from("/app/frontend"), // Production Docker path
+            None => PathBuf::from("../frontend_visit_counter/dist"), // Development path
+        }
+    }
+}
+
+fn invalid(key: &'static str, reason: String) -> SettingsError {
+    SettingsError::Invalid { key, reason }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_match_whole_names() {
+        let cases = [
+            ("home", "home", true),
+            ("home", "homepage", false),
+            ("docs-*", "docs-intro", true),
+            ("docs-*", "docs-", true),
+            ("docs-*", "blog-docs-intro", false),
+            ("*-2024", "launch-2024", true),
+            ("*-2024", "launch-2024-b", false),
+            ("a*b*c", "axxbyyc", true),
+            ("a*b*c", "acb", false),
+            ("a*a", "a", false),
+            ("*", "anything", true),
+            ("docs/*", "docs/home", true),
+            ("docs/*", "home", false),
+        ];
+        for (pattern, name, expected) in cases {
+            assert_eq!(matches_pattern(pattern, name), expected, "`{}` against `{}`", pattern, name);
+        }
+    }
+
+    #[test]
+    fn allowlists_only_permit_matching_names() {
+        let open = CreationSettings::default();
+        assert!(open.permits("anything"));
+
+        let allowlist = CreationSettings {
+            mode: CreationMode::Allowlist,
+            patterns: vec!["docs-*".to_string(), "home".to_string()],
+            ..CreationSettings::default()
+        };
+        assert!(allowlist.permits("home"));
+        assert!(allowlist.permits("docs-intro"));
+        assert!(!allowlist.permits("blog"));
+        assert!(!CreationSettings { patterns: Vec::new(), ..allowlist }.permits("home"));
+    }
+
+    #[test]
+    fn max_counters_of_zero_is_unlimited() {
+        let unlimited = CreationSettings::default();
+        assert!(unlimited.has_room(1_000_000));
+
+        let capped = CreationSettings { max_counters: 2, ..CreationSettings::default() };
+        assert!(capped.has_room(1));
+        assert!(!capped.has_room(2));
+    }
+}
@@ -0,0 +1,87 @@
+// backend_visit_counter/src/signing.rs
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use rocket::http::uri::Query;
+use subtle::ConstantTimeEq;
+use visit_counter_types::signing::{canonical_message, sign, SIGNATURE_PARAM};
+
+use crate::models::SigningKeys;
+
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Checks the `sig` parameter of a badge URL against the current secret,
+/// or the previous one while its grace period lasts.
+pub fn verify(keys: &SigningKeys, name: &str, query: Option<Query<'_>>, now: DateTime<Utc>) -> bool {
+    let params: Vec<(&str, &str)> = query.map(|query| query.segments().collect()).unwrap_or_default();
+    let Some(signature) = params.iter().find(|(key, _)| *key == SIGNATURE_PARAM).map(|(_, value)| *value) else {
+        return false;
+    };
+
+    let message = canonical_message(name, params.iter().copied());
+    keys.valid_secrets(now)
+        .any(|secret| bool::from(sign(secret, &message).as_bytes().ct_eq(signature.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use rocket::http::uri::Origin;
+
+    use crate::clock::{Clock, ManualClock};
+
+    fn keys(clock: &ManualClock) -> SigningKeys {
+        SigningKeys {
+            secret: "current".to_string(),
+            previous: Some("previous".to_string()),
+            previous_expires_at: Some(clock.now() + Duration::hours(24)),
+        }
+    }
+
+    fn signed_uri(secret: &str, name: &str) -> String {
+        let signature = sign(secret, &canonical_message(name, [("label", "Views and more"), ("width", "200")]));
+        format!("/counter/{}/svg?width=200&label=Views%20and%20more&sig={}", name, signature)
+    }
+
+    fn verify_uri(keys: &SigningKeys, name: &str, uri: &str, now: DateTime<Utc>) -> bool {
+        verify(keys, name, Origin::parse(uri).unwrap().query(), now)
+    }
+
+    #[test]
+    fn urls_signed_with_the_current_secret_are_accepted() {
+        let clock = ManualClock::at("2024-01-01T08:00:00Z");
+        let keys = keys(&clock);
+        assert!(verify_uri(&keys, "home", &signed_uri("current", "home"), clock.now()));
+        assert!(verify_uri(&keys, "docs/home", &signed_uri("current", "docs/home"), clock.now()));
+    }
+
+    #[test]
+    fn wrong_or_missing_signatures_are_rejected() {
+        let clock = ManualClock::at("2024-01-01T08:00:00Z");
+        let keys = keys(&clock);
+        assert!(!verify_uri(&keys, "home", &signed_uri("other", "home"), clock.now()));
+        assert!(!verify_uri(&keys, "blog", &signed_uri("current", "home"), clock.now()));
+        assert!(!verify_uri(&keys, "home", &signed_uri("current", "home").replace("width=200", "width=300"), clock.now()));
+        assert!(!verify_uri(&keys, "home", "/counter/home/svg?width=200", clock.now()));
+        assert!(!verify(&keys, "home", None, clock.now()));
+    }
+
+    #[test]
+    fn the_previous_secret_is_accepted_until_its_grace_period_ends() {
+        let clock = ManualClock::at("2024-01-01T08:00:00Z");
+        let keys = keys(&clock);
+        let uri = signed_uri("previous", "home");
+        assert!(verify_uri(&keys, "home", &uri, clock.now()));
+
+        clock.advance(Duration::hours(24) - Duration::seconds(1));
+        assert!(verify_uri(&keys, "home", &uri, clock.now()));
+
+        clock.advance(Duration::seconds(1));
+        assert!(!verify_uri(&keys, "home", &uri, clock.now()));
+        assert!(verify_uri(&keys, "home", &signed_uri("current", "home"), clock.now()));
+    }
+}
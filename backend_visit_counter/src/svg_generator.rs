@@ -1,12 +1,41 @@
 // backend_visit_counter/src/svg_generator.rs
 use crate::models::SvgOptions;
 
+/// Stylesheet every badge starts from; `build_custom_css` overrides its variables.
+pub const BASE_CSS: &str = include_str!("../../assets/style.css");
+
+/// Whether XML 1.0 allows `c` at all, even escaped.
+fn is_xml_char(c: char) -> bool {
+  matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..)
+}
+
+/// Escapes `text` for element content and attribute values, dropping characters XML does not allow.
+fn escape_xml(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars().filter(|c| is_xml_char(*c)) {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&apos;"),
+      c => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+/// Makes `text` safe inside a CDATA section: a `]]>` would end it early, so it is split across two sections.
+fn escape_cdata(text: &str) -> String {
+  text.chars().filter(|c| is_xml_char(*c)).collect::<String>().replace("]]>", "]]]]><![CDATA[>")
+}
+
 /// Generates an SVG counter image.
 /// * `label` - The label to display on the left side.
-/// * `count` - The counter value to display on the right side.
+/// * `value` - The counter value, or other text, to display on the right side.
 /// * `css`   - The CSS to embed in the SVG.
 /// * `options` - Optional parameters for customization.
-pub fn generate_svg(label: &str, count: u64, css: &str, width: u32, height: u32, options: Option<&crate::models::SvgOptions>) -> String {
+pub fn generate_svg(label: &str, value: &str, css: &str, width: u32, height: u32, options: Option<&crate::models::SvgOptions>) -> String {
   let has_border = options
     .and_then(|opts| opts.border_width)
     .map(|w| w > 0)
@@ -18,7 +47,7 @@ pub fn generate_svg(label: &str, count: u64, css: &str, width: u32, height: u32,
     .unwrap_or(false);
 
   let logo_element = if has_logo {
-    let logo_url = options.unwrap().logo_url.as_ref().unwrap();
+    let logo_url = escape_xml(options.unwrap().logo_url.as_ref().unwrap());
 
     // Calculate logo dimensions based on height with padding
     let logo_size = if height > 20 {
@@ -26,7 +55,6 @@ pub fn generate_svg(label: &str, count: u64, css: &str, width: u32, height: u32,
     } else {
         (height as f32 * 0.8).round() as u32
     };
-    let logo_margin = (height - logo_size) / 2;
 
     // Logo will be positioned using CSS variables (--logo-offset-x) like text
     format!("<rect class=\"logo-rect\"/><image href=\"{}\" xlink:href=\"{}\" class=\"logo-image\" width=\"{}\" height=\"{}\" preserveAspectRatio=\"xMidYMid meet\"/>", logo_url, logo_url, logo_size, logo_size)
@@ -77,9 +105,9 @@ r##"<?xml version="1.0" encoding="UTF-8"?>
 </svg>"##,
       width = width,
       height = height,
-      css = css,
-      label = label,
-      count = count,
+      css = escape_cdata(css),
+      label = escape_xml(label),
+      count = escape_xml(value),
       border_element = border_element,
       logo_element = logo_element
   )
@@ -125,41 +153,38 @@ pub fn build_custom_css(options: Option<SvgOptions>) -> String {
 
           // Calculate section widths based on element positions
           if opts.label_width.is_none() && opts.counter_width.is_none() {
-              let positions = opts.element_positions.as_ref()
-                  .map(|s| s.as_str())
+              let positions = opts.element_positions.as_deref()
                   .unwrap_or("label,logo,counter");
               let elements: Vec<&str> = positions.split(',').map(|s| s.trim()).collect();
 
               // Calculate widths: if logo present, make it smaller, distribute rest between label/counter
-              let has_logo = elements.contains(&"logo") && opts.logo_url.as_ref().map_or(false, |url| !url.is_empty());
+              let has_logo = elements.contains(&"logo") && opts.logo_url.as_ref().is_some_and(|url| !url.is_empty());
               let logo_width = if has_logo { opts.logo_width.unwrap_or(30) } else { 0 };
 
-              let remaining_width = width - logo_width;
-              let text_sections = if has_logo { 2 } else { 2 }; // label + counter
+              let remaining_width = width.saturating_sub(logo_width);
+              let text_sections = 2; // label + counter
               let section_width = remaining_width / text_sections;
 
-              // Set section widths and positions based on element order
+              // Set section widths and positions based on element order (saturating, elements may repeat)
               let mut current_x = 0u32;
-              for (i, element) in elements.iter().enumerate() {
+              for element in &elements {
                   match *element {
                       "label" => {
                           let section_w = if has_logo { section_width } else { (width as f32 * 0.667).round() as u32 };
                           custom_css.push_str(&format!("  --label-width: {}px;\n", section_w));
-                          custom_css.push_str(&format!("  --label-offset-x: {}px;\n", current_x + section_w / 2));
-                          current_x += section_w;
+                          custom_css.push_str(&format!("  --label-offset-x: {}px;\n", current_x.saturating_add(section_w / 2)));
+                          current_x = current_x.saturating_add(section_w);
                       },
-                      "logo" => {
-                          if has_logo {
-                              custom_css.push_str(&format!("  --logo-width: {}px;\n", logo_width));
-                              custom_css.push_str(&format!("  --logo-offset-x: {}px;\n", current_x + logo_width / 2));
-                              current_x += logo_width;
-                          }
+                      "logo" if has_logo => {
+                          custom_css.push_str(&format!("  --logo-width: {}px;\n", logo_width));
+                          custom_css.push_str(&format!("  --logo-offset-x: {}px;\n", current_x.saturating_add(logo_width / 2)));
+                          current_x = current_x.saturating_add(logo_width);
                       },
                       "counter" => {
                           let section_w = if has_logo { section_width } else { width - (width as f32 * 0.667).round() as u32 };
                           custom_css.push_str(&format!("  --counter-width: {}px;\n", section_w));
-                          custom_css.push_str(&format!("  --counter-offset-x: {}px;\n", current_x + section_w / 2));
-                          current_x += section_w;
+                          custom_css.push_str(&format!("  --counter-offset-x: {}px;\n", current_x.saturating_add(section_w / 2)));
+                          current_x = current_x.saturating_add(section_w);
                       },
                       _ => {}
                   }
@@ -177,11 +202,7 @@ pub fn build_custom_css(options: Option<SvgOptions>) -> String {
 
       push_prop(&mut custom_css, "--height", opts.height, to_px);
       // Handle main radius (affects the inner badge shape)
-      if let Some(radius) = opts.radius {
-          custom_css.push_str(&format!("  --radius: {}px;\n", radius));
-          // Only affect the mask rect (inner badge shape), not border
-          custom_css.push_str(&format!(".mask-rect {{ rx: {}px; ry: {}px; }}\n", radius, radius));
-      }
+      push_prop(&mut custom_css, "--radius", opts.radius, to_px);
 
       // Gradient Settings
       push_prop(&mut custom_css, "--grad-stop1-color", opts.grad_stop1_color, normalize_color);
@@ -235,8 +256,13 @@ pub fn build_custom_css(options: Option<SvgOptions>) -> String {
 
       custom_css.push_str("}\n");
 
+      // Only affect the mask rect (inner badge shape), not border
+      if let Some(radius) = opts.radius {
+          custom_css.push_str(&format!(".mask-rect {{ rx: {}px; ry: {}px; }}\n", radius, radius));
+      }
+
       // Add CSS for logo section positioning and styling
-      if opts.logo_url.as_ref().map_or(false, |url| !url.is_empty()) {
+      if opts.logo_url.as_ref().is_some_and(|url| !url.is_empty()) {
           custom_css.push_str(&format!(r#"
 .logo-rect {{
   width: var(--logo-width, 30px);
@@ -268,7 +294,7 @@ pub fn build_custom_css(options: Option<SvgOptions>) -> String {
           custom_css.push_str(&format!(".mask-rect {{ rx: {}px; ry: {}px; }}\n", inner_radius, inner_radius));
       } else if has_border {
           // Default border radius when border existsbut no radius specified
-          custom_css.push_str(&format!(".border-rect {{ rx: 3px; ry: 3px; }}\n"));
+          custom_css.push_str(".border-rect { rx: 3px; ry: 3px; }\n");
           let border_width = opts.border_width.unwrap_or(1) as f32;
           let inner_radius = if 3.0 > border_width * 0.5 {
               (3.0 - (border_width * 0.5)).round() as u32
@@ -0,0 +1,287 @@
+// backend_visit_counter/src/users.rs
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use rocket::http::Status;
+
+use crate::clock::SharedClock;
+use crate::auth::{hash_password, reject_password, verify_password, AdminCredentials};
+use crate::models::{Role, UserRecord, UserResponse};
+
+/// Username of the owner account backed by `ADMIN_PASSWORD_HASH`.
+pub const BOOTSTRAP_USERNAME: &str = "admin";
+
+/// Why a user operation was refused.
+#[derive(Debug)]
+pub enum UserError {
+    NotFound,
+    AlreadyExists,
+    /// The operation would leave no stored owner of every namespace.
+    LastOwner,
+    /// Usernames are 1-64 letters, digits, '-', '_' or '.', and passwords at least 8 characters.
+    Invalid,
+    Hash(String),
+}
+
+impl From<UserError> for Status {
+    fn from(error: UserError) -> Self {
+        match error {
+            UserError::NotFound => Status::NotFound,
+            UserError::AlreadyExists | UserError::LastOwner => Status::Conflict,
+            UserError::Invalid => Status::BadRequest,
+            UserError::Hash(e) => {
+                eprintln!("Failed to hash password: {}", e);
+                Status::InternalServerError
+            }
+        }
+    }
+}
+
+/// Admin users, persisted with their password hashes in a JSON file.
+/// The password from the environment logs in as the `admin` owner until a stored user takes that name.
+pub struct UserStore {
+    users: Mutex<HashMap<String, UserRecord>>,
+    path: Option<String>,
+    bootstrap: AdminCredentials,
+    clock: SharedClock,
+}
+
+impl UserStore {
+    /// Loads the users stored at `path`, or starts empty. `None` keeps them in memory only.
+    pub fn new(path: Option<String>, bootstrap: AdminCredentials, clock: SharedClock) -> Self {
+        let users = path.as_deref()
+            .filter(|path| Path::new(path).exists())
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        UserStore {
+            users: Mutex::new(users),
+            path,
+            bootstrap,
+            clock,
+        }
+    }
+
+    /// Checks a username and password, returning the role of the user.
+    /// Unknown usernames still run a password check, so they take as long as wrong passwords.
+    pub fn verify(&self, username: &str, password: &str) -> Option<Role> {
+        let stored = self.users.lock().unwrap().get(username).cloned();
+        match stored {
+            Some(user) => verify_password(&user.password_hash, password).then_some(user.role),
+            None if username == BOOTSTRAP_USERNAME => self.bootstrap.verify(password).then_some(Role::Owner),
+            None => {
+                reject_password(password);
+                None
+            }
+        }
+    }
+
+    /// Current role and namespace of a user, or `None` if it no longer exists.
+    /// A `None` namespace gives access to every namespace.
+    pub fn access_of(&self, username: &str) -> Option<(Role, Option<String>)> {
+        match self.users.lock().unwrap().get(username) {
+            Some(user) => Some((user.role, user.namespace.clone())),
+            None if username == BOOTSTRAP_USERNAME => self.bootstrap.is_configured().then_some((Role::Owner, None)),
+            None => None,
+        }
+    }
+
+    /// Lists the stored users, oldest first. `Some(namespace)` only lists the users of that namespace.
+    pub fn list(&self, namespace: Option<&str>) -> Vec<UserResponse> {
+        let users = self.users.lock().unwrap();
+        let mut list: Vec<_> = users.values()
+            .filter(|user| namespace.is_none_or(|namespace| user.namespace.as_deref() == Some(namespace)))
+            .map(to_response)
+            .collect();
+        list.sort_by_key(|user| user.created_at);
+        list
+    }
+
+    /// Details of a stored user.
+    pub fn get(&self, username: &str) -> Option<UserResponse> {
+        self.users.lock().unwrap().get(username).map(to_response)
+    }
+
+    pub fn create(&self, username: &str, password: &str, role: Role, namespace: Option<String>) -> Result<UserResponse, UserError> {
+        validate_username(username)?;
+        validate_password(password)?;
+        let password_hash = hash_password(password).map_err(UserError::Hash)?;
+
+        let record = {
+            let mut users = self.users.lock().unwrap();
+            if users.contains_key(username) {
+                return Err(UserError::AlreadyExists);
+            }
+            let record = UserRecord {
+                username: username.to_string(),
+                password_hash,
+                role,
+                namespace,
+                created_at: self.clock.now(),
+            };
+            users.insert(username.to_string(), record.clone());
+            record
+        };
+        self.save();
+
+        Ok(to_response(&record))
+    }
+
+    /// Changes the role and/or password of a user.
+    pub fn update(&self, username: &str, role: Option<Role>, password: Option<&str>) -> Result<UserResponse, UserError> {
+        let password_hash = match password {
+            Some(password) => {
+                validate_password(password)?;
+                Some(hash_password(password).map_err(UserError::Hash)?)
+            }
+            None => None,
+        };
+
+        let record = {
+            let mut users = self.users.lock().unwrap();
+            let current = users.get(username).ok_or(UserError::NotFound)?;
+            if let Some(role) = role {
+                if is_global_owner(current) && role != Role::Owner && owner_count(&users) == 1 {
+                    return Err(UserError::LastOwner);
+                }
+            }
+
+            let user = users.get_mut(username).ok_or(UserError::NotFound)?;
+            if let Some(role) = role {
+                user.role = role;
+            }
+            if let Some(password_hash) = password_hash {
+                user.password_hash = password_hash;
+            }
+            user.clone()
+        };
+        self.save();
+
+        Ok(to_response(&record))
+    }
+
+    pub fn delete(&self, username: &str) -> Result<(), UserError> {
+        {
+            let mut users = self.users.lock().unwrap();
+            let user = users.get(username).ok_or(UserError::NotFound)?;
+            if is_global_owner(user) && owner_count(&users) == 1 {
+                return Err(UserError::LastOwner);
+            }
+            users.remove(username);
+        }
+        self.save();
+        Ok(())
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let users = self.users.lock().unwrap();
+        if let Ok(content) = serde_json::to_string_pretty(&*users) {
+            if let Err(e) = std::fs::write(path, content) {
+                eprintln!("Failed to save users: {}", e);
+            }
+        }
+    }
+}
+
+fn is_global_owner(user: &UserRecord) -> bool {
+    user.role == Role::Owner && user.namespace.is_none()
+}
+
+fn owner_count(users: &HashMap<String, UserRecord>) -> usize {
+    users.values().filter(|user| is_global_owner(user)).count()
+}
+
+fn validate_username(username: &str) -> Result<(), UserError> {
+    let valid_chars = username.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if username.is_empty() || username.len() > 64 || !valid_chars {
+        return Err(UserError::Invalid);
+    }
+    Ok(())
+}
+
+fn validate_password(password: &str) -> Result<(), UserError> {
+    if password.chars().count() < 8 {
+        return Err(UserError::Invalid);
+    }
+    Ok(())
+}
+
+fn to_response(record: &UserRecord) -> UserResponse {
+    UserResponse {
+        username: record.username.clone(),
+        role: record.role,
+        namespace: record.namespace.clone(),
+        created_at: record.created_at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::hash_password;
+    use crate::clock::ManualClock;
+
+    const PASSWORD: &str = "long enough";
+
+    fn store() -> UserStore {
+        let bootstrap = AdminCredentials::from_hash(hash_password(PASSWORD).unwrap()).unwrap();
+        UserStore::new(None, bootstrap, ManualClock::at("2024-01-01T08:00:00Z"))
+    }
+
+    #[test]
+    fn the_bootstrap_owner_logs_in_until_a_stored_user_takes_its_name() {
+        let users = store();
+        assert_eq!(users.verify(BOOTSTRAP_USERNAME, PASSWORD), Some(Role::Owner));
+        assert_eq!(users.access_of(BOOTSTRAP_USERNAME), Some((Role::Owner, None)));
+        assert_eq!(users.verify("nobody", PASSWORD), None);
+
+        users.create(BOOTSTRAP_USERNAME, "another password", Role::Editor, None).unwrap();
+        assert_eq!(users.verify(BOOTSTRAP_USERNAME, PASSWORD), None);
+        assert_eq!(users.verify(BOOTSTRAP_USERNAME, "another password"), Some(Role::Editor));
+    }
+
+    #[test]
+    fn usernames_and_passwords_are_validated() {
+        let users = store();
+        for username in ["", "with space", "slash/name", &"a".repeat(65)] {
+            assert!(matches!(users.create(username, PASSWORD, Role::Viewer, None), Err(UserError::Invalid)), "{}", username);
+        }
+        assert!(matches!(users.create("vera", "short", Role::Viewer, None), Err(UserError::Invalid)));
+        users.create("vera.v_2-x", PASSWORD, Role::Viewer, None).unwrap();
+        assert!(matches!(users.create("vera.v_2-x", PASSWORD, Role::Viewer, None), Err(UserError::AlreadyExists)));
+        assert!(matches!(users.update("vera.v_2-x", None, Some("short")), Err(UserError::Invalid)));
+        assert!(matches!(users.update("nobody", Some(Role::Editor), None), Err(UserError::NotFound)));
+    }
+
+    #[test]
+    fn the_last_global_owner_is_kept() {
+        let users = store();
+        users.create("olga", PASSWORD, Role::Owner, None).unwrap();
+        users.create("nora", PASSWORD, Role::Owner, Some("docs".to_string())).unwrap();
+        assert!(matches!(users.update("olga", Some(Role::Editor), None), Err(UserError::LastOwner)));
+        assert!(matches!(users.delete("olga"), Err(UserError::LastOwner)));
+        users.delete("nora").unwrap();
+
+        users.create("otto", PASSWORD, Role::Owner, None).unwrap();
+        users.update("olga", Some(Role::Editor), Some("new password")).unwrap();
+        assert_eq!(users.verify("olga", "new password"), Some(Role::Editor));
+        assert!(matches!(users.delete("otto"), Err(UserError::LastOwner)));
+    }
+
+    #[test]
+    fn namespaces_filter_the_list() {
+        let users = store();
+        users.create("olga", PASSWORD, Role::Owner, None).unwrap();
+        users.create("nora", PASSWORD, Role::Viewer, Some("docs".to_string())).unwrap();
+        assert_eq!(users.list(None).len(), 2);
+        let docs: Vec<_> = users.list(Some("docs")).into_iter().map(|user| user.username).collect();
+        assert_eq!(docs, vec!["nora"]);
+        assert_eq!(users.access_of("nora"), Some((Role::Viewer, Some("docs".to_string()))));
+        assert_eq!(users.access_of("nobody"), None);
+    }
+}
@@ -0,0 +1,510 @@
+// backend_visit_counter/tests/admin.rs
+//! Admin routes: badges, trash, aliases, signing secrets, audit log, export and import, API keys and users.
+mod common;
+
+use backend_visit_counter::models::{AuditAction, AuditListResponse, BadgeListResponse, BadgeResponse, TokenResponse, TrashListResponse};
+use chrono::Duration;
+use common::{bearer, spawn, ADMIN_PASSWORD};
+use rocket::http::{ContentType, Status};
+use serde::de::DeserializeOwned;
+
+#[test]
+fn badges_are_created_read_and_listed() {
+    let app = spawn();
+    let admin = app.admin();
+
+    let (status, badge) = app.post(&admin, "/api/admin/badges", r#"{"name":"home","count":10}"#);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(badge["name"], "home");
+    assert_eq!(badge["count"], 10);
+    assert_eq!(badge["created_at"], "2024-06-01T12:00:00Z");
+    app.create_badge(&admin, "about", 3);
+
+    assert_eq!(app.post(&admin, "/api/admin/badges", r#"{"name":"home"}"#).0, Status::Conflict);
+    let (status, error) = app.post(&admin, "/api/admin/badges", r#"{"name":"no spaces"}"#);
+    assert_eq!(status, Status::BadRequest);
+    assert_eq!(error["error"], "invalid_counter_name");
+
+    app.clock.advance(Duration::days(1));
+    let admin = app.admin();
+    app.client.get("/counter/home/svg").dispatch();
+    let (status, badge) = app.get(&admin, "/api/admin/badges/home");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(badge["count"], 11);
+    assert_eq!(badge["last_accessed"], "2024-06-02T12:00:00Z");
+    assert_eq!(badge["history"], serde_json::json!({"2024-06-02": 1}));
+    assert_eq!(app.get(&admin, "/api/admin/badges/missing").0, Status::NotFound);
+
+    let (status, list) = app.get(&admin, "/api/admin/badges?sort=count&order=asc");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(list["total"], 2);
+    assert_eq!(list["visits"], 14);
+    assert_eq!(list["badges"][0]["name"], "about");
+    let (_, list) = app.get(&admin, "/api/admin/badges?q=hom");
+    assert_eq!(list["total"], 1);
+    let (_, list) = app.get(&admin, "/api/admin/badges?inactive_since=2024-06-02");
+    assert_eq!(list["badges"][0]["name"], "about");
+    assert_eq!(app.get(&admin, "/api/admin/badges?inactive_since=yesterday").0, Status::BadRequest);
+    assert_eq!(app.get(&admin, "/api/admin/badges?ns=nowhere").0, Status::NotFound);
+
+    let (_, namespaces) = app.get(&admin, "/api/admin/namespaces");
+    assert_eq!(namespaces["total"], 2);
+    assert_eq!(namespaces["namespaces"][0], serde_json::json!({"name": "default", "counters": 2, "max_counters": 0}));
+}
+
+/// The response body as one of the types the frontend reads it into.
+fn parse<T: DeserializeOwned>(body: serde_json::Value) -> T {
+    serde_json::from_value(body).expect("response matches the shared type")
+}
+
+#[test]
+fn responses_parse_as_the_shared_types() {
+    let app = spawn();
+    let response = app.client.post("/api/auth/login")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{"password":"{}"}}"#, ADMIN_PASSWORD))
+        .dispatch();
+    let session: TokenResponse = parse(common::json(response));
+    let admin = session.token;
+
+    // Counts past u32::MAX
+    let (_, badge) = app.post(&admin, "/api/admin/badges", r#"{"name":"home","count":5000000000}"#);
+    let badge: BadgeResponse = parse(badge);
+    assert_eq!(badge.count, 5_000_000_000);
+    assert_eq!(badge.created_at, session.expires_at - Duration::hours(12));
+    app.client.get("/counter/home/svg").dispatch();
+
+    let (_, badge) = app.get(&admin, "/api/admin/badges/home");
+    let badge: BadgeResponse = parse(badge);
+    assert_eq!(badge.history.unwrap().values().sum::<u64>(), 1);
+    let (_, list) = app.get(&admin, "/api/admin/badges");
+    let list: BadgeListResponse = parse(list);
+    assert_eq!(list.visits, 5_000_000_001);
+
+    assert_eq!(app.delete(&admin, "/api/admin/badges/home"), Status::NoContent);
+    let (_, trash) = app.get(&admin, "/api/admin/trash");
+    let trash: TrashListResponse = parse(trash);
+    assert_eq!(trash.badges[0].count, 5_000_000_001);
+    let (_, audit) = app.get(&admin, "/api/admin/audit");
+    let audit: AuditListResponse = parse(audit);
+    assert_eq!(audit.entries[0].action, AuditAction::Delete);
+    assert_eq!(audit.entries[0].old_value, Some(5_000_000_001));
+}
+
+#[test]
+fn listed_visits_saturate_instead_of_overflowing() {
+    let app = spawn();
+    let admin = app.admin();
+    app.create_badge(&admin, "home", u64::MAX);
+    app.create_badge(&admin, "about", u64::MAX);
+
+    let (status, list) = app.get(&admin, "/api/admin/badges");
+    assert_eq!(status, Status::Ok);
+    let list: BadgeListResponse = parse(list);
+    assert_eq!(list.visits, u64::MAX);
+}
+
+#[test]
+fn admin_routes_reject_invalid_names() {
+    let app = spawn();
+    let admin = app.admin();
+    app.create_badge(&admin, "home", 1);
+
+    let (status, error) = app.get(&admin, "/api/admin/badges/has%20space");
+    assert_eq!(status, Status::BadRequest);
+    assert_eq!(error["error"], "invalid_counter_name");
+    assert_eq!(app.put(&admin, "/api/admin/badges/no.dots", r#"{"count":1}"#).0, Status::BadRequest);
+    assert_eq!(app.put(&admin, "/api/admin/badges/no.dots/metadata", "{}").0, Status::BadRequest);
+    assert_eq!(app.post(&admin, "/api/admin/badges/no.dots/secret", "").0, Status::BadRequest);
+    assert_eq!(app.delete(&admin, "/api/admin/badges/no.dots"), Status::BadRequest);
+    assert_eq!(app.delete(&admin, "/api/admin/trash/no.dots"), Status::BadRequest);
+    assert_eq!(app.delete(&admin, "/api/admin/aliases/no.dots"), Status::BadRequest);
+    assert_eq!(app.post(&admin, "/api/admin/aliases", r#"{"alias":"start","target":"no.dots"}"#).0, Status::BadRequest);
+    let (status, error) = app.post(&admin, "/api/admin/badges/merge", r#"{"sources":["home","no.dots"],"target":"index"}"#);
+    assert_eq!(status, Status::BadRequest);
+    assert_eq!(error["error"], "invalid_counter_name");
+    assert_eq!(app.get(&admin, "/api/admin/badges/home").1["count"], 1);
+
+    // Counters stored before the policy can still be renamed
+    let app = common::spawn_with(|settings| std::fs::write(&settings.data_path, r#"{"old name":3}"#).unwrap());
+    let admin = app.admin();
+    let (status, badge) = app.post(&admin, "/api/admin/badges/old%20name/rename", r#"{"new_name":"old-name"}"#);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(badge["count"], 3);
+}
+
+#[test]
+fn badges_are_updated() {
+    let app = spawn();
+    let admin = app.admin();
+    app.create_badge(&admin, "home", 1);
+
+    let (status, badge) = app.put(&admin, "/api/admin/badges/home", r#"{"count":99}"#);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(badge["count"], 99);
+    assert_eq!(app.put(&admin, "/api/admin/badges/missing", r#"{"count":1}"#).0, Status::NotFound);
+
+    let metadata = r#"{"description":"Landing page","owner":"web","url":"https://example.com","tags":["site"]}"#;
+    let (status, badge) = app.put(&admin, "/api/admin/badges/home/metadata", metadata);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(badge["owner"], "web");
+    assert_eq!(badge["tags"], serde_json::json!(["site"]));
+    let (status, error) = app.put(&admin, "/api/admin/badges/home/metadata", r#"{"url":"ftp://example.com"}"#);
+    assert_eq!(status, Status::BadRequest);
+    assert_eq!(error["error"], "invalid_metadata");
+    assert_eq!(app.put(&admin, "/api/admin/badges/missing/metadata", metadata).0, Status::NotFound);
+
+    let (status, badge) = app.put(&admin, "/api/admin/badges/home/period", r#"{"period":"monthly","timezone":"Europe/Berlin"}"#);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(badge["period"]["start"], "2024-06-01");
+    let (status, error) = app.put(&admin, "/api/admin/badges/home/period", r#"{"period":"daily","timezone":"Mars/Olympus"}"#);
+    assert_eq!(status, Status::BadRequest);
+    assert_eq!(error["error"], "invalid_timezone");
+    assert_eq!(app.put(&admin, "/api/admin/badges/missing/period", r#"{"period":"daily"}"#).0, Status::NotFound);
+
+    app.client.get("/counter/home/svg").dispatch();
+    app.clock.set("2024-07-01T12:00:00Z".parse().unwrap());
+    let svg = app.client.get("/counter/home/svg?period=previous").dispatch().into_string().unwrap();
+    assert!(svg.contains(">1</text>"));
+}
+
+#[test]
+fn deleted_badges_go_through_the_trash() {
+    let app = spawn();
+    let admin = app.admin();
+    app.create_badge(&admin, "home", 7);
+    app.create_badge(&admin, "old", 2);
+
+    assert_eq!(app.delete(&admin, "/api/admin/badges/home"), Status::NoContent);
+    assert_eq!(app.delete(&admin, "/api/admin/badges/home"), Status::NotFound);
+    assert_eq!(app.get(&admin, "/api/admin/badges/home").0, Status::NotFound);
+
+    let (status, trash) = app.get(&admin, "/api/admin/trash");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(trash["total"], 1);
+    assert_eq!(trash["badges"][0]["purge_at"], "2024-07-01T12:00:00Z");
+
+    let (status, badge) = app.post(&admin, "/api/admin/trash/home/restore", "");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(badge["count"], 7);
+    assert_eq!(app.post(&admin, "/api/admin/trash/home/restore", "").0, Status::NotFound);
+
+    app.delete(&admin, "/api/admin/badges/old");
+    assert_eq!(app.delete(&admin, "/api/admin/trash/old"), Status::NoContent);
+    assert_eq!(app.delete(&admin, "/api/admin/trash/old"), Status::NotFound);
+
+    // The retention period purges the trash by itself
+    app.delete(&admin, "/api/admin/badges/home");
+    app.clock.advance(Duration::days(30));
+    let admin = app.admin();
+    assert_eq!(app.get(&admin, "/api/admin/trash").1["total"], 0);
+}
+
+#[test]
+fn badges_are_renamed_and_merged() {
+    let app = spawn();
+    let admin = app.admin();
+    app.create_badge(&admin, "home", 5);
+    app.create_badge(&admin, "blog", 3);
+    app.create_badge(&admin, "news", 1);
+
+    let (status, badge) = app.post(&admin, "/api/admin/badges/home/rename", r#"{"new_name":"index"}"#);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(badge["name"], "index");
+    assert_eq!(badge["aliases"], serde_json::json!(["home"]));
+    app.client.post("/api/counter/home/increment").dispatch();
+    assert_eq!(app.get(&admin, "/api/admin/badges/index").1["count"], 6);
+
+    assert_eq!(app.post(&admin, "/api/admin/badges/blog/rename", r#"{"new_name":"index"}"#).0, Status::Conflict);
+    assert_eq!(app.post(&admin, "/api/admin/badges/blog/rename", r#"{"new_name":"home"}"#).0, Status::Conflict);
+    assert_eq!(app.post(&admin, "/api/admin/badges/missing/rename", r#"{"new_name":"other"}"#).0, Status::NotFound);
+    assert_eq!(app.post(&admin, "/api/admin/badges/blog/rename", r#"{"new_name":"a/b"}"#).0, Status::BadRequest);
+
+    let (status, badge) = app.post(&admin, "/api/admin/badges/merge", r#"{"sources":["blog","news"],"target":"index"}"#);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(badge["count"], 10);
+    assert_eq!(app.get(&admin, "/api/admin/badges/blog").0, Status::NotFound);
+    let (status, _) = app.post(&admin, "/api/admin/badges/merge", r#"{"sources":["missing"],"target":"index"}"#);
+    assert_eq!(status, Status::NotFound);
+}
+
+#[test]
+fn bulk_operations_apply_to_every_badge_or_none() {
+    let app = spawn();
+    let admin = app.admin();
+    app.create_badge(&admin, "one", 1);
+    app.create_badge(&admin, "two", 2);
+
+    let (status, response) = app.post(&admin, "/api/admin/badges/bulk", r#"{"names":["one","two"],"operation":"add_offset","offset":10}"#);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(response["succeeded"], 2);
+    assert_eq!(app.get(&admin, "/api/admin/badges/two").1["count"], 12);
+
+    let (status, response) = app.post(&admin, "/api/admin/badges/bulk", r#"{"names":["one","missing"],"operation":"reset"}"#);
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(response["applied"], false);
+    assert_eq!(response["failed"], 1);
+    assert_eq!(app.get(&admin, "/api/admin/badges/one").1["count"], 11);
+
+    let (status, _) = app.post(&admin, "/api/admin/badges/bulk", r#"{"filter":{"min_count":12},"operation":"tag","tags":["big"]}"#);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(app.get(&admin, "/api/admin/badges?tag=big").1["total"], 1);
+
+    let (status, _) = app.post(&admin, "/api/admin/badges/bulk", r#"{"names":["one"],"operation":"move","namespace":"docs"}"#);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(app.get(&admin, "/api/admin/badges/one?ns=docs").0, Status::Ok);
+
+    let (status, error) = app.post(&admin, "/api/admin/badges/bulk", r#"{"names":[],"operation":"reset"}"#);
+    assert_eq!(status, Status::BadRequest);
+    assert_eq!(error["error"], "invalid_bulk_request");
+    let (status, error) = app.post(&admin, "/api/admin/badges/bulk", r#"{"names":["two"],"operation":"tag","tags":["no spaces"]}"#);
+    assert_eq!(status, Status::BadRequest);
+    assert_eq!(error["error"], "invalid_tag");
+    let (status, _) = app.post(&admin, "/api/admin/badges/bulk", r#"{"names":["two"],"operation":"move","namespace":"nowhere"}"#);
+    assert_eq!(status, Status::NotFound);
+}
+
+#[test]
+fn aliases_are_added_and_removed() {
+    let app = spawn();
+    let admin = app.admin();
+    app.create_badge(&admin, "home", 1);
+    app.create_badge(&admin, "blog", 1);
+
+    let (status, alias) = app.post(&admin, "/api/admin/aliases", r#"{"alias":"start","target":"home"}"#);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(alias, serde_json::json!({"alias": "start", "target": "home"}));
+    app.client.get("/counter/start/svg").dispatch();
+    assert_eq!(app.get(&admin, "/api/admin/badges/home").1["count"], 2);
+
+    assert_eq!(app.post(&admin, "/api/admin/aliases", r#"{"alias":"start","target":"home"}"#).0, Status::Conflict);
+    assert_eq!(app.post(&admin, "/api/admin/aliases", r#"{"alias":"blog","target":"home"}"#).0, Status::Conflict);
+    assert_eq!(app.post(&admin, "/api/admin/aliases", r#"{"alias":"home","target":"home"}"#).0, Status::BadRequest);
+    assert_eq!(app.post(&admin, "/api/admin/aliases", r#"{"alias":"other","target":"missing"}"#).0, Status::NotFound);
+
+    let (status, list) = app.get(&admin, "/api/admin/aliases");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(list["total"], 1);
+
+    assert_eq!(app.delete(&admin, "/api/admin/aliases/start"), Status::NoContent);
+    assert_eq!(app.delete(&admin, "/api/admin/aliases/start"), Status::NotFound);
+}
+
+#[test]
+fn signing_secrets_are_rotated_and_disabled() {
+    let app = spawn();
+    let admin = app.admin();
+    app.create_badge(&admin, "home", 1);
+
+    assert_eq!(app.get(&admin, "/api/admin/badges/home/secret").0, Status::NotFound);
+    assert_eq!(app.post(&admin, "/api/admin/badges/missing/secret", "").0, Status::NotFound);
+
+    let (status, first) = app.post(&admin, "/api/admin/badges/home/secret", "");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(first["name"], "home");
+    assert_eq!(first["previous_expires_at"], serde_json::Value::Null);
+    let (status, second) = app.post(&admin, "/api/admin/badges/home/secret", "");
+    assert_eq!(status, Status::Ok);
+    assert_ne!(first["secret"], second["secret"]);
+    assert_eq!(second["previous_expires_at"], "2024-06-02T12:00:00Z");
+    assert_eq!(app.get(&admin, "/api/admin/badges/home/secret").1["secret"], second["secret"]);
+    assert_eq!(app.get(&admin, "/api/admin/badges/home").1["signed"], true);
+
+    assert_eq!(app.delete(&admin, "/api/admin/badges/home/secret"), Status::NoContent);
+    assert_eq!(app.get(&admin, "/api/admin/badges/home").1["signed"], false);
+    assert_eq!(app.delete(&admin, "/api/admin/badges/missing/secret"), Status::NotFound);
+}
+
+#[test]
+fn changes_are_recorded_in_the_audit_log() {
+    let app = spawn();
+    let admin = app.admin();
+    app.create_badge(&admin, "home", 1);
+    app.clock.advance(Duration::hours(1));
+    app.put(&admin, "/api/admin/badges/home", r#"{"count":5}"#);
+
+    let (status, log) = app.get(&admin, "/api/admin/audit");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(log["total"], 2);
+    let latest = &log["entries"][0];
+    assert_eq!(latest["action"], "set");
+    assert_eq!(latest["actor"], "user:admin");
+    assert_eq!(latest["counter"], "home");
+    assert_eq!(latest["old_value"], 1);
+    assert_eq!(latest["new_value"], 5);
+    assert_eq!(latest["timestamp"], "2024-06-01T13:00:00Z");
+
+    assert_eq!(app.get(&admin, "/api/admin/audit?action=create").1["total"], 1);
+    assert_eq!(app.get(&admin, "/api/admin/audit?since=2024-06-01T12:30:00Z").1["total"], 1);
+    assert_eq!(app.get(&admin, "/api/admin/audit?ns=docs").1["total"], 0);
+    assert_eq!(app.get(&admin, "/api/admin/audit?since=soon").0, Status::BadRequest);
+}
+
+#[test]
+fn counters_are_exported_and_imported() {
+    let app = spawn();
+    let admin = app.admin();
+    app.create_badge(&admin, "home", 4);
+
+    let response = app.client.get("/api/admin/export").header(bearer(&admin)).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.headers().get_one("Content-Disposition"), Some("attachment; filename=\"counters-default-20240601.json\""));
+    let export = response.into_string().unwrap();
+    let document: serde_json::Value = serde_json::from_str(&export).unwrap();
+    assert_eq!(document["exported_at"], "2024-06-01T12:00:00Z");
+    assert_eq!(document["counters"][0]["count"], 4);
+
+    let response = app.client.get("/api/admin/export?format=csv").header(bearer(&admin)).dispatch();
+    assert_eq!(response.content_type(), Some(ContentType::CSV));
+    assert!(response.into_string().unwrap().starts_with("name,count,"));
+
+    let import = |uri: &str, body: &str| {
+        let response = app.client.post(uri.to_string()).header(bearer(&admin)).body(body).dispatch();
+        (response.status(), common::json(response))
+    };
+    let (status, report) = import("/api/admin/import?ns=docs&dry_run=true", &export);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(report["dry_run"], true);
+    assert_eq!(report["created"], 1);
+    assert_eq!(app.get(&admin, "/api/admin/badges/home?ns=docs").0, Status::NotFound);
+
+    let (status, report) = import("/api/admin/import?ns=docs", &export);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(report["created"], 1);
+    assert_eq!(app.get(&admin, "/api/admin/badges/home?ns=docs").1["count"], 4);
+
+    let (status, error) = import("/api/admin/import", "not json");
+    assert_eq!(status, Status::BadRequest);
+    assert_eq!(error["error"], "invalid_import");
+}
+
+#[test]
+fn other_services_are_imported_with_their_identifiers_as_aliases() {
+    let app = spawn();
+    let admin = app.admin();
+    let import = |uri: &str, body: &str| {
+        let response = app.client.post(uri.to_string()).header(bearer(&admin)).body(body).dispatch();
+        (response.status(), common::json(response))
+    };
+
+    let body = r#"[{"namespace": "example.com", "key": "blog", "value": 3}, {"key": "home", "value": 4}]"#;
+    let (status, report) = import("/api/admin/import?format=countapi", body);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(report["created"], 2);
+    assert_eq!(app.get(&admin, "/api/admin/badges/example-com-blog").1["count"], 3);
+
+    let body = r#"[{"from": "old-home", "name": "home"}, {"from": "start", "name": "home", "count": 2}]"#;
+    let (status, report) = import("/api/admin/import?format=mapping&strategy=sum", body);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(report["changes"][0]["aliases"], serde_json::json!(["old-home", "start"]));
+    assert_eq!(app.get(&admin, "/api/admin/badges/home").1["count"], 6);
+    app.client.get("/counter/old-home/svg").dispatch();
+    assert_eq!(app.get(&admin, "/api/admin/badges/home").1["count"], 7);
+    assert_eq!(app.get(&admin, "/api/admin/aliases").1["total"], 2);
+
+    let (status, error) = import("/api/admin/import?format=moe-counter", r#"[{"name": "home"}]"#);
+    assert_eq!(status, Status::BadRequest);
+    assert_eq!(error["error"], "invalid_import");
+}
+
+#[test]
+fn summed_imports_saturate_instead_of_overflowing() {
+    let app = spawn();
+    let admin = app.admin();
+    app.create_badge(&admin, "home", 4);
+
+    let response = app.client.post("/api/admin/import?format=csv&strategy=sum")
+        .header(bearer(&admin))
+        .body("home,18446744073709551615")
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(common::json(response)["updated"], 1);
+    assert_eq!(app.get(&admin, "/api/admin/badges/home").1["count"], u64::MAX);
+
+    let response = app.client.post("/api/counter/home/increment").dispatch();
+    assert_eq!(common::json(response)["count"], u64::MAX);
+}
+
+#[test]
+fn csv_exports_do_not_start_fields_with_formulas() {
+    let app = spawn();
+    let admin = app.admin();
+    app.create_badge(&admin, "home", 4);
+    let (status, _) = app.put(&admin, "/api/admin/badges/home/metadata", r#"{"description":"=HYPERLINK(\"https://example.com\")","owner":"@team"}"#);
+    assert_eq!(status, Status::Ok);
+
+    let response = app.client.get("/api/admin/export?format=csv").header(bearer(&admin)).dispatch();
+    let csv = response.into_string().unwrap();
+    assert!(csv.contains(r#""'=HYPERLINK(""https://example.com"")",'@team"#));
+}
+
+#[test]
+fn api_keys_are_created_and_revoked() {
+    let app = spawn();
+    let admin = app.admin();
+
+    let (status, created) = app.post(&admin, "/api/admin/api-keys", r#"{"name":"ci","scopes":["read"]}"#);
+    assert_eq!(status, Status::Ok);
+    let id = created["id"].as_str().unwrap().to_string();
+    assert_eq!(app.post(&admin, "/api/admin/api-keys", r#"{"name":" ","scopes":["read"]}"#).0, Status::BadRequest);
+    assert_eq!(app.post(&admin, "/api/admin/api-keys", r#"{"name":"ci","scopes":[]}"#).0, Status::BadRequest);
+
+    let (status, list) = app.get(&admin, "/api/admin/api-keys");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(list["total"], 1);
+    assert_eq!(app.get(&admin, "/api/admin/api-keys?ns=docs").1["total"], 0);
+
+    assert_eq!(app.delete(&admin, &format!("/api/admin/api-keys/{}?ns=docs", id)), Status::NotFound);
+    assert_eq!(app.delete(&admin, &format!("/api/admin/api-keys/{}", id)), Status::NoContent);
+    assert_eq!(app.delete(&admin, &format!("/api/admin/api-keys/{}", id)), Status::NotFound);
+}
+
+#[test]
+fn only_owners_create_admin_keys() {
+    let app = spawn();
+    let admin = app.admin();
+    let editor = app.user(&admin, "eddie", "editor");
+    let viewer = app.user(&admin, "vera", "viewer");
+
+    let admin_key = r#"{"name":"ci","scopes":["read","admin"]}"#;
+    assert_eq!(app.post(&editor, "/api/admin/api-keys", admin_key).0, Status::Forbidden);
+    assert_eq!(app.post(&viewer, "/api/admin/api-keys", r#"{"name":"ci","scopes":["read"]}"#).0, Status::Forbidden);
+    assert_eq!(app.get(&admin, "/api/admin/api-keys").1["total"], 0);
+
+    assert_eq!(app.post(&editor, "/api/admin/api-keys", r#"{"name":"ci","scopes":["read","increment"]}"#).0, Status::Ok);
+    let (status, created) = app.post(&admin, "/api/admin/api-keys", admin_key);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(created["scopes"], serde_json::json!(["read", "admin"]));
+}
+
+#[test]
+fn users_are_managed_by_owners() {
+    let app = spawn();
+    let admin = app.admin();
+    let user = |name: &str, role: &str| format!(r#"{{"username":"{}","password":"{}","role":"{}"}}"#, name, ADMIN_PASSWORD, role);
+
+    let (status, created) = app.post(&admin, "/api/admin/users", user("eddie", "editor"));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(created["role"], "editor");
+    assert_eq!(app.post(&admin, "/api/admin/users", user("eddie", "viewer")).0, Status::Conflict);
+    let (status, _) = app.post(&admin, "/api/admin/users", format!(
+        r#"{{"username":"nora","password":"{}","role":"viewer","namespace":"nowhere"}}"#, ADMIN_PASSWORD
+    ));
+    assert_eq!(status, Status::BadRequest);
+
+    let (status, list) = app.get(&admin, "/api/admin/users");
+    assert_eq!(status, Status::Ok);
+    assert!(list["users"].as_array().unwrap().iter().any(|user| user["username"] == "eddie"));
+
+    let (status, updated) = app.put(&admin, "/api/admin/users/eddie", r#"{"role":"viewer"}"#);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(updated["role"], "viewer");
+    assert_eq!(app.put(&admin, "/api/admin/users/missing", r#"{"role":"viewer"}"#).0, Status::NotFound);
+
+    assert_eq!(app.delete(&admin, "/api/admin/users/eddie"), Status::NoContent);
+    assert_eq!(app.delete(&admin, "/api/admin/users/eddie"), Status::NotFound);
+
+    // The last owner of every namespace stays
+    app.post(&admin, "/api/admin/users", user("olga", "owner"));
+    assert_eq!(app.delete(&admin, "/api/admin/users/olga"), Status::Conflict);
+}
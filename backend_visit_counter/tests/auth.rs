@@ -0,0 +1,142 @@
+// backend_visit_counter/tests/auth.rs
+//! Login, sessions and the roles of admin users.
+mod common;
+
+use chrono::Duration;
+use common::{bearer, json, spawn, ADMIN_PASSWORD};
+use rocket::http::{ContentType, Status};
+
+fn login(app: &common::TestApp, password: &str) -> Status {
+    app.client.post("/api/auth/login")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{"password":"{}"}}"#, password))
+        .dispatch()
+        .status()
+}
+
+#[test]
+fn login_returns_a_session_of_the_user() {
+    let app = spawn();
+    let response = app.client.post("/api/auth/login")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{"password":"{}"}}"#, ADMIN_PASSWORD))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let body = json(response);
+    assert_eq!(body["username"], "admin");
+    assert_eq!(body["role"], "owner");
+    assert_eq!(body["expires_at"], "2024-06-02T00:00:00Z");
+
+    let (status, me) = app.get(body["token"].as_str().unwrap(), "/api/auth/me");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(me, serde_json::json!({"username": "admin", "role": "owner", "namespace": null}));
+}
+
+#[test]
+fn wrong_credentials_are_rejected() {
+    let app = spawn();
+    assert_eq!(login(&app, "wrong"), Status::Unauthorized);
+    assert_eq!(app.client.get("/api/auth/me").dispatch().status(), Status::Unauthorized);
+    assert_eq!(app.client.get("/api/auth/me").header(bearer("not-a-session")).dispatch().status(), Status::Unauthorized);
+    assert_eq!(app.client.get("/api/admin/badges").dispatch().status(), Status::Unauthorized);
+}
+
+#[test]
+fn repeated_failures_are_throttled() {
+    let app = spawn();
+    for _ in 0..3 {
+        assert_eq!(login(&app, "wrong"), Status::Unauthorized);
+    }
+
+    let response = app.client.post("/api/auth/login")
+        .header(ContentType::JSON)
+        .body(format!(r#"{{"password":"{}"}}"#, ADMIN_PASSWORD))
+        .dispatch();
+    assert_eq!(response.status(), Status::TooManyRequests);
+    assert_eq!(response.headers().get_one("Retry-After"), Some("1"));
+
+    app.clock.advance(Duration::seconds(1));
+    assert_eq!(login(&app, ADMIN_PASSWORD), Status::Ok);
+    // A success clears the failures
+    assert_eq!(login(&app, "wrong"), Status::Unauthorized);
+    assert_eq!(login(&app, ADMIN_PASSWORD), Status::Ok);
+}
+
+#[test]
+fn sessions_expire_and_can_be_refreshed() {
+    let app = spawn();
+    let token = app.admin();
+
+    app.clock.advance(Duration::hours(11));
+    let (status, refreshed) = app.post(&token, "/api/auth/refresh", "");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(refreshed["expires_at"], "2024-06-02T11:00:00Z");
+    let refreshed = refreshed["token"].as_str().unwrap().to_string();
+    assert_eq!(app.get(&token, "/api/auth/me").0, Status::Unauthorized, "refreshing revokes the old token");
+
+    app.clock.advance(Duration::hours(12) - Duration::seconds(1));
+    assert_eq!(app.get(&refreshed, "/api/auth/me").0, Status::Ok);
+    app.clock.advance(Duration::seconds(1));
+    assert_eq!(app.get(&refreshed, "/api/auth/me").0, Status::Unauthorized);
+}
+
+#[test]
+fn logout_revokes_the_session() {
+    let app = spawn();
+    let token = app.admin();
+    assert_eq!(app.post(&token, "/api/auth/logout", "").0, Status::NoContent);
+    assert_eq!(app.get(&token, "/api/auth/me").0, Status::Unauthorized);
+    assert_eq!(app.post(&token, "/api/auth/logout", "").0, Status::Unauthorized);
+}
+
+#[test]
+fn roles_limit_what_users_can_change() {
+    let app = spawn();
+    let admin = app.admin();
+    app.create_badge(&admin, "home", 1);
+    let viewer = app.user(&admin, "vera", "viewer");
+    let editor = app.user(&admin, "eddie", "editor");
+
+    assert_eq!(app.get(&viewer, "/api/admin/badges").0, Status::Ok);
+    assert_eq!(app.post(&viewer, "/api/admin/badges", r#"{"name":"new"}"#).0, Status::Forbidden);
+    assert_eq!(app.put(&viewer, "/api/admin/badges/home", r#"{"count":5}"#).0, Status::Forbidden);
+
+    assert_eq!(app.put(&editor, "/api/admin/badges/home", r#"{"count":5}"#).0, Status::Ok);
+    assert_eq!(app.delete(&editor, "/api/admin/badges/home"), Status::Forbidden);
+    assert_eq!(app.get(&editor, "/api/admin/users").0, Status::Forbidden);
+    let (status, _) = app.post(&editor, "/api/admin/badges/bulk", r#"{"names":["home"],"operation":"delete"}"#);
+    assert_eq!(status, Status::Forbidden);
+
+    assert_eq!(app.delete(&admin, "/api/admin/badges/home"), Status::NoContent);
+}
+
+#[test]
+fn namespaced_users_only_see_their_namespace() {
+    let app = spawn();
+    let admin = app.admin();
+    let (status, _) = app.post(&admin, "/api/admin/users", format!(
+        r#"{{"username":"dora","password":"{}","role":"owner","namespace":"docs"}}"#, ADMIN_PASSWORD
+    ));
+    assert_eq!(status, Status::Ok);
+    let dora = app.login("dora", ADMIN_PASSWORD);
+
+    let (_, me) = app.get(&dora, "/api/auth/me");
+    assert_eq!(me["namespace"], "docs");
+    let (_, namespaces) = app.get(&dora, "/api/admin/namespaces");
+    assert_eq!(namespaces["total"], 1);
+    assert_eq!(namespaces["namespaces"][0]["name"], "docs");
+
+    assert_eq!(app.post(&dora, "/api/admin/badges", r#"{"name":"guide"}"#).0, Status::Ok);
+    assert_eq!(app.get(&dora, "/api/admin/badges?ns=default").0, Status::Forbidden);
+    assert_eq!(app.get(&admin, "/api/admin/badges/guide?ns=docs").0, Status::Ok);
+    assert_eq!(app.get(&admin, "/api/admin/badges/guide").0, Status::NotFound);
+
+    // Their users belong to their namespace, and the other users are hidden
+    let (status, _) = app.post(&dora, "/api/admin/users", format!(
+        r#"{{"username":"doug","password":"{}","role":"viewer","namespace":"default"}}"#, ADMIN_PASSWORD
+    ));
+    assert_eq!(status, Status::Forbidden);
+    let (_, users) = app.get(&dora, "/api/admin/users");
+    assert_eq!(users["total"], 1);
+    assert_eq!(app.put(&dora, "/api/admin/users/admin", r#"{"role":"viewer"}"#).0, Status::NotFound);
+}
@@ -0,0 +1,139 @@
+// backend_visit_counter/tests/common/mod.rs
+//! Builds the server with its data in a temporary directory and a manual clock, and drives it
+//! through Rocket's local client.
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock};
+
+use backend_visit_counter::auth::{hash_password, AdminCredentials};
+use backend_visit_counter::clock::ManualClock;
+use backend_visit_counter::settings::{NamespaceSettings, RateLimitSettings, Settings};
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::blocking::{Client, LocalResponse};
+use rocket::serde::json::Value;
+use tempfile::TempDir;
+
+pub const ADMIN_PASSWORD: &str = "correct horse battery staple";
+
+/// Second namespace of the test server, next to `default`.
+pub const DOCS_NAMESPACE: &str = "docs";
+
+pub struct TestApp {
+    pub client: Client,
+    pub clock: Arc<ManualClock>,
+    // Removed with the app
+    pub dir: TempDir,
+}
+
+/// Hashing is slow in debug builds, so every test shares one hash of `ADMIN_PASSWORD`.
+fn admin_hash() -> String {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| hash_password(ADMIN_PASSWORD).unwrap()).clone()
+}
+
+/// A server with the default settings, the `docs` namespace and no rate limit.
+pub fn spawn() -> TestApp {
+    spawn_with(|_| {})
+}
+
+/// Like `spawn`, with settings changed by `configure`.
+pub fn spawn_with(configure: impl FnOnce(&mut Settings)) -> TestApp {
+    let dir = tempfile::tempdir().unwrap();
+    let mut settings = Settings {
+        data_path: dir.path().join("counters.json"),
+        static_dir: Some(dir.path().to_path_buf()),
+        rate_limit: RateLimitSettings { per_minute: 0 },
+        namespaces: BTreeMap::from([(DOCS_NAMESPACE.to_string(), NamespaceSettings::default())]),
+        ..Settings::default()
+    };
+    configure(&mut settings);
+
+    let figment = rocket::Config::figment().merge(("log_level", "off"));
+    let credentials = AdminCredentials::from_hash(admin_hash()).unwrap();
+    let clock = ManualClock::at("2024-06-01T12:00:00Z");
+    let rocket = backend_visit_counter::build(figment, settings, credentials, clock.clone());
+    TestApp {
+        client: Client::tracked(rocket).unwrap(),
+        clock,
+        dir,
+    }
+}
+
+impl TestApp {
+    /// Logs in and returns the session token.
+    pub fn login(&self, username: &str, password: &str) -> String {
+        let response = self.client.post("/api/auth/login")
+            .header(ContentType::JSON)
+            .body(format!(r#"{{"username":"{}","password":"{}"}}"#, username, password))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        json(response)["token"].as_str().unwrap().to_string()
+    }
+
+    /// Session token of the bootstrap `admin` owner.
+    pub fn admin(&self) -> String {
+        self.login("admin", ADMIN_PASSWORD)
+    }
+
+    /// Creates a user with `role` and returns their session token.
+    pub fn user(&self, admin: &str, username: &str, role: &str) -> String {
+        let (status, _) = self.post(admin, "/api/admin/users", format!(
+            r#"{{"username":"{}","password":"{}","role":"{}"}}"#, username, ADMIN_PASSWORD, role
+        ));
+        assert_eq!(status, Status::Ok);
+        self.login(username, ADMIN_PASSWORD)
+    }
+
+    /// Creates an API key of the default namespace with `scopes` and returns it.
+    pub fn api_key(&self, admin: &str, scopes: &[&str]) -> String {
+        let scopes: Vec<String> = scopes.iter().map(|scope| format!("\"{}\"", scope)).collect();
+        let (status, body) = self.post(admin, "/api/admin/api-keys", format!(r#"{{"name":"test","scopes":[{}]}}"#, scopes.join(",")));
+        assert_eq!(status, Status::Ok);
+        body["key"].as_str().unwrap().to_string()
+    }
+
+    pub fn get(&self, token: &str, uri: &str) -> (Status, Value) {
+        let response = self.client.get(uri.to_string()).header(bearer(token)).dispatch();
+        (response.status(), json(response))
+    }
+
+    pub fn post(&self, token: &str, uri: &str, body: impl AsRef<str>) -> (Status, Value) {
+        let response = self.client.post(uri.to_string())
+            .header(bearer(token))
+            .header(ContentType::JSON)
+            .body(body.as_ref())
+            .dispatch();
+        (response.status(), json(response))
+    }
+
+    pub fn put(&self, token: &str, uri: &str, body: impl AsRef<str>) -> (Status, Value) {
+        let response = self.client.put(uri.to_string())
+            .header(bearer(token))
+            .header(ContentType::JSON)
+            .body(body.as_ref())
+            .dispatch();
+        (response.status(), json(response))
+    }
+
+    pub fn delete(&self, token: &str, uri: &str) -> Status {
+        self.client.delete(uri.to_string()).header(bearer(token)).dispatch().status()
+    }
+
+    /// Creates a badge in the default namespace.
+    pub fn create_badge(&self, admin: &str, name: &str, count: u64) {
+        let (status, _) = self.post(admin, "/api/admin/badges", format!(r#"{{"name":"{}","count":{}}}"#, name, count));
+        assert_eq!(status, Status::Ok);
+    }
+}
+
+pub fn bearer(token: &str) -> Header<'static> {
+    Header::new("Authorization", format!("Bearer {}", token))
+}
+
+/// The body as JSON, `null` when it is empty or not JSON.
+pub fn json(response: LocalResponse<'_>) -> Value {
+    response.into_string()
+        .and_then(|body| serde_json::from_str(&body).ok())
+        .unwrap_or(Value::Null)
+}
@@ -0,0 +1,307 @@
+// backend_visit_counter/tests/counters.rs
+//! Public routes: counters as JSON and SVG badges, health checks and metrics.
+mod common;
+
+use std::net::SocketAddr;
+
+use backend_visit_counter::settings::CreationMode;
+use chrono::Duration;
+use common::{json, spawn, spawn_with};
+use rocket::http::{ContentType, Header, Status};
+use visit_counter_types::signing::{canonical_message, sign};
+
+fn api_key(key: &str) -> Header<'static> {
+    Header::new("x-api-key", key.to_string())
+}
+
+#[test]
+fn counters_are_read_and_incremented_as_json() {
+    let app = spawn();
+
+    let response = app.client.get("/api/counter/home").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json(response)["count"], 0);
+
+    for expected in 1..=2 {
+        let response = app.client.post("/api/counter/home/increment").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(json(response), serde_json::json!({"name": "home", "count": expected}));
+    }
+    assert_eq!(json(app.client.get("/api/counter/home").dispatch())["count"], 2);
+}
+
+#[test]
+fn invalid_counter_names_are_rejected() {
+    let app = spawn();
+    let response = app.client.get("/api/counter/no.dots").dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(json(response)["error"], "invalid_counter_name");
+    assert_eq!(app.client.post("/api/counter/no.dots/increment").dispatch().status(), Status::BadRequest);
+
+    // Every route naming a counter in its path checks the name the same way
+    for uri in ["/api/counter/docs/no.dots", "/counter/no.dots/svg", "/counter/docs/no.dots/svg"] {
+        let response = app.client.get(uri).dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(json(response)["error"], "invalid_counter_name");
+    }
+}
+
+#[test]
+fn every_server_applies_its_own_name_policy() {
+    let insensitive = spawn_with(|settings| settings.names.case_insensitive = true);
+    insensitive.client.post("/api/counter/MyRepo/increment").dispatch();
+    assert_eq!(json(insensitive.client.get("/api/counter/myrepo").dispatch())["count"], 1);
+    let admin = insensitive.admin();
+    assert_eq!(insensitive.get(&admin, "/api/admin/badges/MyRepo").1["name"], "myrepo");
+    assert_eq!(insensitive.get(&admin, "/api/admin/badges/myrepo").0, Status::Ok);
+
+    // A second server in the same process keeps names as they are
+    let sensitive = spawn_with(|settings| settings.names.max_length = 6);
+    sensitive.client.post("/api/counter/MyRepo/increment").dispatch();
+    assert_eq!(json(sensitive.client.get("/api/counter/myrepo").dispatch())["count"], 0);
+    assert_eq!(json(sensitive.client.get("/api/counter/MyRepo").dispatch())["count"], 1);
+    let response = sensitive.client.get("/api/counter/MyRepo2").dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(json(response)["message"], "counter name must be at most 6 characters");
+}
+
+#[test]
+fn namespaced_counters_are_separate() {
+    let app = spawn();
+    app.client.post("/api/counter/docs/home/increment").dispatch();
+    app.client.post("/api/counter/docs/home/increment").dispatch();
+
+    assert_eq!(json(app.client.get("/api/counter/docs/home").dispatch())["count"], 2);
+    assert_eq!(json(app.client.get("/api/counter/home").dispatch())["count"], 0);
+
+    let response = app.client.get("/api/counter/nowhere/home").dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(json(response)["error"], "unknown_namespace");
+    assert_eq!(app.client.post("/api/counter/nowhere/home/increment").dispatch().status(), Status::NotFound);
+}
+
+#[test]
+fn setting_a_counter_needs_an_api_key_with_the_set_scope() {
+    let app = spawn();
+    let admin = app.admin();
+    let set_key = app.api_key(&admin, &["set"]);
+    let read_key = app.api_key(&admin, &["read"]);
+    let body = r#"{"count":42}"#;
+
+    let response = app.client.put("/api/counter/home").header(ContentType::JSON).body(body).dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    let response = app.client.put("/api/counter/home").header(ContentType::JSON).header(api_key("vc_wrong")).body(body).dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    let response = app.client.put("/api/counter/home").header(ContentType::JSON).header(api_key(&read_key)).body(body).dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response = app.client.put("/api/counter/home").header(ContentType::JSON).header(api_key(&set_key)).body(body).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json(response)["count"], 42);
+    let response = app.client.put("/api/counter/docs/home").header(ContentType::JSON).header(api_key(&set_key)).body(body).dispatch();
+    assert_eq!(response.status(), Status::Forbidden, "keys only work in their own namespace");
+
+    // An invalid key is rejected even where a key is optional
+    let response = app.client.post("/api/counter/home/increment").header(api_key("vc_wrong")).dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    let response = app.client.get("/api/counter/home").header(api_key(&read_key)).dispatch();
+    assert_eq!(json(response)["count"], 42);
+}
+
+#[test]
+fn svg_badges_count_visits_and_parse_as_xml() {
+    let app = spawn();
+
+    for expected in ["1", "2"] {
+        let response = app.client.get("/counter/home/svg?label=Hits%20%3C%26%3E").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::new("image", "svg+xml")));
+        assert_eq!(response.headers().get_one("Cache-Control"), Some("max-age=0, no-cache, no-store, must-revalidate"));
+
+        let svg = response.into_string().unwrap();
+        let document = roxmltree::Document::parse(&svg).unwrap();
+        let texts: Vec<_> = document.descendants().filter(|node| node.has_tag_name("text")).filter_map(|node| node.text()).collect();
+        assert_eq!(texts, ["Hits <&>", "Hits <&>", expected, expected]);
+    }
+
+    let response = app.client.get("/counter/docs/home/svg").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json(app.client.get("/api/counter/docs/home").dispatch())["count"], 1);
+    assert_eq!(app.client.get("/counter/nowhere/home/svg").dispatch().status(), Status::NotFound);
+    assert_eq!(app.client.get("/counter/no.dots/svg").dispatch().status(), Status::BadRequest);
+}
+
+#[test]
+fn signed_counters_only_count_signed_urls_or_api_keys() {
+    let app = spawn();
+    let admin = app.admin();
+    app.create_badge(&admin, "signed", 5);
+    let (status, _) = app.post(&admin, "/api/admin/badges/signed/secret", "");
+    assert_eq!(status, Status::Ok);
+
+    let response = app.client.post("/api/counter/signed/increment").dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+    assert_eq!(json(response)["error"], "signature_required");
+
+    let svg = app.client.get("/counter/signed/svg").dispatch().into_string().unwrap();
+    assert!(svg.contains(">5</text>"));
+
+    let key = app.api_key(&admin, &["increment"]);
+    let response = app.client.post("/api/counter/signed/increment").header(api_key(&key)).dispatch();
+    assert_eq!(json(response)["count"], 6);
+}
+
+fn signed_svg(secret: &str) -> String {
+    let signature = sign(secret, &canonical_message("signed", [("label", "Views and more")]));
+    format!("/counter/signed/svg?label=Views%20and%20more&sig={}", signature)
+}
+
+#[test]
+fn signed_urls_count_with_the_current_secret_or_the_previous_one_during_the_grace_period() {
+    let app = spawn();
+    let admin = app.admin();
+    app.create_badge(&admin, "signed", 5);
+    let (_, first) = app.post(&admin, "/api/admin/badges/signed/secret", "");
+    let first = first["secret"].as_str().unwrap().to_string();
+
+    let svg = app.client.get(signed_svg(&first)).dispatch().into_string().unwrap();
+    assert!(svg.contains(">6</text>"));
+    let svg = app.client.get(signed_svg("wrong")).dispatch().into_string().unwrap();
+    assert!(svg.contains(">6</text>"));
+    let tampered = signed_svg(&first).replace("Views%20and%20more", "Views");
+    let svg = app.client.get(tampered).dispatch().into_string().unwrap();
+    assert!(svg.contains(">6</text>"));
+
+    let (_, second) = app.post(&admin, "/api/admin/badges/signed/secret", "");
+    let second = second["secret"].as_str().unwrap().to_string();
+    app.client.get(signed_svg(&first)).dispatch();
+    app.client.get(signed_svg(&second)).dispatch();
+    assert_eq!(json(app.client.get("/api/counter/signed").dispatch())["count"], 8);
+
+    app.clock.advance(Duration::hours(25));
+    app.client.get(signed_svg(&first)).dispatch();
+    app.client.get(signed_svg(&second)).dispatch();
+    assert_eq!(json(app.client.get("/api/counter/signed").dispatch())["count"], 9);
+}
+
+#[test]
+fn urls_signed_for_names_in_another_case_count() {
+    let app = spawn_with(|settings| settings.names.case_insensitive = true);
+    let admin = app.admin();
+    app.create_badge(&admin, "myrepo", 0);
+    app.post(&admin, "/api/admin/badges?ns=docs", r#"{"name":"myrepo"}"#);
+    let (_, secret) = app.post(&admin, "/api/admin/badges/myrepo/secret", "");
+    let secret = secret["secret"].as_str().unwrap().to_string();
+    let (_, docs_secret) = app.post(&admin, "/api/admin/badges/myrepo/secret?ns=docs", "");
+    let docs_secret = docs_secret["secret"].as_str().unwrap().to_string();
+
+    // Signed like the badge generator does, with the name as typed
+    let signature = sign(&secret, &canonical_message("MyRepo", []));
+    app.client.get(format!("/counter/MyRepo/svg?sig={}", signature)).dispatch();
+    let signature = sign(&docs_secret, &canonical_message("docs/MyRepo", []));
+    app.client.get(format!("/counter/docs/MyRepo/svg?sig={}", signature)).dispatch();
+
+    assert_eq!(json(app.client.get("/api/counter/myrepo").dispatch())["count"], 1);
+    assert_eq!(json(app.client.get("/api/counter/docs/myrepo").dispatch())["count"], 1);
+}
+
+#[test]
+fn unknown_counters_leave_no_state_in_allowlist_mode() {
+    let app = spawn_with(|settings| {
+        settings.creation.mode = CreationMode::Allowlist;
+        settings.creation.patterns = vec!["docs-*".to_string()];
+        settings.creation.unknown_text = "n/a".to_string();
+    });
+    let admin = app.admin();
+
+    let svg = app.client.get("/counter/blog/svg").dispatch().into_string().unwrap();
+    assert!(svg.contains(">n/a</text>"));
+    let response = app.client.post("/api/counter/blog/increment").dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(json(response)["error"], "unknown_counter");
+    assert_eq!(app.get(&admin, "/api/admin/badges").1["total"], 0);
+    assert!(!app.dir.path().join("counters.json").exists());
+
+    // Names matching a pattern, and badges created by an admin, count as usual
+    app.client.get("/counter/docs-intro/svg").dispatch();
+    app.create_badge(&admin, "blog", 0);
+    let svg = app.client.get("/counter/blog/svg").dispatch().into_string().unwrap();
+    assert!(svg.contains(">1</text>"));
+    assert_eq!(app.get(&admin, "/api/admin/badges").1["total"], 2);
+}
+
+#[test]
+fn first_hits_stop_creating_counters_at_the_limit() {
+    let app = spawn_with(|settings| settings.creation.max_counters = 2);
+    let admin = app.admin();
+    app.client.post("/api/counter/one/increment").dispatch();
+    app.client.post("/api/counter/two/increment").dispatch();
+
+    let response = app.client.post("/api/counter/three/increment").dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+    assert_eq!(json(response)["error"], "counter_limit_reached");
+    let svg = app.client.get("/counter/three/svg").dispatch().into_string().unwrap();
+    assert!(svg.contains(">unknown</text>"));
+    assert_eq!(app.get(&admin, "/api/admin/badges").1["total"], 2);
+
+    // Existing counters keep counting
+    assert_eq!(json(app.client.post("/api/counter/two/increment").dispatch())["count"], 2);
+}
+
+#[test]
+fn increments_are_rate_limited_per_client_and_minute() {
+    let app = spawn_with(|settings| settings.rate_limit.per_minute = 2);
+    let client: SocketAddr = "203.0.113.7:4000".parse().unwrap();
+    let other: SocketAddr = "203.0.113.8:4000".parse().unwrap();
+    let increment = |remote| app.client.post("/api/counter/home/increment").remote(remote).dispatch().status();
+
+    assert_eq!(increment(client), Status::Ok);
+    assert_eq!(increment(client), Status::Ok);
+    assert_eq!(increment(client), Status::TooManyRequests);
+    assert_eq!(app.client.get("/counter/home/svg").remote(client).dispatch().status(), Status::TooManyRequests);
+    assert_eq!(increment(other), Status::Ok);
+
+    app.clock.advance(Duration::minutes(1));
+    assert_eq!(increment(client), Status::Ok);
+}
+
+#[test]
+fn health_checks_report_ok() {
+    let app = spawn();
+    let response = app.client.get("/healthz").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(json(response)["status"], "ok");
+
+    let response = app.client.get("/readyz").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let body = json(response);
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["storage"]["ok"], true);
+}
+
+#[test]
+fn metrics_need_a_global_key() {
+    let app = spawn();
+    let admin = app.admin();
+    let admin_key = app.api_key(&admin, &["admin"]);
+    let read_key = app.api_key(&admin, &["read"]);
+    let (status, body) = app.post(&admin, "/api/admin/api-keys?ns=docs", r#"{"name":"docs","scopes":["admin"]}"#);
+    assert_eq!(status, Status::Ok);
+    let docs_key = body["key"].as_str().unwrap().to_string();
+    let (status, _) = app.post(&admin, "/api/admin/users", format!(
+        r#"{{"username":"dora","password":"{}","role":"owner","namespace":"default"}}"#, common::ADMIN_PASSWORD
+    ));
+    assert_eq!(status, Status::Ok);
+    let default_owner = app.login("dora", common::ADMIN_PASSWORD);
+    let default_owner_key = app.api_key(&default_owner, &["admin"]);
+    app.client.get("/counter/home/svg").dispatch();
+
+    assert_eq!(app.client.get("/metrics").dispatch().status(), Status::Unauthorized);
+    assert_eq!(app.client.get("/metrics").header(api_key(&read_key)).dispatch().status(), Status::Forbidden);
+    assert_eq!(app.client.get("/metrics").header(api_key(&docs_key)).dispatch().status(), Status::Forbidden);
+    assert_eq!(app.client.get("/metrics").header(api_key(&default_owner_key)).dispatch().status(), Status::Forbidden);
+
+    let response = app.client.get("/metrics").header(api_key(&admin_key)).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert!(response.into_string().unwrap().contains("home"));
+}
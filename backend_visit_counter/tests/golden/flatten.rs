@@ -0,0 +1,268 @@
+// backend_visit_counter/tests/golden/flatten.rs
+//! Badges are laid out by their stylesheet: custom properties, `calc()` and CSS geometry
+//! (`width`, `rx`, `transform`...) that resvg does not apply. `flatten` computes the style of every
+//! element the way a browser would for the badge stylesheets, and writes it as plain attributes.
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use roxmltree::{Document, Node, NodeType};
+
+const SVG_NS: &str = "http://www.w3.org/2000/svg";
+const XLINK_NS: &str = "http://www.w3.org/1999/xlink";
+
+/// Nesting of `var()` references followed before giving up on a cycle.
+const MAX_VAR_DEPTH: usize = 16;
+
+struct Rule {
+    selectors: Vec<String>,
+    declarations: Vec<(String, String)>,
+}
+
+/// The SVG with its `<style>` applied as attributes and removed.
+pub fn flatten(svg: &str) -> String {
+    let document = Document::parse(svg).expect("badge is well-formed XML");
+    let css: String = document.descendants()
+        .filter(|node| node.has_tag_name("style"))
+        .flat_map(|node| node.children())
+        .filter_map(|node| node.text())
+        .collect();
+    let rules = parse_rules(&css);
+
+    // Custom properties are only declared on :root, the last declaration wins
+    let mut variables = HashMap::new();
+    for rule in rules.iter().filter(|rule| rule.selectors.iter().any(|selector| selector == ":root")) {
+        for (name, value) in rule.declarations.iter().filter(|(name, _)| name.starts_with("--")) {
+            variables.insert(name.clone(), value.clone());
+        }
+    }
+
+    let mut flat = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    write_element(&mut flat, document.root_element(), &rules, &variables);
+    flat
+}
+
+fn parse_rules(css: &str) -> Vec<Rule> {
+    let mut css = strip_comments(css);
+    // `@import` statements have no block, and their URL may hold semicolons
+    while let Some(start) = css.find("@import") {
+        let url_end = css[start..].find(')').map_or(start, |end| start + end);
+        let end = css[url_end..].find(';').map_or(css.len(), |end| url_end + end + 1);
+        css.replace_range(start..end, "");
+    }
+
+    let mut rules = Vec::new();
+    let mut rest = css.as_str();
+    while let (Some(open), Some(close)) = (rest.find('{'), rest.find('}')) {
+        if close < open {
+            rest = &rest[close + 1..];
+            continue;
+        }
+        let selectors = rest[..open].split(',').map(|selector| selector.trim().to_string()).collect();
+        let declarations = rest[open + 1..close].split(';')
+            .filter_map(|declaration| declaration.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(), value.replace("!important", "").trim().to_string()))
+            .collect();
+        rules.push(Rule { selectors, declarations });
+        rest = &rest[close + 1..];
+    }
+    rules
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut stripped = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        rest = rest[start + 2..].find("*/").map_or("", |end| &rest[start + 2 + end + 2..]);
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Whether `selector` matches `node`. Badge stylesheets only use `:root` and single classes.
+fn matches(selector: &str, node: Node) -> bool {
+    match selector.strip_prefix('.') {
+        Some(class) => node.attribute("class").is_some_and(|classes| classes.split_whitespace().any(|c| c == class)),
+        None => selector == ":root" && node.parent().is_some_and(|parent| parent.is_root()),
+    }
+}
+
+fn write_element(out: &mut String, node: Node, rules: &[Rule], variables: &HashMap<String, String>) {
+    let name = node.tag_name().name();
+    if name == "style" {
+        return;
+    }
+
+    // Presentation attributes first, then the matching rules in order
+    let mut attributes: Vec<(String, String)> = node.attributes()
+        .map(|attribute| match attribute.namespace() {
+            Some(XLINK_NS) => (format!("xlink:{}", attribute.name()), attribute.value().to_string()),
+            _ => (attribute.name().to_string(), attribute.value().to_string()),
+        })
+        .collect();
+    for rule in rules.iter().filter(|rule| rule.selectors.iter().any(|selector| matches(selector, node))) {
+        for (property, value) in rule.declarations.iter().filter(|(property, _)| !property.starts_with("--")) {
+            attributes.retain(|(name, _)| name != property);
+            attributes.push((property.clone(), value.clone()));
+        }
+    }
+
+    write!(out, "<{}", name).unwrap();
+    if node.parent().is_some_and(|parent| parent.is_root()) {
+        write!(out, " xmlns=\"{}\" xmlns:xlink=\"{}\"", SVG_NS, XLINK_NS).unwrap();
+    }
+    for (attribute, value) in attributes {
+        write!(out, " {}=\"{}\"", attribute, escape(&resolve(&value, variables))).unwrap();
+    }
+    out.push('>');
+    for child in node.children() {
+        match child.node_type() {
+            NodeType::Element => write_element(out, child, rules, variables),
+            NodeType::Text => out.push_str(&escape(child.text().unwrap_or_default())),
+            _ => {}
+        }
+    }
+    write!(out, "</{}>", name).unwrap();
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// `value` with its `var()` references substituted, `calc()` evaluated, and lengths in user units.
+fn resolve(value: &str, variables: &HashMap<String, String>) -> String {
+    let value = substitute_vars(value, variables, 0);
+    let value = evaluate_calcs(&value);
+    let value = value.replace("translateX(", "translate(").replace("translateY(", "translate(0, ");
+    strip_px(&value)
+}
+
+/// Index of the parenthesis closing the one opened just before `text`.
+fn closing_paren(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(index),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn substitute_vars(value: &str, variables: &HashMap<String, String>, depth: usize) -> String {
+    let Some(start) = value.find("var(") else {
+        return value.to_string();
+    };
+    let inner_start = start + "var(".len();
+    let Some(length) = closing_paren(&value[inner_start..]) else {
+        return value.to_string();
+    };
+    let inner = &value[inner_start..inner_start + length];
+    let (name, fallback) = match inner.split_once(',') {
+        Some((name, fallback)) => (name.trim(), Some(fallback.trim())),
+        None => (inner.trim(), None),
+    };
+
+    let replacement = match variables.get(name).map(String::as_str).or(fallback) {
+        Some(replacement) if depth < MAX_VAR_DEPTH => substitute_vars(replacement, variables, depth + 1),
+        _ => String::new(),
+    };
+    let rest = substitute_vars(&value[inner_start + length + 1..], variables, depth);
+    format!("{}{}{}", &value[..start], replacement, rest)
+}
+
+fn evaluate_calcs(value: &str) -> String {
+    let Some(start) = value.find("calc(") else {
+        return value.to_string();
+    };
+    let inner_start = start + "calc(".len();
+    let Some(length) = closing_paren(&value[inner_start..]) else {
+        return value.to_string();
+    };
+    let inner = &value[inner_start..inner_start + length];
+    let evaluated = match Calc::new(inner).evaluate() {
+        Some(number) => format!("{}px", number),
+        None => format!("calc({})", inner),
+    };
+    format!("{}{}{}", &value[..start], evaluated, evaluate_calcs(&value[inner_start + length + 1..]))
+}
+
+/// Drops the `px` unit of numbers: user units are pixels, and transforms only take numbers.
+fn strip_px(value: &str) -> String {
+    let mut stripped = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(index) = rest.find("px") {
+        let after_number = rest[..index].ends_with(|c: char| c.is_ascii_digit() || c == '.');
+        stripped.push_str(&rest[..index]);
+        if !after_number {
+            stripped.push_str("px");
+        }
+        rest = &rest[index + 2..];
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Evaluator of `calc()` expressions on pixel lengths and plain numbers.
+struct Calc<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Calc<'a> {
+    fn new(expression: &'a str) -> Self {
+        Calc { rest: expression }
+    }
+
+    fn evaluate(mut self) -> Option<f64> {
+        let value = self.sum()?;
+        self.rest.trim().is_empty().then_some(value)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.rest = self.rest.trim_start();
+        self.rest.chars().next()
+    }
+
+    fn sum(&mut self) -> Option<f64> {
+        let mut value = self.product()?;
+        while let Some(operator @ ('+' | '-')) = self.peek() {
+            self.rest = &self.rest[1..];
+            let operand = self.product()?;
+            value = if operator == '+' { value + operand } else { value - operand };
+        }
+        Some(value)
+    }
+
+    fn product(&mut self) -> Option<f64> {
+        let mut value = self.factor()?;
+        while let Some(operator @ ('*' | '/')) = self.peek() {
+            self.rest = &self.rest[1..];
+            let operand = self.factor()?;
+            value = if operator == '*' { value * operand } else { value / operand };
+        }
+        Some(value)
+    }
+
+    fn factor(&mut self) -> Option<f64> {
+        match self.peek()? {
+            '(' => {
+                self.rest = &self.rest[1..];
+                let value = self.sum()?;
+                self.rest = self.rest.trim_start().strip_prefix(')')?;
+                Some(value)
+            }
+            '-' => {
+                self.rest = &self.rest[1..];
+                Some(-self.factor()?)
+            }
+            _ => {
+                let end = self.rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(self.rest.len());
+                let number = self.rest[..end].parse().ok()?;
+                self.rest = self.rest[end..].strip_prefix("px").unwrap_or(&self.rest[end..]);
+                Some(number)
+            }
+        }
+    }
+}
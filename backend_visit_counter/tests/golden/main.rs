@@ -0,0 +1,281 @@
+// backend_visit_counter/tests/golden/main.rs
+//! Golden files of representative badges: the SVG as served, and a PNG rendering of its layout.
+//! Run with `UPDATE_GOLDENS=1` to write the current output as the new goldens, then review the diff.
+mod flatten;
+
+use std::path::{Path, PathBuf};
+
+use backend_visit_counter::models::SvgOptions;
+use backend_visit_counter::svg_generator::{build_custom_css, generate_svg, BASE_CSS};
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg;
+
+/// Channel difference under which two pixels count as equal, for antialiasing noise.
+const CHANNEL_TOLERANCE: u8 = 8;
+/// Share of the pixels that may differ before a rendering counts as changed.
+const MAX_DIFFERENT_PIXELS: f64 = 0.002;
+
+/// An orange dot, standing in for a logo.
+const LOGO: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 10 10'%3E%3Ccircle cx='5' cy='5' r='5' fill='%23f5a623'/%3E%3C/svg%3E";
+
+struct Case {
+    name: &'static str,
+    value: &'static str,
+    options: SvgOptions,
+}
+
+/// Options as the server completes them: label, width and height are always set.
+fn options(configure: impl FnOnce(&mut SvgOptions)) -> SvgOptions {
+    let mut options = SvgOptions {
+        label: Some("Visits".to_string()),
+        width: Some(150),
+        height: Some(20),
+        ..SvgOptions::default()
+    };
+    configure(&mut options);
+    options
+}
+
+fn text(value: &str) -> Option<String> {
+    Some(value.to_string())
+}
+
+fn catalog() -> Vec<Case> {
+    vec![
+        Case { name: "default", value: "1234", options: options(|_| {}) },
+        Case {
+            name: "large",
+            value: "98765",
+            options: options(|o| {
+                o.width = Some(300);
+                o.height = Some(40);
+                o.font_size = Some(18);
+            }),
+        },
+        Case {
+            name: "explicit_widths",
+            value: "42",
+            options: options(|o| {
+                o.label_width = Some(60);
+                o.counter_width = Some(90);
+            }),
+        },
+        Case {
+            name: "counter_first",
+            value: "42",
+            options: options(|o| o.element_positions = text("counter,label")),
+        },
+        Case {
+            name: "logo_left",
+            value: "42",
+            options: options(|o| {
+                o.logo_url = text(LOGO);
+                o.element_positions = text("logo,label,counter");
+            }),
+        },
+        Case {
+            name: "logo_middle",
+            value: "42",
+            options: options(|o| {
+                o.width = Some(180);
+                o.height = Some(28);
+                o.logo_url = text(LOGO);
+                o.logo_width = Some(40);
+            }),
+        },
+        Case {
+            name: "logo_right",
+            value: "42",
+            options: options(|o| {
+                o.logo_url = text(LOGO);
+                o.element_positions = text("label,counter,logo");
+            }),
+        },
+        Case { name: "radius", value: "7", options: options(|o| o.radius = Some(10)) },
+        Case {
+            name: "border",
+            value: "7",
+            options: options(|o| {
+                o.border_width = Some(2);
+                o.border_color = text("ff0000");
+            }),
+        },
+        Case {
+            name: "border_radius",
+            value: "7",
+            options: options(|o| {
+                o.border_width = Some(3);
+                o.border_color = text("#1e90ff");
+                o.border_radius = Some(10);
+            }),
+        },
+        Case {
+            name: "colors",
+            value: "512",
+            options: options(|o| {
+                o.background_label = text("2d3748");
+                o.background_counter = text("#38a169");
+                o.label_color = text("#ffd700");
+                o.counter_color = text("000000");
+                o.grad_stop1_color = text("fff");
+                o.grad_stop1_opacity = Some(0.5);
+                o.grad_stop2_opacity = Some(0.3);
+            }),
+        },
+        Case {
+            name: "legacy_colors",
+            value: "512",
+            options: options(|o| {
+                o.text_color = text("333333");
+                o.background_color = text("eeeeee");
+                o.background_counter = text("ffa500");
+            }),
+        },
+        Case {
+            name: "text",
+            value: "1,000,000",
+            options: options(|o| {
+                o.label = text("Page views & <hits>");
+                o.font_family = text("Verdana, sans-serif");
+                o.font_weight = text("bold");
+                o.shadow_fill = text("ff00ff");
+                o.shadow_opacity = Some(0.6);
+                o.label_offset_y = Some(16);
+                o.counter_offset_x = Some(120);
+            }),
+        },
+        Case {
+            name: "custom_style",
+            value: "3",
+            options: options(|o| o.style = text(".right-rect { fill: #6b46c1; } .count { font-style: italic; }")),
+        },
+    ]
+}
+
+/// The badge exactly as `/counter/<name>/svg` serves it.
+fn render_svg(case: &Case) -> String {
+    let options = &case.options;
+    let css = format!("{}\n{}", BASE_CSS, build_custom_css(Some(options.clone())));
+    let label = options.label.as_deref().unwrap_or_default();
+    generate_svg(label, case.value, &css, options.width.unwrap_or(150), options.height.unwrap_or(20), Some(options))
+}
+
+/// Rendering of the layout of a badge. No fonts are loaded so the result does not depend on the
+/// machine: text is left out, and only checked by the SVG golden.
+fn rasterize(svg: &str) -> Pixmap {
+    let tree = usvg::Tree::from_str(&flatten::flatten(svg), &usvg::Options::default()).expect("flattened badge parses");
+    let size = tree.size().to_int_size();
+    let mut pixmap = Pixmap::new(size.width(), size.height()).expect("badge has a size");
+    resvg::render(&tree, Transform::default(), &mut pixmap.as_mut());
+    pixmap
+}
+
+/// Pixels of `actual` differing from `expected`, and an image of them in red, when their sizes match.
+fn pixel_diff(expected: &Pixmap, actual: &Pixmap) -> Option<(usize, Pixmap)> {
+    if (expected.width(), expected.height()) != (actual.width(), actual.height()) {
+        return None;
+    }
+    let mut diff = Pixmap::new(actual.width(), actual.height())?;
+    let mut different = 0;
+    let pixels = expected.pixels().iter().zip(actual.pixels()).zip(diff.pixels_mut());
+    for ((expected, actual), marker) in pixels {
+        let distance = [
+            expected.red().abs_diff(actual.red()),
+            expected.green().abs_diff(actual.green()),
+            expected.blue().abs_diff(actual.blue()),
+            expected.alpha().abs_diff(actual.alpha()),
+        ].into_iter().max().unwrap_or(0);
+        if distance > CHANNEL_TOLERANCE {
+            different += 1;
+            *marker = resvg::tiny_skia::PremultipliedColorU8::from_rgba(255, 0, 0, 255).unwrap();
+        }
+    }
+    Some((different, diff))
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/snapshots")
+}
+
+/// Where the actual output and pixel diffs of changed badges are written for review.
+fn output_dir() -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden")
+}
+
+#[test]
+fn badges_match_their_goldens() {
+    let update = std::env::var_os("UPDATE_GOLDENS").is_some();
+    let golden_dir = golden_dir();
+    let output_dir = output_dir();
+    std::fs::create_dir_all(&output_dir).unwrap();
+    let mut failures = Vec::new();
+
+    for case in catalog() {
+        let svg = render_svg(&case);
+        let png = rasterize(&svg);
+        let svg_path = golden_dir.join(format!("{}.svg", case.name));
+        let png_path = golden_dir.join(format!("{}.png", case.name));
+
+        if update {
+            std::fs::create_dir_all(&golden_dir).unwrap();
+            std::fs::write(&svg_path, &svg).unwrap();
+            png.save_png(&png_path).unwrap();
+            continue;
+        }
+
+        match std::fs::read_to_string(&svg_path) {
+            Ok(expected) if expected == svg => {}
+            Ok(_) => {
+                let actual = output_dir.join(format!("{}.svg", case.name));
+                std::fs::write(&actual, &svg).unwrap();
+                failures.push(format!("{}: SVG differs, see {}", case.name, actual.display()));
+            }
+            Err(e) => failures.push(format!("{}: cannot read {}: {}", case.name, svg_path.display(), e)),
+        }
+
+        let expected = match Pixmap::load_png(&png_path) {
+            Ok(expected) => expected,
+            Err(e) => {
+                failures.push(format!("{}: cannot read {}: {}", case.name, png_path.display(), e));
+                continue;
+            }
+        };
+        let actual_path = output_dir.join(format!("{}.png", case.name));
+        match pixel_diff(&expected, &png) {
+            Some((different, _)) if (different as f64) <= MAX_DIFFERENT_PIXELS * png.pixels().len() as f64 => {}
+            Some((different, diff)) => {
+                png.save_png(&actual_path).unwrap();
+                let diff_path = output_dir.join(format!("{}.diff.png", case.name));
+                diff.save_png(&diff_path).unwrap();
+                failures.push(format!("{}: {} pixels differ, see {} and {}", case.name, different, actual_path.display(), diff_path.display()));
+            }
+            None => {
+                png.save_png(&actual_path).unwrap();
+                failures.push(format!(
+                    "{}: rendered {}x{} instead of {}x{}, see {}",
+                    case.name, png.width(), png.height(), expected.width(), expected.height(), actual_path.display()
+                ));
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} badge(s) changed:\n{}\nRun `UPDATE_GOLDENS=1 cargo test -p backend_visit_counter --test golden` to accept the changes.",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn flattening_resolves_the_stylesheet() {
+    let svg = render_svg(&Case { name: "flat", value: "1", options: options(|o| o.border_width = Some(2)) });
+    let flat = flatten::flatten(&svg);
+
+    assert!(!flat.contains("<style"), "{}", flat);
+    assert!(!flat.contains("var(") && !flat.contains("calc("), "{}", flat);
+    assert!(flat.contains(r##"<rect class="left-rect" width="100" height="20" fill="#18181b">"##), "{}", flat);
+    assert!(flat.contains(r#"transform="translate(100)""#), "{}", flat);
+    assert!(flat.contains(r##"stroke="#cccccc" stroke-width="2""##), "{}", flat);
+    assert!(flat.contains(r#"<text class="count" transform="translate(125, 14)""#), "{}", flat);
+}
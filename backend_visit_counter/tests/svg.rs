@@ -0,0 +1,79 @@
+// backend_visit_counter/tests/svg.rs
+//! Badges stay well-formed XML whatever the query string holds.
+use backend_visit_counter::models::{PeriodView, SvgOptions};
+use backend_visit_counter::svg_generator::{build_custom_css, generate_svg};
+use proptest::option;
+use proptest::prelude::*;
+
+fn text() -> impl Strategy<Value = Option<String>> {
+    option::of(prop_oneof![
+        any::<String>(),
+        // Markup, entities and the end of the style's CDATA section
+        "[<>&\"' ;:{}a-z#0-9\\]\\[]{0,24}",
+        Just("]]>".to_string()),
+    ])
+}
+
+fn size() -> impl Strategy<Value = Option<u32>> {
+    option::of(prop_oneof![0..500u32, any::<u32>()])
+}
+
+fn opacity() -> impl Strategy<Value = Option<f32>> {
+    option::of(any::<f32>())
+}
+
+prop_compose! {
+    fn svg_options()(
+        (label, style, font_family, font_weight, text_color, background_color) in (text(), text(), text(), text(), text(), text()),
+        (grad_stop1_color, shadow_fill, background_label, background_counter, label_color, counter_color) in (text(), text(), text(), text(), text(), text()),
+        (border_color, logo_url, element_positions) in (text(), text(), option::of("(label|logo|counter|x)(,(label|logo|counter|x)){0,5}")),
+        (width, height, label_width, counter_width, radius, font_size) in (size(), size(), size(), size(), size(), size()),
+        (label_offset_x, label_offset_y, counter_offset_x, counter_offset_y) in (size(), size(), size(), size()),
+        (border_width, border_radius, logo_width) in (size(), size(), size()),
+        (grad_stop1_opacity, grad_stop2_opacity, shadow_opacity) in (opacity(), opacity(), opacity()),
+        period in option::of(prop_oneof![Just(PeriodView::Current), Just(PeriodView::Previous), Just(PeriodView::All)]),
+    ) -> SvgOptions {
+        SvgOptions {
+            label, style, width, height, label_width, counter_width, radius,
+            grad_stop1_color, grad_stop1_opacity, grad_stop2_opacity,
+            font_family, font_size, label_offset_x, label_offset_y, counter_offset_x, counter_offset_y,
+            shadow_fill, shadow_opacity, background_label, background_counter, label_color, counter_color,
+            font_weight, text_color, background_color, border_width, border_color, border_radius,
+            logo_url, logo_width, element_positions, period,
+        }
+    }
+}
+
+/// What XML can hold of `text`: characters it does not allow at all are dropped.
+fn xml_text(text: &str) -> String {
+    text.chars()
+        .filter(|c| matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..))
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn custom_css_never_panics(options in option::of(svg_options())) {
+        build_custom_css(options);
+    }
+
+    #[test]
+    fn badges_parse_as_xml(options in svg_options(), label in any::<String>(), value in text(), width in size(), height in size()) {
+        let value = value.unwrap_or_default();
+        let css = build_custom_css(Some(options.clone()));
+        let svg = generate_svg(&label, &value, &css, width.unwrap_or(150), height.unwrap_or(20), Some(&options));
+
+        let document = roxmltree::Document::parse(&svg).map_err(|e| TestCaseError::fail(format!("{}\n{}", e, svg)))?;
+        prop_assert_eq!(document.root_element().tag_name().name(), "svg");
+
+        let text_of = |class: &str| document.descendants()
+            .find(|node| node.attribute("class") == Some(class))
+            .map(|node| node.text().unwrap_or_default().to_string());
+        prop_assert_eq!(text_of("label"), Some(xml_text(&label)));
+        prop_assert_eq!(text_of("count"), Some(xml_text(&value)));
+
+        let style = document.descendants().find(|node| node.has_tag_name("style")).unwrap();
+        let style: String = style.children().filter_map(|node| node.text()).collect();
+        prop_assert!(style.contains(&xml_text(&css).replace('\r', "\n")));
+    }
+}
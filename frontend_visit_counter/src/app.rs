@@ -1,6 +1,7 @@
 // frontend_visit_counter/src/app.rs
 use yew::prelude::*;
 use gloo::timers::callback::Timeout;
+use chrono::{DateTime, Utc};
 
 use crate::components::*;
 use crate::services::ApiService;
@@ -9,11 +10,12 @@ use crate::types::*;
 pub enum AppMsg {
     SetRoute(AppRoute),
     ToggleTheme,
-    SetAuthToken(Option<String>),
+    SetAuthToken(Option<TokenResponse>),
+    RefreshSession,
     ShowAlert(String, String), // message, type
     HideAlert,
-    Login(String), // password
-    LoginSuccess(String), // token
+    Login((String, String)), // (username, password)
+    LoginSuccess(TokenResponse),
     LoginError(String),
     Logout,
 }
@@ -22,16 +24,20 @@ pub struct App {
     state: AppState,
     alert_message: Option<(String, String)>, // (message, type)
     alert_timeout: Option<Timeout>,
+    refresh_timeout: Option<Timeout>,
 }
 
 impl Component for App {
     type Message = AppMsg;
     type Properties = ();
 
-    fn create(_ctx: &Context<Self>) -> Self {
+    fn create(ctx: &Context<Self>) -> Self {
         let theme = ApiService::get_theme();
-        let auth_token = ApiService::get_auth_token();
-        let is_authenticated = auth_token.is_some();
+        let session = ApiService::get_auth_token();
+        let is_authenticated = session.is_some();
+        let refresh_timeout = session.as_ref().map(|s| Self::schedule_refresh(ctx, &s.expires_at));
+        let role = session.as_ref().map(|s| s.role);
+        let auth_token = session.map(|s| s.token);
 
         // Apply initial theme
         if let Some(document) = web_sys::window().and_then(|w| w.document()) {
@@ -44,11 +50,13 @@ impl Component for App {
             state: AppState {
                 theme,
                 auth_token,
+                role,
                 is_authenticated,
                 ..Default::default()
             },
             alert_message: None,
             alert_timeout: None,
+            refresh_timeout,
         }
     }
 
@@ -70,16 +78,38 @@ impl Component for App {
                 }
                 true
             }
-            AppMsg::SetAuthToken(token) => {
-                self.state.auth_token = token.clone();
-                self.state.is_authenticated = token.is_some();
+            AppMsg::SetAuthToken(session) => {
+                self.state.auth_token = session.as_ref().map(|s| s.token.clone());
+                self.state.role = session.as_ref().map(|s| s.role);
+                self.state.is_authenticated = session.is_some();
 
-                match token {
-                    Some(t) => ApiService::set_auth_token(t),
+                if let Some(timeout) = self.refresh_timeout.take() {
+                    timeout.cancel();
+                }
+                match session {
+                    Some(s) => {
+                        ApiService::set_auth_token(&s);
+                        self.refresh_timeout = Some(Self::schedule_refresh(ctx, &s.expires_at));
+                    }
                     None => ApiService::remove_auth_token(),
                 }
                 true
             }
+            AppMsg::RefreshSession => {
+                if let Some(token) = self.state.auth_token.clone() {
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::refresh_token(&token).await {
+                            Ok(session) => link.send_message(AppMsg::SetAuthToken(Some(session))),
+                            Err(_) => {
+                                link.send_message(AppMsg::SetAuthToken(None));
+                                link.send_message(AppMsg::ShowAlert("Your session has expired, please log in again".to_string(), "info".to_string()));
+                            }
+                        }
+                    });
+                }
+                false
+            }
             AppMsg::ShowAlert(message, alert_type) => {
                 // Cancel existing timeout
                 if let Some(timeout) = self.alert_timeout.take() {
@@ -102,18 +132,18 @@ impl Component for App {
                 }
                 true
             }
-            AppMsg::Login(password) => {
+            AppMsg::Login((username, password)) => {
                 let link = ctx.link().clone();
                 wasm_bindgen_futures::spawn_local(async move {
-                    match ApiService::login(password).await {
+                    match ApiService::login(username, password).await {
                         Ok(token) => link.send_message(AppMsg::LoginSuccess(token)),
                         Err(error) => link.send_message(AppMsg::LoginError(error)),
                     }
                 });
                 false
             }
-            AppMsg::LoginSuccess(token) => {
-                ctx.link().send_message(AppMsg::SetAuthToken(Some(token)));
+            AppMsg::LoginSuccess(session) => {
+                ctx.link().send_message(AppMsg::SetAuthToken(Some(session)));
                 ctx.link().send_message(AppMsg::ShowAlert("Login successful!".to_string(), "success".to_string()));
                 true
             }
@@ -122,6 +152,12 @@ impl Component for App {
                 false
             }
             AppMsg::Logout => {
+                // Revoke the token server-side, the local session is dropped either way
+                if let Some(token) = self.state.auth_token.clone() {
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let _ = ApiService::logout(&token).await;
+                    });
+                }
                 ctx.link().send_message(AppMsg::SetAuthToken(None));
                 ctx.link().send_message(AppMsg::ShowAlert("Logged out successfully".to_string(), "info".to_string()));
                 ctx.link().send_message(AppMsg::SetRoute(AppRoute::Home));
@@ -171,6 +207,13 @@ impl Component for App {
                                 },
                                 AppRoute::Admin => html! {
                                     <AdminPanel
+                                        auth_token={self.state.auth_token.clone()}
+                                        role={self.state.role}
+                                        is_authenticated={self.state.is_authenticated}
+                                    />
+                                },
+                                AppRoute::Audit => html! {
+                                    <AuditLog
                                         auth_token={self.state.auth_token.clone()}
                                         is_authenticated={self.state.is_authenticated}
                                     />
@@ -182,4 +225,17 @@ impl Component for App {
             </div>
         }
     }
-}
\ No newline at end of file
+}
+
+impl App {
+    /// Refreshes the session five minutes before it expires, or halfway through shorter sessions.
+    fn schedule_refresh(ctx: &Context<Self>, expires_at: &DateTime<Utc>) -> Timeout {
+        let remaining = ApiService::millis_until(expires_at).max(0.0);
+        let delay = (remaining - 5.0 * 60.0 * 1000.0).max(remaining / 2.0);
+
+        let link = ctx.link().clone();
+        Timeout::new(delay.min(u32::MAX as f64) as u32, move || {
+            link.send_message(AppMsg::RefreshSession);
+        })
+    }
+}
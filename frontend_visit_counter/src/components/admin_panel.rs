@@ -1,39 +1,146 @@
 // frontend_visit_counter/src/components/admin_panel.rs
 use yew::prelude::*;
+use gloo::file::callbacks::{read_as_text, FileReader};
+use gloo::file::{Blob, File, ObjectUrl};
+use gloo::timers::callback::Timeout;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlElement, HtmlInputElement, HtmlSelectElement};
 
-use crate::components::CreateBadgeModal;
+use crate::components::{BadgeDetailModal, ConfirmModal, CreateBadgeModal, UndoToast};
 use crate::services::ApiService;
 use crate::types::*;
 
+const PER_PAGE: usize = 25;
+
 #[derive(Properties, PartialEq)]
 pub struct AdminPanelProps {
     pub auth_token: Option<String>,
+    pub role: Option<Role>,
     pub is_authenticated: bool,
 }
 
 pub enum AdminPanelMsg {
     LoadBadges,
     BadgesLoaded(BadgeListResponse),
+    NamespacesLoaded(NamespaceListResponse),
+    UpdateQuery(String),
+    UpdateSort(String),
+    UpdateOrder(String),
+    UpdateMinCount(String),
+    UpdateInactiveSince(String),
+    UpdateTag(String),
+    UpdateOwner(String),
+    FilterTag(String),
+    ApplyFilters,
+    PreviousPage,
+    NextPage,
+    SelectNamespace(String),
     LoadError(String),
     ShowCreateModal,
     HideCreateModal,
-    CreateBadge(String, Option<u32>),
+    CreateBadge(String, Option<u64>),
     BadgeCreated(BadgeResponse),
     CreateError(String),
-    EditBadge(String, u32),
-    UpdateBadge(String, u32),
+    ShowDetail(String),
+    DetailLoaded(BadgeResponse),
+    HideDetail,
+    SaveMetadata(BadgeMetadata),
+    DetailSaved,
+    SavePeriod(PeriodRequest),
+    EditBadge(String, u64),
+    UpdateBadge(String, u64),
     BadgeUpdated(BadgeResponse),
     UpdateError(String),
     DeleteBadge(String),
     BadgeDeleted(String),
     DeleteError(String),
+    ConfirmAction,
+    CancelConfirm,
+    UndoDelete,
+    HideUndo,
+    ToggleTrash,
+    LoadTrash,
+    TrashLoaded(TrashListResponse),
+    RestoreBadge(String),
+    BadgeRestored(BadgeResponse),
+    PurgeBadge(String),
+    BadgePurged(String),
+    RenameBadge(String),
+    ToggleSelected(String),
+    TogglePageSelected,
+    SelectMatching,
+    ClearSelection,
+    Bulk(BulkAction),
+    RunBulk(BulkRequest),
+    BulkDone,
+    MergeSelected,
+    BadgesMoved,
+    AddAlias(String),
+    RemoveAlias(String),
+    RotateSecret(String),
+    SecretRotated(SigningSecretResponse),
+    DisableSigning(String),
+    Export(&'static str),
+    Exported(&'static str, String),
+    UpdateImportFormat(String),
+    UpdateImportStrategy(String),
+    ImportFile(Option<web_sys::File>),
+    ImportRead(String),
+    ImportPreviewed(String, ImportReport),
+    Imported(ImportReport),
+}
+
+/// Operation of the bulk action bar, before its parameters are asked for.
+#[derive(Clone, Copy)]
+pub enum BulkAction {
+    Delete,
+    Reset,
+    AddOffset,
+    Tag,
+    Untag,
+    Move,
+}
+
+/// Destructive action waiting for confirmation.
+pub enum PendingAction {
+    Delete(String),
+    Purge(String),
+    /// Bulk operation, and the number of badges it applies to
+    Bulk(BulkRequest, usize),
+    /// Import of the file contents, after its dry run
+    Import(String, ImportReport),
 }
 
 pub struct AdminPanel {
     badges: Vec<BadgeResponse>,
+    /// Badges and visits matching the filter, over all pages
+    total: usize,
+    visits: u64,
+    page: usize,
+    filter: BadgeFilter,
+    trash: Vec<TrashedBadgeResponse>,
     loading: bool,
     error: Option<String>,
     show_create_modal: bool,
+    /// Badge shown in the detail view, with its history
+    detail: Option<BadgeResponse>,
+    show_trash: bool,
+    confirm: Option<PendingAction>,
+    undo: Option<String>,
+    undo_timeout: Option<Timeout>,
+    /// Badges checked for bulk operations and merging
+    selected: Vec<String>,
+    /// Whether bulk operations apply to every badge matching the filter, not just the checked ones
+    select_matching: bool,
+    /// Namespaces the user can administer, and the one shown
+    namespaces: Vec<NamespaceResponse>,
+    namespace: String,
+    /// Format and merge strategy of imports, and the file being read
+    import_format: String,
+    import_strategy: String,
+    import_reader: Option<FileReader>,
+    /// URL of the last export, kept until the download started
+    export_url: Option<ObjectUrl>,
 }
 
 impl Component for AdminPanel {
@@ -48,9 +155,27 @@ impl Component for AdminPanel {
 
         Self {
             badges: Vec::new(),
+            total: 0,
+            visits: 0,
+            page: 1,
+            filter: BadgeFilter::default(),
+            trash: Vec::new(),
             loading: false,
             error: None,
             show_create_modal: false,
+            detail: None,
+            show_trash: false,
+            confirm: None,
+            undo: None,
+            undo_timeout: None,
+            selected: Vec::new(),
+            select_matching: false,
+            namespaces: Vec::new(),
+            namespace: ApiService::get_namespace(),
+            import_format: "auto".to_string(),
+            import_strategy: "keep-max".to_string(),
+            import_reader: None,
+            export_url: None,
         }
     }
 
@@ -73,21 +198,98 @@ impl Component for AdminPanel {
                     self.error = None;
 
                     let token = token.clone();
+                    let filter = self.filter.clone();
+                    let page = self.page;
                     let link = ctx.link().clone();
                     wasm_bindgen_futures::spawn_local(async move {
-                        match ApiService::fetch_badges(&token).await {
+                        match ApiService::fetch_badges(&token, &filter, page, PER_PAGE).await {
                             Ok(response) => link.send_message(AdminPanelMsg::BadgesLoaded(response)),
                             Err(error) => link.send_message(AdminPanelMsg::LoadError(error)),
                         }
+                        if let Ok(response) = ApiService::fetch_namespaces(&token).await {
+                            link.send_message(AdminPanelMsg::NamespacesLoaded(response));
+                        }
                     });
                 }
                 true
             }
             AdminPanelMsg::BadgesLoaded(response) => {
                 self.badges = response.badges;
+                self.total = response.total;
+                self.visits = response.visits;
+                self.page = response.page;
                 self.loading = false;
                 true
             }
+            AdminPanelMsg::NamespacesLoaded(response) => {
+                self.namespaces = response.namespaces;
+                true
+            }
+            AdminPanelMsg::UpdateQuery(value) => {
+                self.filter.query = value;
+                false
+            }
+            AdminPanelMsg::UpdateSort(value) => {
+                self.filter.sort = value;
+                false
+            }
+            AdminPanelMsg::UpdateOrder(value) => {
+                self.filter.order = value;
+                false
+            }
+            AdminPanelMsg::UpdateMinCount(value) => {
+                self.filter.min_count = value;
+                false
+            }
+            AdminPanelMsg::UpdateInactiveSince(value) => {
+                self.filter.inactive_since = value;
+                false
+            }
+            AdminPanelMsg::UpdateTag(value) => {
+                self.filter.tag = value;
+                false
+            }
+            AdminPanelMsg::UpdateOwner(value) => {
+                self.filter.owner = value;
+                false
+            }
+            AdminPanelMsg::FilterTag(tag) => {
+                self.filter.tag = tag;
+                ctx.link().send_message(AdminPanelMsg::ApplyFilters);
+                true
+            }
+            AdminPanelMsg::ApplyFilters => {
+                self.page = 1;
+                self.select_matching = false;
+                ctx.link().send_message(AdminPanelMsg::LoadBadges);
+                false
+            }
+            AdminPanelMsg::PreviousPage => {
+                if self.page > 1 {
+                    self.page -= 1;
+                    ctx.link().send_message(AdminPanelMsg::LoadBadges);
+                }
+                false
+            }
+            AdminPanelMsg::NextPage => {
+                if self.page * PER_PAGE < self.total {
+                    self.page += 1;
+                    ctx.link().send_message(AdminPanelMsg::LoadBadges);
+                }
+                false
+            }
+            AdminPanelMsg::SelectNamespace(namespace) => {
+                ApiService::set_namespace(&namespace);
+                self.namespace = namespace;
+                self.page = 1;
+                self.selected.clear();
+                self.select_matching = false;
+                ctx.link().send_message(AdminPanelMsg::LoadBadges);
+                if self.show_trash {
+                    ctx.link().send_message(AdminPanelMsg::LoadTrash);
+                }
+                true
+            }
             AdminPanelMsg::LoadError(error) => {
                 self.error = Some(error);
                 self.loading = false;
@@ -114,21 +316,73 @@ impl Component for AdminPanel {
                 }
                 false
             }
-            AdminPanelMsg::BadgeCreated(badge) => {
-                self.badges.push(badge);
+            AdminPanelMsg::BadgeCreated(_badge) => {
+                // Reload the page, so the new badge lands where the sort puts it
                 self.show_create_modal = false;
+                ctx.link().send_message(AdminPanelMsg::LoadBadges);
                 true
             }
             AdminPanelMsg::CreateError(error) => {
                 self.error = Some(error);
                 false
             }
+            AdminPanelMsg::ShowDetail(name) => {
+                if let Some(token) = ctx.props().auth_token.clone() {
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::fetch_badge(&token, &name).await {
+                            Ok(badge) => link.send_message(AdminPanelMsg::DetailLoaded(badge)),
+                            Err(error) => link.send_message(AdminPanelMsg::UpdateError(error)),
+                        }
+                    });
+                }
+                false
+            }
+            AdminPanelMsg::DetailLoaded(badge) => {
+                self.detail = Some(badge);
+                true
+            }
+            AdminPanelMsg::HideDetail => {
+                self.detail = None;
+                true
+            }
+            AdminPanelMsg::SaveMetadata(metadata) => {
+                if let (Some(token), Some(badge)) = (ctx.props().auth_token.clone(), &self.detail) {
+                    let name = badge.name.clone();
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::update_badge_metadata(&token, &name, &metadata).await {
+                            Ok(_) => link.send_message(AdminPanelMsg::DetailSaved),
+                            Err(error) => link.send_message(AdminPanelMsg::UpdateError(error)),
+                        }
+                    });
+                }
+                false
+            }
+            AdminPanelMsg::SavePeriod(request) => {
+                if let (Some(token), Some(badge)) = (ctx.props().auth_token.clone(), &self.detail) {
+                    let name = badge.name.clone();
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::set_badge_period(&token, &name, &request).await {
+                            Ok(_) => link.send_message(AdminPanelMsg::DetailSaved),
+                            Err(error) => link.send_message(AdminPanelMsg::UpdateError(error)),
+                        }
+                    });
+                }
+                false
+            }
+            AdminPanelMsg::DetailSaved => {
+                self.detail = None;
+                ctx.link().send_message(AdminPanelMsg::LoadBadges);
+                true
+            }
             AdminPanelMsg::EditBadge(name, _current_count) => {
                 if let Some(new_count_str) = web_sys::window()
                     .and_then(|w| w.prompt_with_message(&format!("Enter new count for \"{}\":", name)).ok())
                     .flatten()
                 {
-                    if let Ok(new_count) = new_count_str.parse::<u32>() {
+                    if let Ok(new_count) = new_count_str.parse::<u64>() {
                         ctx.link().send_message(AdminPanelMsg::UpdateBadge(name, new_count));
                     }
                 }
@@ -158,30 +412,414 @@ impl Component for AdminPanel {
                 false
             }
             AdminPanelMsg::DeleteBadge(name) => {
-                if web_sys::window()
-                    .and_then(|w| w.confirm_with_message(&format!("Are you sure you want to delete the badge \"{}\"?", name)).ok())
-                    .unwrap_or(false)
-                {
-                    if let Some(token) = &ctx.props().auth_token {
-                        let token = token.clone();
-                        let name_clone = name.clone();
-                        let link = ctx.link().clone();
-                        wasm_bindgen_futures::spawn_local(async move {
-                            match ApiService::delete_badge(&token, name_clone.clone()).await {
-                                Ok(()) => link.send_message(AdminPanelMsg::BadgeDeleted(name_clone)),
+                self.confirm = Some(PendingAction::Delete(name));
+                true
+            }
+            AdminPanelMsg::PurgeBadge(name) => {
+                self.confirm = Some(PendingAction::Purge(name));
+                true
+            }
+            AdminPanelMsg::CancelConfirm => {
+                self.confirm = None;
+                true
+            }
+            AdminPanelMsg::ConfirmAction => {
+                if let (Some(action), Some(token)) = (self.confirm.take(), ctx.props().auth_token.clone()) {
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match action {
+                            PendingAction::Delete(name) => match ApiService::delete_badge(&token, name.clone()).await {
+                                Ok(()) => link.send_message(AdminPanelMsg::BadgeDeleted(name)),
+                                Err(error) => link.send_message(AdminPanelMsg::DeleteError(error)),
+                            },
+                            PendingAction::Purge(name) => match ApiService::purge_badge(&token, name.clone()).await {
+                                Ok(()) => link.send_message(AdminPanelMsg::BadgePurged(name)),
                                 Err(error) => link.send_message(AdminPanelMsg::DeleteError(error)),
+                            },
+                            PendingAction::Bulk(request, _) => match ApiService::bulk_badges(&token, &request).await {
+                                Ok(_) => link.send_message(AdminPanelMsg::BulkDone),
+                                Err(error) => link.send_message(AdminPanelMsg::UpdateError(error)),
+                            },
+                            PendingAction::Import(body, report) => {
+                                match ApiService::import_counters(&token, body, &report.format.to_string(), &report.strategy.to_string(), false).await {
+                                    Ok(report) => link.send_message(AdminPanelMsg::Imported(report)),
+                                    Err(error) => link.send_message(AdminPanelMsg::UpdateError(error)),
+                                }
                             }
-                        });
-                    }
+                        }
+                    });
                 }
-                false
+                true
             }
             AdminPanelMsg::BadgeDeleted(name) => {
                 self.badges.retain(|b| b.name != name);
+                ctx.link().send_message(AdminPanelMsg::LoadBadges);
+                if self.show_trash {
+                    ctx.link().send_message(AdminPanelMsg::LoadTrash);
+                }
+
+                // Offer to undo the deletion for a few seconds
+                let link = ctx.link().clone();
+                self.undo_timeout = Some(Timeout::new(8000, move || {
+                    link.send_message(AdminPanelMsg::HideUndo);
+                }));
+                self.undo = Some(name);
                 true
             }
             AdminPanelMsg::DeleteError(error) => {
                 self.error = Some(error);
+                true
+            }
+            AdminPanelMsg::UndoDelete => {
+                if let Some(name) = self.undo.take() {
+                    ctx.link().send_message(AdminPanelMsg::RestoreBadge(name));
+                }
+                if let Some(timeout) = self.undo_timeout.take() {
+                    timeout.cancel();
+                }
+                true
+            }
+            AdminPanelMsg::HideUndo => {
+                self.undo = None;
+                if let Some(timeout) = self.undo_timeout.take() {
+                    timeout.cancel();
+                }
+                true
+            }
+            AdminPanelMsg::ToggleTrash => {
+                self.show_trash = !self.show_trash;
+                if self.show_trash {
+                    ctx.link().send_message(AdminPanelMsg::LoadTrash);
+                }
+                true
+            }
+            AdminPanelMsg::LoadTrash => {
+                if let Some(token) = &ctx.props().auth_token {
+                    let token = token.clone();
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::fetch_trash(&token).await {
+                            Ok(response) => link.send_message(AdminPanelMsg::TrashLoaded(response)),
+                            Err(error) => link.send_message(AdminPanelMsg::LoadError(error)),
+                        }
+                    });
+                }
+                false
+            }
+            AdminPanelMsg::TrashLoaded(response) => {
+                self.trash = response.badges;
+                true
+            }
+            AdminPanelMsg::RestoreBadge(name) => {
+                if let Some(token) = &ctx.props().auth_token {
+                    let token = token.clone();
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::restore_badge(&token, name).await {
+                            Ok(badge) => link.send_message(AdminPanelMsg::BadgeRestored(badge)),
+                            Err(error) => link.send_message(AdminPanelMsg::UpdateError(error)),
+                        }
+                    });
+                }
+                false
+            }
+            AdminPanelMsg::BadgeRestored(badge) => {
+                self.trash.retain(|b| b.name != badge.name);
+                ctx.link().send_message(AdminPanelMsg::LoadBadges);
+                true
+            }
+            AdminPanelMsg::BadgePurged(name) => {
+                self.trash.retain(|b| b.name != name);
+                true
+            }
+            AdminPanelMsg::RenameBadge(name) => {
+                let new_name = web_sys::window()
+                    .and_then(|w| w.prompt_with_message_and_default(&format!("Rename \"{}\" to:", name), &name).ok())
+                    .flatten()
+                    .map(|new_name| new_name.trim().to_string())
+                    .filter(|new_name| !new_name.is_empty() && *new_name != name);
+
+                if let (Some(new_name), Some(token)) = (new_name, ctx.props().auth_token.clone()) {
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::rename_badge(&token, name, new_name).await {
+                            Ok(_) => link.send_message(AdminPanelMsg::BadgesMoved),
+                            Err(error) => link.send_message(AdminPanelMsg::UpdateError(error)),
+                        }
+                    });
+                }
+                false
+            }
+            AdminPanelMsg::ToggleSelected(name) => {
+                self.select_matching = false;
+                if self.selected.contains(&name) {
+                    self.selected.retain(|selected| *selected != name);
+                } else {
+                    self.selected.push(name);
+                }
+                true
+            }
+            AdminPanelMsg::TogglePageSelected => {
+                self.select_matching = false;
+                if self.page_selected() {
+                    self.selected.retain(|name| !self.badges.iter().any(|badge| badge.name == *name));
+                } else {
+                    for badge in &self.badges {
+                        if !self.selected.contains(&badge.name) {
+                            self.selected.push(badge.name.clone());
+                        }
+                    }
+                }
+                true
+            }
+            AdminPanelMsg::SelectMatching => {
+                self.select_matching = true;
+                true
+            }
+            AdminPanelMsg::ClearSelection => {
+                self.selected.clear();
+                self.select_matching = false;
+                true
+            }
+            AdminPanelMsg::Bulk(action) => {
+                let count = self.selection_count();
+                let prompt = |message: &str, default: &str| {
+                    web_sys::window()
+                        .and_then(|w| w.prompt_with_message_and_default(message, default).ok())
+                        .flatten()
+                        .map(|value| value.trim().to_string())
+                        .filter(|value| !value.is_empty())
+                };
+                let tags = |message: &str| {
+                    prompt(message, "").map(|tags| {
+                        tags.split(',').map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()).collect::<Vec<_>>()
+                    })
+                };
+
+                let operation = match action {
+                    BulkAction::Delete | BulkAction::Reset => {
+                        let operation = if matches!(action, BulkAction::Delete) { BulkOperation::Delete } else { BulkOperation::Reset };
+                        self.confirm = Some(PendingAction::Bulk(self.bulk_request(operation), count));
+                        return true;
+                    }
+                    BulkAction::AddOffset => prompt(
+                        &format!("Add how many visits to the {} selected badges? Use a negative number to subtract.", count), ""
+                    )
+                        .and_then(|offset| offset.parse().ok())
+                        .map(|offset| BulkOperation::AddOffset { offset }),
+                    BulkAction::Tag => tags(&format!("Tags to add to the {} selected badges, separated by commas:", count))
+                        .map(|tags| BulkOperation::Tag { tags }),
+                    BulkAction::Untag => tags(&format!("Tags to remove from the {} selected badges, separated by commas:", count))
+                        .map(|tags| BulkOperation::Untag { tags }),
+                    BulkAction::Move => {
+                        let other = self.namespaces.iter()
+                            .map(|namespace| namespace.name.as_str())
+                            .find(|namespace| *namespace != self.namespace)
+                            .unwrap_or_default();
+                        prompt(&format!("Move the {} selected badges to which namespace?", count), other)
+                            .map(|namespace| BulkOperation::Move { namespace })
+                    }
+                };
+                if let Some(operation) = operation {
+                    ctx.link().send_message(AdminPanelMsg::RunBulk(self.bulk_request(operation)));
+                }
+                false
+            }
+            AdminPanelMsg::RunBulk(request) => {
+                if let Some(token) = ctx.props().auth_token.clone() {
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::bulk_badges(&token, &request).await {
+                            Ok(_) => link.send_message(AdminPanelMsg::BulkDone),
+                            Err(error) => link.send_message(AdminPanelMsg::UpdateError(error)),
+                        }
+                    });
+                }
+                false
+            }
+            AdminPanelMsg::BulkDone => {
+                self.selected.clear();
+                self.select_matching = false;
+                ctx.link().send_message(AdminPanelMsg::LoadBadges);
+                if self.show_trash {
+                    ctx.link().send_message(AdminPanelMsg::LoadTrash);
+                }
+                true
+            }
+            AdminPanelMsg::MergeSelected => {
+                let target = web_sys::window()
+                    .and_then(|w| w.prompt_with_message(&format!(
+                        "Merge {} into which badge? Enter an existing or a new name:",
+                        self.selected.join(", ")
+                    )).ok())
+                    .flatten()
+                    .map(|target| target.trim().to_string())
+                    .filter(|target| !target.is_empty());
+
+                if let (Some(target), Some(token)) = (target, ctx.props().auth_token.clone()) {
+                    let sources: Vec<String> = self.selected.iter()
+                        .filter(|name| **name != target)
+                        .cloned()
+                        .collect();
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::merge_badges(&token, sources, target).await {
+                            Ok(_) => link.send_message(AdminPanelMsg::BadgesMoved),
+                            Err(error) => link.send_message(AdminPanelMsg::UpdateError(error)),
+                        }
+                    });
+                }
+                false
+            }
+            AdminPanelMsg::AddAlias(target) => {
+                let alias = web_sys::window()
+                    .and_then(|w| w.prompt_with_message(&format!("New alias for \"{}\":", target)).ok())
+                    .flatten()
+                    .map(|alias| alias.trim().to_string())
+                    .filter(|alias| !alias.is_empty());
+
+                if let (Some(alias), Some(token)) = (alias, ctx.props().auth_token.clone()) {
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::add_alias(&token, alias, target).await {
+                            Ok(_) => link.send_message(AdminPanelMsg::LoadBadges),
+                            Err(error) => link.send_message(AdminPanelMsg::UpdateError(error)),
+                        }
+                    });
+                }
+                false
+            }
+            AdminPanelMsg::RemoveAlias(alias) => {
+                if let Some(token) = ctx.props().auth_token.clone() {
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::remove_alias(&token, alias).await {
+                            Ok(()) => link.send_message(AdminPanelMsg::LoadBadges),
+                            Err(error) => link.send_message(AdminPanelMsg::UpdateError(error)),
+                        }
+                    });
+                }
+                false
+            }
+            AdminPanelMsg::RotateSecret(name) => {
+                let confirmed = web_sys::window()
+                    .and_then(|w| w.confirm_with_message(&format!(
+                        "Generate a new signing secret for \"{}\"? Only signed badge URLs will count visits.", name
+                    )).ok())
+                    .unwrap_or(false);
+
+                if let (true, Some(token)) = (confirmed, ctx.props().auth_token.clone()) {
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::rotate_signing_secret(&token, name).await {
+                            Ok(secret) => link.send_message(AdminPanelMsg::SecretRotated(secret)),
+                            Err(error) => link.send_message(AdminPanelMsg::UpdateError(error)),
+                        }
+                    });
+                }
+                false
+            }
+            AdminPanelMsg::SecretRotated(secret) => {
+                let mut message = format!(
+                    "Signing secret for \"{}\":\n\n{}\n\nPaste it into the badge generator to build signed URLs.",
+                    secret.name, secret.secret
+                );
+                if let Some(expires_at) = &secret.previous_expires_at {
+                    message.push_str(&format!(" URLs signed with the previous secret count until {}.", expires_at));
+                }
+                if let Some(window) = web_sys::window() {
+                    let _ = window.prompt_with_message_and_default(&message, &secret.secret);
+                }
+                ctx.link().send_message(AdminPanelMsg::LoadBadges);
+                false
+            }
+            AdminPanelMsg::DisableSigning(name) => {
+                if let Some(token) = ctx.props().auth_token.clone() {
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::disable_signing(&token, name).await {
+                            Ok(()) => link.send_message(AdminPanelMsg::LoadBadges),
+                            Err(error) => link.send_message(AdminPanelMsg::UpdateError(error)),
+                        }
+                    });
+                }
+                false
+            }
+            AdminPanelMsg::BadgesMoved => {
+                self.selected.clear();
+                ctx.link().send_message(AdminPanelMsg::LoadBadges);
+                false
+            }
+            AdminPanelMsg::Export(format) => {
+                if let Some(token) = ctx.props().auth_token.clone() {
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::export_counters(&token, format).await {
+                            Ok(export) => link.send_message(AdminPanelMsg::Exported(format, export)),
+                            Err(error) => link.send_message(AdminPanelMsg::UpdateError(error)),
+                        }
+                    });
+                }
+                false
+            }
+            AdminPanelMsg::Exported(format, export) => {
+                let mime = if format == "csv" { "text/csv" } else { "application/json" };
+                let url = ObjectUrl::from(Blob::new_with_options(export.as_str(), Some(mime)));
+                let link = gloo::utils::document()
+                    .create_element("a")
+                    .ok()
+                    .and_then(|link| link.dyn_into::<HtmlElement>().ok());
+                if let Some(link) = link {
+                    let _ = link.set_attribute("href", &url);
+                    let _ = link.set_attribute("download", &format!("counters-{}.{}", self.namespace, format));
+                    link.click();
+                }
+                self.export_url = Some(url);
+                false
+            }
+            AdminPanelMsg::UpdateImportFormat(format) => {
+                self.import_format = format;
+                false
+            }
+            AdminPanelMsg::UpdateImportStrategy(strategy) => {
+                self.import_strategy = strategy;
+                false
+            }
+            AdminPanelMsg::ImportFile(file) => {
+                if let Some(file) = file {
+                    let link = ctx.link().clone();
+                    self.import_reader = Some(read_as_text(&File::from(file), move |result| match result {
+                        Ok(contents) => link.send_message(AdminPanelMsg::ImportRead(contents)),
+                        Err(error) => link.send_message(AdminPanelMsg::UpdateError(format!("Failed to read the file: {}", error))),
+                    }));
+                }
+                false
+            }
+            AdminPanelMsg::ImportRead(contents) => {
+                self.import_reader = None;
+                // Dry run first, so the changes can be confirmed
+                if let Some(token) = ctx.props().auth_token.clone() {
+                    let format = self.import_format.clone();
+                    let strategy = self.import_strategy.clone();
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::import_counters(&token, contents.clone(), &format, &strategy, true).await {
+                            Ok(report) => link.send_message(AdminPanelMsg::ImportPreviewed(contents, report)),
+                            Err(error) => link.send_message(AdminPanelMsg::UpdateError(error)),
+                        }
+                    });
+                }
+                false
+            }
+            AdminPanelMsg::ImportPreviewed(contents, report) => {
+                self.confirm = Some(PendingAction::Import(contents, report));
+                true
+            }
+            AdminPanelMsg::Imported(report) => {
+                if let Some(window) = web_sys::window() {
+                    let _ = window.alert_with_message(&format!("Import finished: {}", import_summary(&report)));
+                }
+                ctx.link().send_message(AdminPanelMsg::LoadBadges);
                 false
             }
         }
@@ -220,14 +858,95 @@ impl Component for AdminPanel {
 
         let on_modal_submit = {
             let link = ctx.link().clone();
-            Callback::from(move |(name, count): (String, Option<u32>)| {
+            Callback::from(move |(name, count): (String, Option<u64>)| {
                 link.send_message(AdminPanelMsg::CreateBadge(name, count))
             })
         };
 
+        let can_edit = ctx.props().role.is_some_and(|role| role.can_edit());
+
+        let on_toggle_trash = {
+            let link = ctx.link().clone();
+            Callback::from(move |_| link.send_message(AdminPanelMsg::ToggleTrash))
+        };
+
+        let on_confirm = {
+            let link = ctx.link().clone();
+            Callback::from(move |_| link.send_message(AdminPanelMsg::ConfirmAction))
+        };
+
+        let on_confirm_close = {
+            let link = ctx.link().clone();
+            Callback::from(move |_| link.send_message(AdminPanelMsg::CancelConfirm))
+        };
+
+        let on_merge = {
+            let link = ctx.link().clone();
+            Callback::from(move |_| link.send_message(AdminPanelMsg::MergeSelected))
+        };
+
+        let on_export_json = ctx.link().callback(|_| AdminPanelMsg::Export("json"));
+        let on_export_csv = ctx.link().callback(|_| AdminPanelMsg::Export("csv"));
+        let on_import_format = ctx.link().callback(|e: Event| {
+            AdminPanelMsg::UpdateImportFormat(e.target_unchecked_into::<HtmlSelectElement>().value())
+        });
+        let on_import_strategy = ctx.link().callback(|e: Event| {
+            AdminPanelMsg::UpdateImportStrategy(e.target_unchecked_into::<HtmlSelectElement>().value())
+        });
+        let on_import_file = ctx.link().callback(|e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let file = input.files().and_then(|files| files.get(0));
+            // Clear the input, so the same file can be picked again
+            input.set_value("");
+            AdminPanelMsg::ImportFile(file)
+        });
+
+        let link = ctx.link();
+        let on_namespace = link.callback(|e: Event| {
+            AdminPanelMsg::SelectNamespace(e.target_unchecked_into::<HtmlSelectElement>().value())
+        });
+        let on_query = link.callback(|e: InputEvent| {
+            AdminPanelMsg::UpdateQuery(e.target_unchecked_into::<HtmlInputElement>().value())
+        });
+        let on_sort = link.callback(|e: Event| {
+            AdminPanelMsg::UpdateSort(e.target_unchecked_into::<HtmlSelectElement>().value())
+        });
+        let on_order = link.callback(|e: Event| {
+            AdminPanelMsg::UpdateOrder(e.target_unchecked_into::<HtmlSelectElement>().value())
+        });
+        let on_min_count = link.callback(|e: InputEvent| {
+            AdminPanelMsg::UpdateMinCount(e.target_unchecked_into::<HtmlInputElement>().value())
+        });
+        let on_inactive_since = link.callback(|e: InputEvent| {
+            AdminPanelMsg::UpdateInactiveSince(e.target_unchecked_into::<HtmlInputElement>().value())
+        });
+        let on_owner = link.callback(|e: InputEvent| {
+            AdminPanelMsg::UpdateOwner(e.target_unchecked_into::<HtmlInputElement>().value())
+        });
+        let on_tag = link.callback(|e: InputEvent| {
+            AdminPanelMsg::UpdateTag(e.target_unchecked_into::<HtmlInputElement>().value())
+        });
+        let on_filter = link.callback(|e: SubmitEvent| {
+            e.prevent_default();
+            AdminPanelMsg::ApplyFilters
+        });
+        let on_previous = link.callback(|_| AdminPanelMsg::PreviousPage);
+        let on_next = link.callback(|_| AdminPanelMsg::NextPage);
+        let pages = self.total.div_ceil(PER_PAGE).max(1);
+
+        let on_undo = {
+            let link = ctx.link().clone();
+            Callback::from(move |_| link.send_message(AdminPanelMsg::UndoDelete))
+        };
+
+        let on_undo_close = {
+            let link = ctx.link().clone();
+            Callback::from(move |_| link.send_message(AdminPanelMsg::HideUndo))
+        };
+
         // Calculate stats
-        let total_badges = self.badges.len();
-        let total_visits: u32 = self.badges.iter().map(|b| b.count).sum();
+        let total_badges = self.total;
+        let total_visits = self.visits;
         let recent_activity = self.badges.len(); // Simplified for now
 
         html! {
@@ -236,6 +955,16 @@ impl Component for AdminPanel {
                     <div class="card">
                         <div class="card-header">
                             <h2><i class="fas fa-shield-alt"></i> { " Admin Panel" }</h2>
+                            // Users of a single namespace only get that one back
+                            if self.namespaces.len() > 1 {
+                                <select class="form-input namespace-select" onchange={on_namespace}>
+                                    { for self.namespaces.iter().map(|namespace| html! {
+                                        <option value={namespace.name.clone()} selected={namespace.name == self.namespace}>
+                                            { &namespace.name }
+                                        </option>
+                                    }) }
+                                </select>
+                            }
                             <button class="btn btn-secondary" onclick={on_refresh}>
                                 <i class="fas fa-sync-alt"></i> { " Refresh" }
                             </button>
@@ -269,20 +998,130 @@ impl Component for AdminPanel {
 
                                     <div class="card-header">
                                         <h3><i class="fas fa-list"></i> { " Badge Management" }</h3>
-                                        <button class="btn btn-primary" onclick={on_create_badge}>
-                                            <i class="fas fa-plus"></i> { " Create Badge" }
-                                        </button>
+                                        <div class="badge-actions">
+                                            <button class="btn btn-secondary" onclick={on_toggle_trash}>
+                                                <i class="fas fa-trash"></i> { if self.show_trash { " Hide Trash" } else { " Trash" } }
+                                            </button>
+                                            <button class="btn btn-secondary" onclick={on_export_json}>
+                                                <i class="fas fa-file-export"></i> { " Export JSON" }
+                                            </button>
+                                            <button class="btn btn-secondary" onclick={on_export_csv}>
+                                                <i class="fas fa-file-csv"></i> { " Export CSV" }
+                                            </button>
+                                            if can_edit {
+                                                <select class="form-input import-strategy" title="Where the imported file comes from"
+                                                        onchange={on_import_format}>
+                                                    { for [("auto", "Export / legacy"), ("csv", "CSV name,count"), ("countapi", "CountAPI"), ("moe-counter", "Moe-Counter"), ("mapping", "Mapping file")].iter().map(|(value, label)| html! {
+                                                        <option value={*value} selected={self.import_format == *value}>{ *label }</option>
+                                                    }) }
+                                                </select>
+                                                <select class="form-input import-strategy" title="How imported counts combine with existing ones"
+                                                        onchange={on_import_strategy}>
+                                                    { for [("keep-max", "Keep max"), ("overwrite", "Overwrite"), ("sum", "Sum"), ("skip-existing", "Skip existing")].iter().map(|(value, label)| html! {
+                                                        <option value={*value} selected={self.import_strategy == *value}>{ *label }</option>
+                                                    }) }
+                                                </select>
+                                                <label class="btn btn-secondary">
+                                                    <i class="fas fa-file-import"></i> { " Import" }
+                                                    <input type="file" accept=".json,.csv,application/json,text/csv" hidden=true onchange={on_import_file} />
+                                                </label>
+                                            }
+                                            if can_edit {
+                                                <button class="btn btn-primary" onclick={on_create_badge}>
+                                                    <i class="fas fa-plus"></i> { " Create Badge" }
+                                                </button>
+                                            }
+                                        </div>
                                     </div>
 
+                                    <form class="form-grid" onsubmit={on_filter}>
+                                        <div class="form-group">
+                                            <label for="badge-search">{ "Search" }</label>
+                                            <input type="search" id="badge-search" class="form-input" placeholder="Name or description"
+                                                   value={self.filter.query.clone()} oninput={on_query} />
+                                        </div>
+                                        <div class="form-group">
+                                            <label for="badge-sort">{ "Sort by" }</label>
+                                            <select id="badge-sort" class="form-input" onchange={on_sort}>
+                                                { for [("", "Name"), ("count", "Count"), ("created_at", "Created"), ("last_accessed", "Last visit")].iter().map(|(value, label)| html! {
+                                                    <option value={*value} selected={self.filter.sort == *value}>{ *label }</option>
+                                                }) }
+                                            </select>
+                                        </div>
+                                        <div class="form-group">
+                                            <label for="badge-order">{ "Order" }</label>
+                                            <select id="badge-order" class="form-input" onchange={on_order}>
+                                                { for [("", "Default"), ("asc", "Ascending"), ("desc", "Descending")].iter().map(|(value, label)| html! {
+                                                    <option value={*value} selected={self.filter.order == *value}>{ *label }</option>
+                                                }) }
+                                            </select>
+                                        </div>
+                                        <div class="form-group">
+                                            <label for="badge-min-count">{ "Min. count" }</label>
+                                            <input type="number" id="badge-min-count" class="form-input" min="0"
+                                                   value={self.filter.min_count.clone()} oninput={on_min_count} />
+                                        </div>
+                                        <div class="form-group">
+                                            <label for="badge-inactive-since">{ "Inactive since" }</label>
+                                            <input type="date" id="badge-inactive-since" class="form-input"
+                                                   value={self.filter.inactive_since.clone()} oninput={on_inactive_since} />
+                                        </div>
+                                        <div class="form-group">
+                                            <label for="badge-tag">{ "Tag" }</label>
+                                            <input type="text" id="badge-tag" class="form-input"
+                                                   value={self.filter.tag.clone()} oninput={on_tag} />
+                                        </div>
+                                        <div class="form-group">
+                                            <label for="badge-owner-filter">{ "Owner" }</label>
+                                            <input type="text" id="badge-owner-filter" class="form-input"
+                                                   value={self.filter.owner.clone()} oninput={on_owner} />
+                                        </div>
+                                        <button type="submit" class="btn btn-primary">{ "Filter" }</button>
+                                    </form>
+
+                                    if can_edit && !self.badges.is_empty() {
+                                        { self.render_bulk_bar(ctx, on_merge) }
+                                    }
+
                                     <div class="badge-list">
-                                        if self.badges.is_empty() {
+                                        if self.badges.is_empty() && self.filter == BadgeFilter::default() {
                                             <p class="text-center" style="padding: 2rem; color: var(--text-secondary);">
                                                 { "No badges found. Create your first badge!" }
                                             </p>
+                                        } else if self.badges.is_empty() {
+                                            <p class="text-center" style="padding: 2rem; color: var(--text-secondary);">
+                                                { "No badges match these filters." }
+                                            </p>
                                         } else {
                                             { for self.badges.iter().map(|badge| self.render_badge_item(ctx, badge)) }
                                         }
                                     </div>
+                                    if self.total > PER_PAGE {
+                                        <div class="pagination">
+                                            <button class="btn btn-secondary btn-small" onclick={on_previous} disabled={self.page <= 1}>
+                                                { "Previous" }
+                                            </button>
+                                            <span>{ format!("Page {} of {} ({} badges)", self.page, pages, self.total) }</span>
+                                            <button class="btn btn-secondary btn-small" onclick={on_next} disabled={self.page >= pages}>
+                                                { "Next" }
+                                            </button>
+                                        </div>
+                                    }
+
+                                    if self.show_trash {
+                                        <div class="card-header">
+                                            <h3><i class="fas fa-trash"></i> { " Trash" }</h3>
+                                        </div>
+                                        <div class="badge-list">
+                                            if self.trash.is_empty() {
+                                                <p class="text-center" style="padding: 2rem; color: var(--text-secondary);">
+                                                    { "The trash is empty." }
+                                                </p>
+                                            } else {
+                                                { for self.trash.iter().map(|badge| self.render_trash_item(ctx, badge)) }
+                                            }
+                                        </div>
+                                    }
                                 </>
                             }
                         </div>
@@ -295,15 +1134,166 @@ impl Component for AdminPanel {
                         on_submit={on_modal_submit}
                     />
                 }
+
+                if let Some(badge) = &self.detail {
+                    <BadgeDetailModal
+                        badge={badge.clone()}
+                        can_edit={ctx.props().role.is_some_and(|role| role.can_edit())}
+                        on_close={link.callback(|_| AdminPanelMsg::HideDetail)}
+                        on_save={link.callback(AdminPanelMsg::SaveMetadata)}
+                        on_save_period={link.callback(AdminPanelMsg::SavePeriod)}
+                    />
+                }
+
+                {
+                    match &self.confirm {
+                        Some(PendingAction::Delete(name)) => html! {
+                            <ConfirmModal
+                                title="Delete Badge"
+                                message={format!("Move the badge \"{}\" to the trash? It can be restored from the trash until it is purged.", name)}
+                                confirm_label="Delete"
+                                on_close={on_confirm_close}
+                                on_confirm={on_confirm}
+                            />
+                        },
+                        Some(PendingAction::Purge(name)) => html! {
+                            <ConfirmModal
+                                title="Delete Permanently"
+                                message={format!("Permanently delete the badge \"{}\" and its count? This cannot be undone.", name)}
+                                confirm_label="Delete Permanently"
+                                on_close={on_confirm_close}
+                                on_confirm={on_confirm}
+                            />
+                        },
+                        Some(PendingAction::Bulk(request, count)) => html! {
+                            <ConfirmModal
+                                title={if request.operation == BulkOperation::Delete { "Delete Badges" } else { "Reset Badges" }}
+                                message={if request.operation == BulkOperation::Delete {
+                                    format!("Move the {} selected badges to the trash? They can be restored from the trash until they are purged.", count)
+                                } else {
+                                    format!("Reset the counts of the {} selected badges to zero? This cannot be undone.", count)
+                                }}
+                                confirm_label={if request.operation == BulkOperation::Delete { "Delete" } else { "Reset" }}
+                                on_close={on_confirm_close}
+                                on_confirm={on_confirm}
+                            />
+                        },
+                        Some(PendingAction::Import(_, report)) => html! {
+                            <ConfirmModal
+                                title="Import Counters"
+                                message={format!("Import with the {} strategy? {}", report.strategy, import_summary(report))}
+                                confirm_label="Import"
+                                on_close={on_confirm_close}
+                                on_confirm={on_confirm}
+                            />
+                        },
+                        None => html! {},
+                    }
+                }
+
+                if let Some(name) = &self.undo {
+                    <UndoToast
+                        message={format!("Badge \"{}\" moved to the trash", name)}
+                        on_undo={on_undo}
+                        on_close={on_undo_close}
+                    />
+                }
             </>
         }
     }
 }
 
 impl AdminPanel {
+    /// Whether every badge of the page is checked.
+    fn page_selected(&self) -> bool {
+        !self.badges.is_empty() && self.badges.iter().all(|badge| self.selected.contains(&badge.name))
+    }
+
+    /// Number of badges a bulk operation applies to.
+    fn selection_count(&self) -> usize {
+        if self.select_matching { self.total } else { self.selected.len() }
+    }
+
+    /// Bulk request for the checked badges, or for the filter when every matching badge is selected.
+    fn bulk_request(&self, operation: BulkOperation) -> BulkRequest {
+        if !self.select_matching {
+            return BulkRequest { names: self.selected.clone(), filter: None, operation };
+        }
+        let text = |value: &String| Some(value.clone()).filter(|value| !value.is_empty());
+        BulkRequest {
+            names: Vec::new(),
+            filter: Some(BulkFilter {
+                q: text(&self.filter.query),
+                min_count: self.filter.min_count.parse().ok(),
+                inactive_since: text(&self.filter.inactive_since),
+                tag: text(&self.filter.tag),
+                owner: text(&self.filter.owner),
+            }),
+            operation,
+        }
+    }
+
+    fn render_bulk_bar(&self, ctx: &Context<Self>, on_merge: Callback<MouseEvent>) -> Html {
+        let link = ctx.link();
+        let can_delete = ctx.props().role.is_some_and(|role| role.can_delete());
+        let count = self.selection_count();
+        let page_selected = self.page_selected();
+
+        html! {
+            <div class="bulk-bar">
+                <label>
+                    <input type="checkbox" checked={page_selected} onchange={link.callback(|_| AdminPanelMsg::TogglePageSelected)} />
+                    { " Select page" }
+                </label>
+                if count > 0 {
+                    <span>{ format!("{} selected", count) }</span>
+                    if page_selected && !self.select_matching && self.total > self.badges.len() {
+                        <button class="btn btn-secondary btn-small" onclick={link.callback(|_| AdminPanelMsg::SelectMatching)}>
+                            { format!("Select all {} matching", self.total) }
+                        </button>
+                    }
+                    <button class="btn btn-secondary btn-small" onclick={link.callback(|_| AdminPanelMsg::Bulk(BulkAction::AddOffset))}>
+                        <i class="fas fa-plus-minus"></i> { " Add to Count" }
+                    </button>
+                    <button class="btn btn-secondary btn-small" onclick={link.callback(|_| AdminPanelMsg::Bulk(BulkAction::Reset))}>
+                        <i class="fas fa-undo"></i> { " Reset" }
+                    </button>
+                    <button class="btn btn-secondary btn-small" onclick={link.callback(|_| AdminPanelMsg::Bulk(BulkAction::Tag))}>
+                        <i class="fas fa-tag"></i> { " Tag" }
+                    </button>
+                    <button class="btn btn-secondary btn-small" onclick={link.callback(|_| AdminPanelMsg::Bulk(BulkAction::Untag))}>
+                        <i class="fas fa-tag"></i> { " Untag" }
+                    </button>
+                    // Only users of every namespace get more than one back
+                    if self.namespaces.len() > 1 {
+                        <button class="btn btn-secondary btn-small" onclick={link.callback(|_| AdminPanelMsg::Bulk(BulkAction::Move))}>
+                            <i class="fas fa-share"></i> { " Move" }
+                        </button>
+                    }
+                    if !self.select_matching && self.selected.len() > 1 {
+                        <button class="btn btn-secondary btn-small" onclick={on_merge}>
+                            <i class="fas fa-compress-alt"></i> { " Merge" }
+                        </button>
+                    }
+                    if can_delete {
+                        <button class="btn btn-danger btn-small" onclick={link.callback(|_| AdminPanelMsg::Bulk(BulkAction::Delete))}>
+                            <i class="fas fa-trash"></i> { " Delete" }
+                        </button>
+                    }
+                    <button class="btn btn-secondary btn-small" onclick={link.callback(|_| AdminPanelMsg::ClearSelection)}>
+                        { "Clear" }
+                    </button>
+                }
+            </div>
+        }
+    }
+
     fn render_badge_item(&self, ctx: &Context<Self>, badge: &BadgeResponse) -> Html {
         let badge_name = badge.name.clone();
         let badge_count = badge.count;
+        let role = ctx.props().role;
+        let can_edit = role.is_some_and(|role| role.can_edit());
+        let can_delete = role.is_some_and(|role| role.can_delete());
 
         let on_edit = {
             let link = ctx.link().clone();
@@ -321,25 +1311,193 @@ impl AdminPanel {
             })
         };
 
+        let on_rename = {
+            let link = ctx.link().clone();
+            let name = badge_name.clone();
+            Callback::from(move |_| link.send_message(AdminPanelMsg::RenameBadge(name.clone())))
+        };
+
+        let on_add_alias = {
+            let link = ctx.link().clone();
+            let name = badge_name.clone();
+            Callback::from(move |_| link.send_message(AdminPanelMsg::AddAlias(name.clone())))
+        };
+
+        let on_rotate_secret = {
+            let link = ctx.link().clone();
+            let name = badge_name.clone();
+            Callback::from(move |_| link.send_message(AdminPanelMsg::RotateSecret(name.clone())))
+        };
+
+        let on_disable_signing = {
+            let link = ctx.link().clone();
+            let name = badge_name.clone();
+            Callback::from(move |_| link.send_message(AdminPanelMsg::DisableSigning(name.clone())))
+        };
+
+        let on_select = {
+            let link = ctx.link().clone();
+            let name = badge_name.clone();
+            Callback::from(move |_| link.send_message(AdminPanelMsg::ToggleSelected(name.clone())))
+        };
+
+        let on_details = {
+            let link = ctx.link().clone();
+            let name = badge_name.clone();
+            Callback::from(move |_| link.send_message(AdminPanelMsg::ShowDetail(name.clone())))
+        };
+
         html! {
             <div class="badge-item">
+                if can_edit {
+                    <input type="checkbox" title="Select"
+                           checked={self.select_matching || self.selected.contains(&badge.name)} onchange={on_select} />
+                }
                 <div class="badge-info">
-                    <div class="badge-name">{ &badge.name }</div>
+                    <button class="badge-name" title="Show details" onclick={on_details.clone()}>{ &badge.name }</button>
+                    if let Some(description) = &badge.description {
+                        <div class="badge-description">{ description }</div>
+                    }
                     <div class="badge-stats">
                         <span><i class="fas fa-eye"></i> { format!(" {} visits", badge.count) }</span>
                         <span><i class="fas fa-calendar"></i> { format!(" Created {}", badge.created_at) }</span>
                         <span><i class="fas fa-clock"></i> { format!(" Last accessed {}", badge.last_accessed) }</span>
+                        if badge.signed {
+                            <span><i class="fas fa-lock"></i> { " Signed URLs only" }</span>
+                        }
+                        if let Some(period) = &badge.period {
+                            <span><i class="fas fa-redo"></i> { format!(" {} {} since {}", period.current, period.period, period.start) }</span>
+                        }
                     </div>
+                    if !badge.aliases.is_empty() {
+                        <div class="badge-aliases">
+                            <i class="fas fa-link"></i>
+                            { for badge.aliases.iter().map(|alias| self.render_alias(ctx, alias, can_edit)) }
+                        </div>
+                    }
+                    if !badge.tags.is_empty() {
+                        <div class="badge-tags">
+                            <i class="fas fa-tag"></i>
+                            { for badge.tags.iter().map(|tag| {
+                                let on_tag = {
+                                    let tag = tag.clone();
+                                    ctx.link().callback(move |_| AdminPanelMsg::FilterTag(tag.clone()))
+                                };
+                                html! {
+                                    <button class="tag-chip" title="Show the badges with this tag" onclick={on_tag}>{ tag }</button>
+                                }
+                            }) }
+                        </div>
+                    }
                 </div>
                 <div class="badge-actions">
-                    <button class="btn btn-secondary btn-small" onclick={on_edit}>
-                        <i class="fas fa-edit"></i> { " Edit" }
-                    </button>
-                    <button class="btn btn-danger btn-small" onclick={on_delete}>
-                        <i class="fas fa-trash"></i> { " Delete" }
+                    <button class="btn btn-secondary btn-small" onclick={on_details}>
+                        <i class="fas fa-info-circle"></i> { " Details" }
                     </button>
+                    if can_edit {
+                        <button class="btn btn-secondary btn-small" onclick={on_edit}>
+                            <i class="fas fa-edit"></i> { " Edit" }
+                        </button>
+                        <button class="btn btn-secondary btn-small" onclick={on_rename}>
+                            <i class="fas fa-i-cursor"></i> { " Rename" }
+                        </button>
+                        <button class="btn btn-secondary btn-small" onclick={on_add_alias}>
+                            <i class="fas fa-link"></i> { " Alias" }
+                        </button>
+                        <button class="btn btn-secondary btn-small" onclick={on_rotate_secret}>
+                            <i class="fas fa-key"></i> { if badge.signed { " Rotate Secret" } else { " Require Signing" } }
+                        </button>
+                    }
+                    if can_delete && badge.signed {
+                        <button class="btn btn-secondary btn-small" onclick={on_disable_signing}>
+                            <i class="fas fa-lock-open"></i> { " Stop Signing" }
+                        </button>
+                    }
+                    if can_delete {
+                        <button class="btn btn-danger btn-small" onclick={on_delete}>
+                            <i class="fas fa-trash"></i> { " Delete" }
+                        </button>
+                    }
+                </div>
+            </div>
+        }
+    }
+
+    fn render_alias(&self, ctx: &Context<Self>, alias: &str, can_edit: bool) -> Html {
+        let on_remove = {
+            let link = ctx.link().clone();
+            let alias = alias.to_string();
+            Callback::from(move |_| link.send_message(AdminPanelMsg::RemoveAlias(alias.clone())))
+        };
+
+        html! {
+            <span class="alias-tag">
+                { alias }
+                if can_edit {
+                    <button class="alias-remove" title="Remove alias" onclick={on_remove}>{ "×" }</button>
+                }
+            </span>
+        }
+    }
+
+    fn render_trash_item(&self, ctx: &Context<Self>, badge: &TrashedBadgeResponse) -> Html {
+        let role = ctx.props().role;
+        let can_edit = role.is_some_and(|role| role.can_edit());
+        let can_delete = role.is_some_and(|role| role.can_delete());
+
+        let on_restore = {
+            let link = ctx.link().clone();
+            let name = badge.name.clone();
+            Callback::from(move |_| link.send_message(AdminPanelMsg::RestoreBadge(name.clone())))
+        };
+
+        let on_purge = {
+            let link = ctx.link().clone();
+            let name = badge.name.clone();
+            Callback::from(move |_| link.send_message(AdminPanelMsg::PurgeBadge(name.clone())))
+        };
+
+        html! {
+            <div class="badge-item">
+                <div class="badge-info">
+                    <div class="badge-name">{ &badge.name }</div>
+                    <div class="badge-stats">
+                        <span><i class="fas fa-eye"></i> { format!(" {} visits", badge.count) }</span>
+                        <span><i class="fas fa-trash"></i> { format!(" Deleted {}", badge.deleted_at) }</span>
+                        if let Some(purge_at) = &badge.purge_at {
+                            <span><i class="fas fa-clock"></i> { format!(" Purged {}", purge_at) }</span>
+                        }
+                    </div>
+                </div>
+                <div class="badge-actions">
+                    if can_edit {
+                        <button class="btn btn-secondary btn-small" onclick={on_restore}>
+                            <i class="fas fa-sync-alt"></i> { " Restore" }
+                        </button>
+                    }
+                    if can_delete {
+                        <button class="btn btn-danger btn-small" onclick={on_purge}>
+                            <i class="fas fa-trash"></i> { " Delete Forever" }
+                        </button>
+                    }
                 </div>
             </div>
         }
     }
-}
\ No newline at end of file
+}
+
+/// Counts of an import report, followed by the counters that failed.
+fn import_summary(report: &ImportReport) -> String {
+    let mut summary = format!(
+        "{} created, {} updated, {} unchanged, {} skipped, {} failed.",
+        report.created, report.updated, report.unchanged, report.skipped, report.failed
+    );
+    let aliases: usize = report.changes.iter().map(|change| change.aliases.len()).sum();
+    if aliases > 0 {
+        summary.push_str(&format!(" {} old names kept as aliases.", aliases));
+    }
+    for change in report.changes.iter().filter(|change| change.outcome == ImportOutcome::Failed) {
+        summary.push_str(&format!(" {}: {}.", change.name, change.error.as_deref().unwrap_or("failed")));
+    }
+    summary
+}
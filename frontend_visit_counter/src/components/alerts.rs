@@ -23,4 +23,33 @@ pub fn alert(props: &AlertProps) -> Html {
             </button>
         </div>
     }
-}
\ No newline at end of file
+}
+
+#[derive(Properties, PartialEq)]
+pub struct UndoToastProps {
+    pub message: String,
+    pub on_undo: Callback<()>,
+    pub on_close: Callback<()>,
+}
+
+/// Notification with an undo button, shown at the bottom of the screen.
+#[function_component(UndoToast)]
+pub fn undo_toast(props: &UndoToastProps) -> Html {
+    let on_undo = {
+        let on_undo = props.on_undo.clone();
+        Callback::from(move |_| on_undo.emit(()))
+    };
+
+    let on_close = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |_| on_close.emit(()))
+    };
+
+    html! {
+        <div class="toast">
+            <span>{ &props.message }</span>
+            <button class="btn btn-secondary btn-small" onclick={on_undo}>{ "Undo" }</button>
+            <button class="close-btn" onclick={on_close}>{ "×" }</button>
+        </div>
+    }
+}
@@ -0,0 +1,261 @@
+// frontend_visit_counter/src/components/audit_log.rs
+use yew::prelude::*;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+
+use crate::services::ApiService;
+use crate::types::*;
+
+const PER_PAGE: usize = 25;
+
+#[derive(Properties, PartialEq)]
+pub struct AuditLogProps {
+    pub auth_token: Option<String>,
+    pub is_authenticated: bool,
+}
+
+pub enum AuditLogMsg {
+    Load,
+    Loaded(AuditListResponse),
+    LoadError(String),
+    UpdateCounter(String),
+    UpdateActor(String),
+    UpdateAction(String),
+    ApplyFilters,
+    PreviousPage,
+    NextPage,
+}
+
+pub struct AuditLog {
+    entries: Vec<AuditEntry>,
+    total: usize,
+    page: usize,
+    filter: AuditFilter,
+    loading: bool,
+    error: Option<String>,
+}
+
+impl Component for AuditLog {
+    type Message = AuditLogMsg;
+    type Properties = AuditLogProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        if ctx.props().is_authenticated {
+            ctx.link().send_message(AuditLogMsg::Load);
+        }
+
+        Self {
+            entries: Vec::new(),
+            total: 0,
+            page: 1,
+            filter: AuditFilter::default(),
+            loading: false,
+            error: None,
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        if ctx.props().is_authenticated {
+            ctx.link().send_message(AuditLogMsg::Load);
+        } else {
+            self.entries.clear();
+            self.error = None;
+        }
+        true
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            AuditLogMsg::Load => {
+                if let Some(token) = &ctx.props().auth_token {
+                    self.loading = true;
+                    self.error = None;
+
+                    let token = token.clone();
+                    let filter = self.filter.clone();
+                    let page = self.page;
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match ApiService::fetch_audit(&token, &filter, page, PER_PAGE).await {
+                            Ok(response) => link.send_message(AuditLogMsg::Loaded(response)),
+                            Err(error) => link.send_message(AuditLogMsg::LoadError(error)),
+                        }
+                    });
+                }
+                true
+            }
+            AuditLogMsg::Loaded(response) => {
+                self.entries = response.entries;
+                self.total = response.total;
+                self.page = response.page;
+                self.loading = false;
+                true
+            }
+            AuditLogMsg::LoadError(error) => {
+                self.error = Some(error);
+                self.loading = false;
+                true
+            }
+            AuditLogMsg::UpdateCounter(value) => {
+                self.filter.counter = value;
+                false
+            }
+            AuditLogMsg::UpdateActor(value) => {
+                self.filter.actor = value;
+                false
+            }
+            AuditLogMsg::UpdateAction(value) => {
+                self.filter.action = value;
+                false
+            }
+            AuditLogMsg::ApplyFilters => {
+                self.page = 1;
+                ctx.link().send_message(AuditLogMsg::Load);
+                false
+            }
+            AuditLogMsg::PreviousPage => {
+                if self.page > 1 {
+                    self.page -= 1;
+                    ctx.link().send_message(AuditLogMsg::Load);
+                }
+                false
+            }
+            AuditLogMsg::NextPage => {
+                if self.page * PER_PAGE < self.total {
+                    self.page += 1;
+                    ctx.link().send_message(AuditLogMsg::Load);
+                }
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        if !ctx.props().is_authenticated {
+            return html! {
+                <div id="audit-section" class="section active">
+                    <div class="card">
+                        <div class="card-header">
+                            <h2><i class="fas fa-clock"></i> { " Audit Log" }</h2>
+                        </div>
+                        <div class="admin-content">
+                            <p class="login-prompt">{ "Please log in to access the audit log." }</p>
+                        </div>
+                    </div>
+                </div>
+            };
+        }
+
+        let link = ctx.link();
+        let on_counter = link.callback(|e: InputEvent| {
+            AuditLogMsg::UpdateCounter(e.target_unchecked_into::<HtmlInputElement>().value())
+        });
+        let on_actor = link.callback(|e: InputEvent| {
+            AuditLogMsg::UpdateActor(e.target_unchecked_into::<HtmlInputElement>().value())
+        });
+        let on_action = link.callback(|e: Event| {
+            AuditLogMsg::UpdateAction(e.target_unchecked_into::<HtmlSelectElement>().value())
+        });
+        let on_submit = link.callback(|e: SubmitEvent| {
+            e.prevent_default();
+            AuditLogMsg::ApplyFilters
+        });
+        let on_refresh = link.callback(|_| AuditLogMsg::Load);
+        let on_previous = link.callback(|_| AuditLogMsg::PreviousPage);
+        let on_next = link.callback(|_| AuditLogMsg::NextPage);
+
+        let pages = self.total.div_ceil(PER_PAGE).max(1);
+
+        html! {
+            <div id="audit-section" class="section active">
+                <div class="card">
+                    <div class="card-header">
+                        <h2><i class="fas fa-clock"></i> { " Audit Log" }</h2>
+                        <button class="btn btn-secondary" onclick={on_refresh}>
+                            <i class="fas fa-sync-alt"></i> { " Refresh" }
+                        </button>
+                    </div>
+
+                    <form class="form-grid" onsubmit={on_submit}>
+                        <div class="form-group">
+                            <label for="audit-counter">{ "Counter" }</label>
+                            <input type="text" id="audit-counter" class="form-input"
+                                   value={self.filter.counter.clone()} oninput={on_counter} />
+                        </div>
+                        <div class="form-group">
+                            <label for="audit-actor">{ "Actor" }</label>
+                            <input type="text" id="audit-actor" class="form-input"
+                                   placeholder="alice or api_key:1f2e..."
+                                   value={self.filter.actor.clone()} oninput={on_actor} />
+                        </div>
+                        <div class="form-group">
+                            <label for="audit-action">{ "Action" }</label>
+                            <select id="audit-action" class="form-input" onchange={on_action}>
+                                <option value="" selected={self.filter.action.is_empty()}>{ "All" }</option>
+                                { for AuditAction::ALL.iter().map(|action| action.to_string()).map(|action| html! {
+                                    <option value={action.clone()} selected={self.filter.action == action}>{ action }</option>
+                                }) }
+                            </select>
+                        </div>
+                        <button type="submit" class="btn btn-primary">{ "Filter" }</button>
+                    </form>
+
+                    <div class="admin-content">
+                        if self.loading {
+                            <div style="text-align: center; padding: 2rem;">
+                                <div class="loading"></div>
+                            </div>
+                        } else if let Some(error) = &self.error {
+                            <p style="text-align: center; padding: 2rem; color: #ef4444;">
+                                { format!("Error: {}", error) }
+                            </p>
+                        } else if self.entries.is_empty() {
+                            <p class="text-center" style="padding: 2rem; color: var(--text-secondary);">
+                                { "No changes recorded." }
+                            </p>
+                        } else {
+                            <div class="badge-list">
+                                { for self.entries.iter().map(Self::render_entry) }
+                            </div>
+                            <div class="pagination">
+                                <button class="btn btn-secondary btn-small" onclick={on_previous} disabled={self.page <= 1}>
+                                    { "Previous" }
+                                </button>
+                                <span>{ format!("Page {} of {} ({} entries)", self.page, pages, self.total) }</span>
+                                <button class="btn btn-secondary btn-small" onclick={on_next} disabled={self.page >= pages}>
+                                    { "Next" }
+                                </button>
+                            </div>
+                        }
+                    </div>
+                </div>
+            </div>
+        }
+    }
+}
+
+impl AuditLog {
+    fn render_entry(entry: &AuditEntry) -> Html {
+        let format_value = |value: Option<u64>| value.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string());
+        let counter = match &entry.target {
+            Some(target) => format!("{} → {}", entry.counter, target),
+            None => entry.counter.clone(),
+        };
+
+        html! {
+            <div class="badge-item">
+                <div class="badge-info">
+                    <div class="badge-name">
+                        { format!("{} {} ({} → {})", entry.action, counter, format_value(entry.old_value), format_value(entry.new_value)) }
+                    </div>
+                    <div class="badge-stats">
+                        <span><i class="fas fa-shield-alt"></i> { format!(" {}", entry.actor) }</span>
+                        <span><i class="fas fa-clock"></i> { format!(" {}", entry.timestamp) }</span>
+                        if let Some(ip) = &entry.client_ip {
+                            <span>{ format!("IP {}", ip) }</span>
+                        }
+                    </div>
+                </div>
+            </div>
+        }
+    }
+}
@@ -1,6 +1,6 @@
 // frontend_visit_counter/src/components/badge_generator.rs
 use yew::prelude::*;
-use web_sys::HtmlInputElement;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
 use gloo::utils::window;
 
 use crate::services::ApiService;
@@ -8,6 +8,7 @@ use crate::types::BadgeConfig;
 
 pub enum BadgeGeneratorMsg {
     UpdateName(String),
+    UpdateNamespace(String),
     UpdateLabel(String),
     UpdateStyle(String),
     UpdateWidth(String),
@@ -25,6 +26,8 @@ pub enum BadgeGeneratorMsg {
     UpdateCounterColor(String),
     UpdateBackgroundLabel(String),
     UpdateBackgroundCounter(String),
+    UpdateSecret(String),
+    UpdatePeriod(String),
     ToggleAdvancedPanel,
     CopyCode,
 }
@@ -52,11 +55,17 @@ impl Component for BadgeGenerator {
             BadgeGeneratorMsg::UpdateName(name) => {
                 // Sanitize badge name (only letters, numbers, hyphens, underscores)
                 let sanitized = name.chars()
-                    .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+                    .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
                     .collect();
                 self.config.name = sanitized;
                 true
             }
+            BadgeGeneratorMsg::UpdateNamespace(namespace) => {
+                self.config.namespace = namespace.chars()
+                    .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+                    .collect();
+                true
+            }
             BadgeGeneratorMsg::UpdateLabel(label) => {
                 self.config.label = label;
                 true
@@ -149,6 +158,15 @@ impl Component for BadgeGenerator {
                 self.config.background_counter = background_counter;
                 true
             }
+            BadgeGeneratorMsg::UpdateSecret(secret) => {
+                self.config.secret = secret.trim().to_string();
+                true
+            }
+            BadgeGeneratorMsg::UpdatePeriod(period) => {
+                // Empty for every visit
+                self.config.period = period.parse().ok();
+                true
+            }
             BadgeGeneratorMsg::ToggleAdvancedPanel => {
                 self.advanced_panel_open = !self.advanced_panel_open;
                 true
@@ -200,6 +218,15 @@ impl Component for BadgeGenerator {
             })
         };
 
+        let on_namespace_input = {
+            let link = ctx.link().clone();
+            Callback::from(move |e: InputEvent| {
+                if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                    link.send_message(BadgeGeneratorMsg::UpdateNamespace(input.value()));
+                }
+            })
+        };
+
         let on_label_input = {
             let link = ctx.link().clone();
             Callback::from(move |e: InputEvent| {
@@ -209,6 +236,15 @@ impl Component for BadgeGenerator {
             })
         };
 
+        let on_secret_input = {
+            let link = ctx.link().clone();
+            Callback::from(move |e: InputEvent| {
+                if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                    link.send_message(BadgeGeneratorMsg::UpdateSecret(input.value()));
+                }
+            })
+        };
+
         let on_style_input = {
             let link = ctx.link().clone();
             Callback::from(move |e: InputEvent| {
@@ -370,6 +406,16 @@ impl Component for BadgeGenerator {
             })
         };
 
+        let on_period_change = {
+            let link = ctx.link().clone();
+            Callback::from(move |e: yew::events::Event| {
+                if let Some(select) = e.target_dyn_into::<HtmlSelectElement>() {
+                    link.send_message(BadgeGeneratorMsg::UpdatePeriod(select.value()));
+                }
+            })
+        };
+        let shown_period = self.config.period.map(|period| period.to_string()).unwrap_or_default();
+
         html! {
             <div id="home-section" class="section active">
                 <div class="card">
@@ -388,6 +434,17 @@ impl Component for BadgeGenerator {
                             <small>{ "Use only letters, numbers, hyphens, and underscores" }</small>
                         </div>
 
+                        <div class="form-group">
+                            <label for="badge-namespace">{ "Namespace" }</label>
+                            <input type="text"
+                                   id="badge-namespace"
+                                   placeholder="default"
+                                   class="form-input"
+                                   value={self.config.namespace.clone()}
+                                   oninput={on_namespace_input} />
+                            <small>{ "Leave empty unless your counters live in a namespace" }</small>
+                        </div>
+
                         <div class="form-group">
                             <label for="badge-label">{ "Label Text" }</label>
                             <input type="text"
@@ -398,6 +455,28 @@ impl Component for BadgeGenerator {
                                    oninput={on_label_input} />
                         </div>
 
+                        <div class="form-group">
+                            <label for="badge-period">{ "Shown Count" }</label>
+                            <select id="badge-period" class="form-input" onchange={on_period_change}>
+                                { for [("", "Every visit"), ("current", "This period"), ("previous", "Previous period")].iter().map(|(value, label)| html! {
+                                    <option value={*value} selected={shown_period == *value}>{ *label }</option>
+                                }) }
+                            </select>
+                            <small>{ "For counters that start over every day, week, month or year" }</small>
+                        </div>
+
+                        <div class="form-group">
+                            <label for="badge-secret">{ "Signing Secret" }</label>
+                            <input type="password"
+                                   id="badge-secret"
+                                   placeholder="Optional"
+                                   class="form-input"
+                                   autocomplete="off"
+                                   value={self.config.secret.clone()}
+                                   oninput={on_secret_input} />
+                            <small>{ "For badges that only count signed URLs. Find it in the admin panel." }</small>
+                        </div>
+
                         <div class="form-group">
                             <label for="badge-style">{ "Custom Style" }</label>
                             <textarea id="badge-style"
@@ -4,9 +4,11 @@ pub mod admin_panel;
 pub mod navigation;
 pub mod modals;
 pub mod alerts;
+pub mod audit_log;
 
 pub use badge_generator::BadgeGenerator;
 pub use admin_panel::AdminPanel;
 pub use navigation::Navigation;
-pub use modals::{LoginModal, CreateBadgeModal};
-pub use alerts::Alert;
\ No newline at end of file
+pub use modals::{LoginModal, CreateBadgeModal, ConfirmModal, BadgeDetailModal};
+pub use alerts::{Alert, UndoToast};
+pub use audit_log::AuditLog;
\ No newline at end of file
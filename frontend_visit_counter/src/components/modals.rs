@@ -1,15 +1,18 @@
 // frontend_visit_counter/src/components/modals.rs
 use yew::prelude::*;
-use web_sys::HtmlInputElement;
+use web_sys::{HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
+
+use crate::types::{BadgeMetadata, BadgeResponse, Period, PeriodRequest};
 
 #[derive(Properties, PartialEq)]
 pub struct LoginModalProps {
     pub on_close: Callback<()>,
-    pub on_submit: Callback<String>,
+    pub on_submit: Callback<(String, String)>, // (username, password)
 }
 
 #[function_component(LoginModal)]
 pub fn login_modal(props: &LoginModalProps) -> Html {
+    let username_ref = use_node_ref();
     let password_ref = use_node_ref();
 
     let on_backdrop_click = {
@@ -30,13 +33,17 @@ pub fn login_modal(props: &LoginModalProps) -> Html {
 
     let on_submit = {
         let on_submit = props.on_submit.clone();
+        let username_ref = username_ref.clone();
         let password_ref = password_ref.clone();
         Callback::from(move |e: SubmitEvent| {
             e.prevent_default();
+            let username = username_ref.cast::<HtmlInputElement>()
+                .map(|input| input.value().trim().to_string())
+                .unwrap_or_default();
             if let Some(input) = password_ref.cast::<HtmlInputElement>() {
                 let password = input.value();
                 if !password.is_empty() {
-                    on_submit.emit(password);
+                    on_submit.emit((username, password));
                     input.set_value("");
                 }
             }
@@ -51,6 +58,15 @@ pub fn login_modal(props: &LoginModalProps) -> Html {
                     <button class="close-btn" onclick={on_close_click}>{ "×" }</button>
                 </div>
                 <form class="modal-body" onsubmit={on_submit}>
+                    <div class="form-group">
+                        <label for="admin-username">{ "Username" }</label>
+                        <input type="text"
+                               id="admin-username"
+                               class="form-input"
+                               placeholder="admin"
+                               autocomplete="username"
+                               ref={username_ref} />
+                    </div>
                     <div class="form-group">
                         <label for="admin-password">{ "Password" }</label>
                         <input type="password"
@@ -71,7 +87,7 @@ pub fn login_modal(props: &LoginModalProps) -> Html {
 #[derive(Properties, PartialEq)]
 pub struct CreateBadgeModalProps {
     pub on_close: Callback<()>,
-    pub on_submit: Callback<(String, Option<u32>)>,
+    pub on_submit: Callback<(String, Option<u64>)>,
 }
 
 #[function_component(CreateBadgeModal)]
@@ -110,7 +126,7 @@ pub fn create_badge_modal(props: &CreateBadgeModalProps) -> Html {
                             if value.is_empty() {
                                 None
                             } else {
-                                value.parse::<u32>().ok()
+                                value.parse::<u64>().ok()
                             }
                         });
 
@@ -155,4 +171,246 @@ pub fn create_badge_modal(props: &CreateBadgeModalProps) -> Html {
             </div>
         </div>
     }
-}
\ No newline at end of file
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ConfirmModalProps {
+    pub title: String,
+    pub message: String,
+    pub confirm_label: String,
+    pub on_close: Callback<()>,
+    pub on_confirm: Callback<()>,
+}
+
+#[function_component(ConfirmModal)]
+pub fn confirm_modal(props: &ConfirmModalProps) -> Html {
+    let on_backdrop_click = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |e: MouseEvent| {
+            if let Some(target) = e.target_dyn_into::<web_sys::Element>() {
+                if target.class_name().contains("modal") {
+                    on_close.emit(());
+                }
+            }
+        })
+    };
+
+    let on_close_click = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |_| on_close.emit(()))
+    };
+
+    let on_confirm_click = {
+        let on_confirm = props.on_confirm.clone();
+        Callback::from(move |_| on_confirm.emit(()))
+    };
+
+    html! {
+        <div class="modal show" onclick={on_backdrop_click}>
+            <div class="modal-content">
+                <div class="modal-header">
+                    <h3><i class="fas fa-trash"></i> { format!(" {}", props.title) }</h3>
+                    <button class="close-btn" onclick={on_close_click.clone()}>{ "×" }</button>
+                </div>
+                <div class="modal-body">
+                    <p>{ &props.message }</p>
+                    <div class="modal-footer">
+                        <button class="btn btn-secondary" onclick={on_close_click}>{ "Cancel" }</button>
+                        <button class="btn btn-danger" onclick={on_confirm_click}>{ &props.confirm_label }</button>
+                    </div>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct BadgeDetailModalProps {
+    pub badge: BadgeResponse,
+    /// Whether the description, owner, URL and tags can be edited
+    pub can_edit: bool,
+    pub on_close: Callback<()>,
+    pub on_save: Callback<BadgeMetadata>,
+    pub on_save_period: Callback<PeriodRequest>,
+}
+
+/// Days of history shown in the badge details
+const DETAIL_HISTORY_DAYS: usize = 14;
+
+#[function_component(BadgeDetailModal)]
+pub fn badge_detail_modal(props: &BadgeDetailModalProps) -> Html {
+    let description_ref = use_node_ref();
+    let owner_ref = use_node_ref();
+    let url_ref = use_node_ref();
+    let tags_ref = use_node_ref();
+    let period_ref = use_node_ref();
+    let timezone_ref = use_node_ref();
+    let badge = &props.badge;
+
+    let on_backdrop_click = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |e: MouseEvent| {
+            if let Some(target) = e.target_dyn_into::<web_sys::Element>() {
+                if target.class_name().contains("modal") {
+                    on_close.emit(());
+                }
+            }
+        })
+    };
+
+    let on_close_click = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |_| on_close.emit(()))
+    };
+
+    let on_submit = {
+        let on_save = props.on_save.clone();
+        let description_ref = description_ref.clone();
+        let owner_ref = owner_ref.clone();
+        let url_ref = url_ref.clone();
+        let tags_ref = tags_ref.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let text = |value: String| Some(value.trim().to_string()).filter(|value| !value.is_empty());
+            let input = |node: &NodeRef| node.cast::<HtmlInputElement>().map(|input| input.value()).unwrap_or_default();
+            let description = description_ref.cast::<HtmlTextAreaElement>()
+                .map(|textarea| textarea.value())
+                .unwrap_or_default();
+            on_save.emit(BadgeMetadata {
+                description: text(description),
+                owner: text(input(&owner_ref)),
+                url: text(input(&url_ref)),
+                tags: input(&tags_ref).split(',').filter_map(|tag| text(tag.to_string())).collect(),
+            });
+        })
+    };
+
+    let on_period_submit = {
+        let on_save_period = props.on_save_period.clone();
+        let period_ref = period_ref.clone();
+        let timezone_ref = timezone_ref.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let period = period_ref.cast::<HtmlSelectElement>()
+                .and_then(|select| select.value().parse().ok())
+                .unwrap_or_default();
+            let timezone = timezone_ref.cast::<HtmlInputElement>()
+                .map(|input| input.value().trim().to_string())
+                .filter(|timezone| !timezone.is_empty());
+            on_save_period.emit(PeriodRequest { period, timezone });
+        })
+    };
+    let current_period = badge.period.as_ref().map(|period| period.period).unwrap_or_default();
+
+    let history: Vec<_> = badge.history.iter()
+        .flat_map(|history| history.iter().rev())
+        .take(DETAIL_HISTORY_DAYS)
+        .collect();
+
+    html! {
+        <div class="modal show" onclick={on_backdrop_click}>
+            <div class="modal-content">
+                <div class="modal-header">
+                    <h3><i class="fas fa-info-circle"></i> { format!(" {}", badge.name) }</h3>
+                    <button class="close-btn" onclick={on_close_click}>{ "×" }</button>
+                </div>
+                <div class="modal-body">
+                    <div class="badge-stats">
+                        <span><i class="fas fa-eye"></i> { format!(" {} visits", badge.count) }</span>
+                        <span><i class="fas fa-calendar"></i> { format!(" Created {}", badge.created_at) }</span>
+                        <span><i class="fas fa-clock"></i> { format!(" Last accessed {}", badge.last_accessed) }</span>
+                        if badge.signed {
+                            <span><i class="fas fa-lock"></i> { " Signed URLs only" }</span>
+                        }
+                    </div>
+                    if !badge.aliases.is_empty() {
+                        <div class="badge-aliases">
+                            <i class="fas fa-link"></i> { badge.aliases.join(", ") }
+                        </div>
+                    }
+
+                    if props.can_edit {
+                        <form class="badge-details" onsubmit={on_submit}>
+                            <div class="form-group">
+                                <label for="badge-description">{ "Description" }</label>
+                                <textarea id="badge-description" class="form-textarea" rows="3" maxlength="500"
+                                          ref={description_ref}
+                                          value={badge.description.clone().unwrap_or_default()} />
+                            </div>
+                            <div class="form-group">
+                                <label for="badge-owner">{ "Owner" }</label>
+                                <input type="text" id="badge-owner" class="form-input" maxlength="100"
+                                       ref={owner_ref}
+                                       value={badge.owner.clone().unwrap_or_default()} />
+                            </div>
+                            <div class="form-group">
+                                <label for="badge-url">{ "Embedded On" }</label>
+                                <input type="url" id="badge-url" class="form-input" placeholder="https://"
+                                       ref={url_ref}
+                                       value={badge.url.clone().unwrap_or_default()} />
+                            </div>
+                            <div class="form-group">
+                                <label for="badge-tags">{ "Tags (comma separated)" }</label>
+                                <input type="text" id="badge-tags" class="form-input"
+                                       ref={tags_ref}
+                                       value={badge.tags.join(", ")} />
+                            </div>
+                            <div class="modal-footer">
+                                <button type="submit" class="btn btn-primary">{ "Save" }</button>
+                            </div>
+                        </form>
+                    } else {
+                        <dl class="badge-details">
+                            <dt>{ "Description" }</dt>
+                            <dd>{ badge.description.clone().unwrap_or_else(|| "—".to_string()) }</dd>
+                            <dt>{ "Owner" }</dt>
+                            <dd>{ badge.owner.clone().unwrap_or_else(|| "—".to_string()) }</dd>
+                            <dt>{ "Embedded On" }</dt>
+                            <dd>
+                                if let Some(url) = &badge.url {
+                                    <a href={url.clone()} target="_blank" rel="noopener noreferrer">{ url }</a>
+                                } else {
+                                    { "—" }
+                                }
+                            </dd>
+                            <dt>{ "Tags" }</dt>
+                            <dd>{ if badge.tags.is_empty() { "—".to_string() } else { badge.tags.join(", ") } }</dd>
+                        </dl>
+                    }
+
+                    <h4>{ "Period" }</h4>
+                    if let Some(period) = &badge.period {
+                        <div class="badge-stats">
+                            <span>{ format!("{} visits since {}", period.current, period.start) }</span>
+                            <span>{ format!("{} in the previous period", period.previous) }</span>
+                        </div>
+                    }
+                    if props.can_edit {
+                        <form class="period-form" onsubmit={on_period_submit}>
+                            <select class="form-input" ref={period_ref}>
+                                { for [(Period::None, "Never starts over"), (Period::Daily, "Daily"), (Period::Weekly, "Weekly"), (Period::Monthly, "Monthly"), (Period::Yearly, "Yearly")].iter().map(|(period, label)| html! {
+                                    <option value={period.to_string()} selected={current_period == *period}>{ *label }</option>
+                                }) }
+                            </select>
+                            <input type="text" class="form-input" placeholder="UTC" title="IANA time zone, e.g. Europe/Berlin"
+                                   ref={timezone_ref}
+                                   value={badge.period.as_ref().map(|period| period.timezone.clone()).unwrap_or_default()} />
+                            <button type="submit" class="btn btn-secondary">{ "Set Period" }</button>
+                        </form>
+                    } else if badge.period.is_none() {
+                        <p>{ "Never starts over" }</p>
+                    }
+
+                    if !history.is_empty() {
+                        <h4>{ "Recent Visits" }</h4>
+                        <table class="badge-history">
+                            { for history.into_iter().map(|(day, visits)| html! {
+                                <tr><td>{ day.to_string() }</td><td>{ visits }</td></tr>
+                            }) }
+                        </table>
+                    }
+                </div>
+            </div>
+        </div>
+    }
+}
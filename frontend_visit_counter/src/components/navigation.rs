@@ -9,7 +9,7 @@ pub struct NavigationProps {
     pub is_authenticated: bool,
     pub on_route_change: Callback<AppRoute>,
     pub on_theme_toggle: Callback<()>,
-    pub on_login: Callback<String>,
+    pub on_login: Callback<(String, String)>,
     pub on_logout: Callback<()>,
 }
 
@@ -87,12 +87,20 @@ impl Component for Navigation {
         let on_login_submit = {
             let on_login = props.on_login.clone();
             let link = ctx.link().clone();
-            Callback::from(move |password: String| {
-                on_login.emit(password);
+            Callback::from(move |credentials: (String, String)| {
+                on_login.emit(credentials);
                 link.send_message(NavigationMsg::HideLoginModal);
             })
         };
 
+        let on_audit_click = {
+            let on_route_change = props.on_route_change.clone();
+            Callback::from(move |e: MouseEvent| {
+                e.prevent_default();
+                on_route_change.emit(AppRoute::Audit);
+            })
+        };
+
         let theme_icon = match props.theme {
             Theme::Dark => "fas fa-sun",
             Theme::Light => "fas fa-moon",
@@ -118,6 +126,13 @@ impl Component for Navigation {
                                onclick={on_admin_click}>
                                 <i class="fas fa-cog"></i> { " Admin Panel" }
                             </a>
+                            if props.is_authenticated {
+                                <a href="#"
+                                   class={classes!("nav-link", if props.current_route == AppRoute::Audit { Some("active") } else { None })}
+                                   onclick={on_audit_click}>
+                                    <i class="fas fa-clock"></i> { " Audit Log" }
+                                </a>
+                            }
                         </div>
                         <div class="auth-section">
                             <button class="btn btn-secondary theme-toggle"
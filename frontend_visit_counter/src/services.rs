@@ -1,4 +1,5 @@
 // frontend_visit_counter/src/services.rs
+use chrono::{DateTime, Utc};
 use gloo::storage::{LocalStorage, Storage};
 use gloo_net::http::Request;
 
@@ -6,22 +7,36 @@ use crate::types::*;
 
 const API_BASE: &str = "/api";
 const AUTH_BASE: &str = "/api/auth";
-const TOKEN_KEY: &str = "authToken";
+const SESSION_KEY: &str = "authSession";
 const THEME_KEY: &str = "theme";
+const NAMESPACE_KEY: &str = "namespace";
+pub const DEFAULT_NAMESPACE: &str = "default";
 
 pub struct ApiService;
 
 impl ApiService {
-    pub fn get_auth_token() -> Option<String> {
-        LocalStorage::get(TOKEN_KEY).ok()
+    /// Returns the stored session, unless it has expired.
+    pub fn get_auth_token() -> Option<TokenResponse> {
+        let session: TokenResponse = LocalStorage::get(SESSION_KEY).ok()?;
+
+        if Self::millis_until(&session.expires_at) <= 0.0 {
+            Self::remove_auth_token();
+            return None;
+        }
+        Some(session)
     }
 
-    pub fn set_auth_token(token: String) {
-        LocalStorage::set(TOKEN_KEY, token).ok();
+    pub fn set_auth_token(session: &TokenResponse) {
+        LocalStorage::set(SESSION_KEY, session).ok();
     }
 
     pub fn remove_auth_token() {
-        LocalStorage::delete(TOKEN_KEY);
+        LocalStorage::delete(SESSION_KEY);
+    }
+
+    /// Milliseconds from now until `timestamp` (negative if it is in the past).
+    pub fn millis_until(timestamp: &DateTime<Utc>) -> f64 {
+        timestamp.timestamp_millis() as f64 - js_sys::Date::now()
     }
 
     pub fn get_theme() -> Theme {
@@ -35,8 +50,27 @@ impl ApiService {
         LocalStorage::set(THEME_KEY, theme.as_str()).ok();
     }
 
-    pub async fn login(password: String) -> Result<String, String> {
-        let request = LoginRequest { password };
+    /// Namespace the admin views act on: the user's own, or the one picked by a user of every namespace.
+    pub fn get_namespace() -> String {
+        Self::get_auth_token()
+            .and_then(|session| session.namespace)
+            .or_else(|| LocalStorage::get(NAMESPACE_KEY).ok())
+            .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string())
+    }
+
+    pub fn set_namespace(namespace: &str) {
+        LocalStorage::set(NAMESPACE_KEY, namespace).ok();
+    }
+
+    /// URL of an admin endpoint, scoped to the current namespace.
+    fn admin_url(path: &str) -> String {
+        let separator = if path.contains('?') { '&' } else { '?' };
+        format!("{}/admin{}{}ns={}", API_BASE, path, separator, urlencoding::encode(&Self::get_namespace()))
+    }
+
+    pub async fn login(username: String, password: String) -> Result<TokenResponse, String> {
+        let username = Some(username).filter(|u| !u.is_empty());
+        let request = LoginRequest { username, password };
 
         let response = Request::post(&format!("{}/login", AUTH_BASE))
             .json(&request)
@@ -46,18 +80,84 @@ impl ApiService {
             .map_err(|e| format!("Network error: {}", e))?;
 
         if response.ok() {
-            let token_response: TokenResponse = response
+            response
                 .json()
                 .await
-                .map_err(|e| format!("Failed to parse response: {}", e))?;
-            Ok(token_response.token)
+                .map_err(|e| format!("Failed to parse response: {}", e))
+        } else if response.status() == 429 {
+            let retry_after = response.headers().get("Retry-After").unwrap_or_else(|| "a few".to_string());
+            Err(format!("Too many failed attempts, try again in {} seconds", retry_after))
         } else {
             Err("Invalid credentials".to_string())
         }
     }
 
-    pub async fn fetch_badges(token: &str) -> Result<BadgeListResponse, String> {
-        let response = Request::get(&format!("{}/admin/badges", API_BASE))
+    pub async fn refresh_token(token: &str) -> Result<TokenResponse, String> {
+        let response = Request::post(&format!("{}/refresh", AUTH_BASE))
+            .header("Authorization", &format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if response.ok() {
+            response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))
+        } else {
+            Err("Session expired".to_string())
+        }
+    }
+
+    pub async fn logout(token: &str) -> Result<(), String> {
+        let response = Request::post(&format!("{}/logout", AUTH_BASE))
+            .header("Authorization", &format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if response.ok() {
+            Ok(())
+        } else {
+            Err("Failed to log out".to_string())
+        }
+    }
+
+    pub async fn fetch_namespaces(token: &str) -> Result<NamespaceListResponse, String> {
+        let response = Request::get(&format!("{}/admin/namespaces", API_BASE))
+            .header("Authorization", &format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if response.ok() {
+            response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))
+        } else {
+            Err("Failed to fetch namespaces".to_string())
+        }
+    }
+
+    pub async fn fetch_badges(token: &str, filter: &BadgeFilter, page: usize, per_page: usize) -> Result<BadgeListResponse, String> {
+        let mut params = vec![format!("page={}", page), format!("per_page={}", per_page)];
+        let fields = [
+            ("q", &filter.query),
+            ("sort", &filter.sort),
+            ("order", &filter.order),
+            ("min_count", &filter.min_count),
+            ("inactive_since", &filter.inactive_since),
+            ("tag", &filter.tag),
+            ("owner", &filter.owner),
+        ];
+        for (key, value) in fields {
+            if !value.is_empty() {
+                params.push(format!("{}={}", key, urlencoding::encode(value)));
+            }
+        }
+
+        let response = Request::get(&Self::admin_url(&format!("/badges?{}", params.join("&"))))
             .header("Authorization", &format!("Bearer {}", token))
             .send()
             .await
@@ -73,10 +173,76 @@ impl ApiService {
         }
     }
 
-    pub async fn create_badge(token: &str, name: String, count: Option<u32>) -> Result<BadgeResponse, String> {
+    /// A badge with its visits per day
+    pub async fn fetch_badge(token: &str, name: &str) -> Result<BadgeResponse, String> {
+        let response = Request::get(&Self::admin_url(&format!("/badges/{}", urlencoding::encode(name))))
+            .header("Authorization", &format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if response.ok() {
+            response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))
+        } else {
+            Err("Failed to fetch badge".to_string())
+        }
+    }
+
+    pub async fn update_badge_metadata(token: &str, name: &str, metadata: &BadgeMetadata) -> Result<BadgeResponse, String> {
+        let response = Request::put(&Self::admin_url(&format!("/badges/{}/metadata", urlencoding::encode(name))))
+            .header("Authorization", &format!("Bearer {}", token))
+            .json(metadata)
+            .map_err(|e| format!("Network error: {}", e))?
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if response.ok() {
+            response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))
+        } else if response.status() == 400 {
+            match response.json::<ApiErrorResponse>().await {
+                Ok(error) => Err(error.message),
+                Err(_) => Err("Invalid badge details".to_string()),
+            }
+        } else {
+            Err("Failed to update badge details".to_string())
+        }
+    }
+
+    pub async fn set_badge_period(token: &str, name: &str, request: &PeriodRequest) -> Result<BadgeResponse, String> {
+        let response = Request::put(&Self::admin_url(&format!("/badges/{}/period", urlencoding::encode(name))))
+            .header("Authorization", &format!("Bearer {}", token))
+            .json(request)
+            .map_err(|e| format!("Network error: {}", e))?
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if response.ok() {
+            response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))
+        } else if response.status() == 400 {
+            match response.json::<ApiErrorResponse>().await {
+                Ok(error) => Err(error.message),
+                Err(_) => Err("Invalid period".to_string()),
+            }
+        } else {
+            Err("Failed to update the period".to_string())
+        }
+    }
+
+    pub async fn create_badge(token: &str, name: String, count: Option<u64>) -> Result<BadgeResponse, String> {
         let request = BadgeCreateRequest { name, count };
 
-        let response = Request::post(&format!("{}/admin/badges", API_BASE))
+        let response = Request::post(&Self::admin_url("/badges"))
             .header("Authorization", &format!("Bearer {}", token))
             .json(&request)
             .map_err(|e| format!("Network error: {}", e))?
@@ -96,10 +262,10 @@ impl ApiService {
         }
     }
 
-    pub async fn update_badge(token: &str, name: String, count: u32) -> Result<BadgeResponse, String> {
+    pub async fn update_badge(token: &str, name: String, count: u64) -> Result<BadgeResponse, String> {
         let request = CounterSetRequest { count };
 
-        let response = Request::put(&format!("{}/admin/badges/{}", API_BASE, name))
+        let response = Request::put(&Self::admin_url(&format!("/badges/{}", name)))
             .header("Authorization", &format!("Bearer {}", token))
             .json(&request)
             .map_err(|e| format!("Network error: {}", e))?
@@ -118,7 +284,7 @@ impl ApiService {
     }
 
     pub async fn delete_badge(token: &str, name: String) -> Result<(), String> {
-        let response = Request::delete(&format!("{}/admin/badges/{}", API_BASE, name))
+        let response = Request::delete(&Self::admin_url(&format!("/badges/{}", name)))
             .header("Authorization", &format!("Bearer {}", token))
             .send()
             .await
@@ -131,83 +297,300 @@ impl ApiService {
         }
     }
 
-    pub fn build_badge_url(config: &BadgeConfig) -> String {
-        let mut url = format!("/counter/{}/svg", urlencoding::encode(&config.name));
-        let mut params = vec![];
+    pub async fn rename_badge(token: &str, name: String, new_name: String) -> Result<BadgeResponse, String> {
+        let request = BadgeRenameRequest { new_name };
 
-        if !config.label.is_empty() && config.label != "Visits" {
-            params.push(format!("label={}", urlencoding::encode(&config.label)));
-        }
+        let response = Request::post(&Self::admin_url(&format!("/badges/{}/rename", urlencoding::encode(&name))))
+            .header("Authorization", &format!("Bearer {}", token))
+            .json(&request)
+            .map_err(|e| format!("Network error: {}", e))?
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
 
-        if config.width != 150 {
-            params.push(format!("width={}", config.width));
+        if response.ok() {
+            response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))
+        } else if response.status() == 409 {
+            Err("A badge or alias with that name already exists".to_string())
+        } else {
+            Err("Failed to rename badge".to_string())
         }
+    }
 
-        if config.height != 20 {
-            params.push(format!("height={}", config.height));
-        }
+    pub async fn merge_badges(token: &str, sources: Vec<String>, target: String) -> Result<BadgeResponse, String> {
+        let request = BadgeMergeRequest { sources, target };
 
-        if !config.style.is_empty() {
-            params.push(format!("style={}", urlencoding::encode(&config.style)));
-        }
+        let response = Request::post(&Self::admin_url("/badges/merge"))
+            .header("Authorization", &format!("Bearer {}", token))
+            .json(&request)
+            .map_err(|e| format!("Network error: {}", e))?
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
 
-        // Advanced customization parameters
-        if config.font_family != "Comfortaa, Metrophobic, sans-serif" {
-            params.push(format!("font_family={}", urlencoding::encode(&config.font_family)));
+        if response.ok() {
+            response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))
+        } else if response.status() == 409 {
+            Err("The target name is an alias of another badge".to_string())
+        } else {
+            Err("Failed to merge badges".to_string())
         }
+    }
+
+    /// Applies one operation to many badges. Nothing changes if any of them fails.
+    pub async fn bulk_badges(token: &str, request: &BulkRequest) -> Result<BulkResponse, String> {
+        let response = Request::post(&Self::admin_url("/badges/bulk"))
+            .header("Authorization", &format!("Bearer {}", token))
+            .json(request)
+            .map_err(|e| format!("Network error: {}", e))?
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
 
-        if config.font_size != 11 {
-            params.push(format!("font_size={}", config.font_size));
+        if response.ok() {
+            response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))
+        } else if response.status() == 409 {
+            let failures = match response.json::<BulkResponse>().await {
+                Ok(bulk) => bulk.results.into_iter()
+                    .filter(|result| !result.ok)
+                    .map(|result| format!("{}: {}", result.name, result.error.unwrap_or_default()))
+                    .collect::<Vec<_>>()
+                    .join("; "),
+                Err(_) => String::new(),
+            };
+            Err(format!("Nothing was changed. {}", failures))
+        } else if response.status() == 400 {
+            match response.json::<ApiErrorResponse>().await {
+                Ok(error) => Err(error.message),
+                Err(_) => Err("Invalid bulk operation".to_string()),
+            }
+        } else if response.status() == 403 {
+            Err("You are not allowed to do this".to_string())
+        } else {
+            Err("Failed to update the badges".to_string())
         }
+    }
 
-        if config.font_weight != "normal" {
-            params.push(format!("font_weight={}", urlencoding::encode(&config.font_weight)));
+    pub async fn add_alias(token: &str, alias: String, target: String) -> Result<AliasResponse, String> {
+        let request = AliasCreateRequest { alias, target };
+
+        let response = Request::post(&Self::admin_url("/aliases"))
+            .header("Authorization", &format!("Bearer {}", token))
+            .json(&request)
+            .map_err(|e| format!("Network error: {}", e))?
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if response.ok() {
+            response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))
+        } else if response.status() == 409 {
+            Err("A badge or alias with that name already exists".to_string())
+        } else {
+            Err("Failed to add alias".to_string())
         }
+    }
 
-        if config.label_color != "#ffffff" {
-            params.push(format!("label_color={}", urlencoding::encode(&config.label_color)));
+    pub async fn remove_alias(token: &str, alias: String) -> Result<(), String> {
+        let response = Request::delete(&Self::admin_url(&format!("/aliases/{}", urlencoding::encode(&alias))))
+            .header("Authorization", &format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if response.ok() {
+            Ok(())
+        } else {
+            Err("Failed to remove alias".to_string())
         }
+    }
+
+    pub async fn rotate_signing_secret(token: &str, name: String) -> Result<SigningSecretResponse, String> {
+        let response = Request::post(&Self::admin_url(&format!("/badges/{}/secret", urlencoding::encode(&name))))
+            .header("Authorization", &format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
 
-        if config.counter_color != "#ffffff" {
-            params.push(format!("counter_color={}", urlencoding::encode(&config.counter_color)));
+        if response.ok() {
+            response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))
+        } else {
+            Err("Failed to rotate signing secret".to_string())
         }
+    }
+
+    pub async fn disable_signing(token: &str, name: String) -> Result<(), String> {
+        let response = Request::delete(&Self::admin_url(&format!("/badges/{}/secret", urlencoding::encode(&name))))
+            .header("Authorization", &format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
 
-        if config.background_label != "#18181b" {
-            params.push(format!("background_label={}", urlencoding::encode(&config.background_label)));
+        if response.ok() {
+            Ok(())
+        } else {
+            Err("Failed to disable signing".to_string())
         }
+    }
+
+    pub async fn fetch_trash(token: &str) -> Result<TrashListResponse, String> {
+        let response = Request::get(&Self::admin_url("/trash"))
+            .header("Authorization", &format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
 
-        if config.background_counter != "#DC26B6" {
-            params.push(format!("background_counter={}", urlencoding::encode(&config.background_counter)));
+        if response.ok() {
+            response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))
+        } else {
+            Err("Failed to fetch trash".to_string())
         }
+    }
 
-        if config.border_width != 0 {
-            params.push(format!("border_width={}", config.border_width));
+    pub async fn restore_badge(token: &str, name: String) -> Result<BadgeResponse, String> {
+        let response = Request::post(&Self::admin_url(&format!("/trash/{}/restore", urlencoding::encode(&name))))
+            .header("Authorization", &format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if response.ok() {
+            response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))
+        } else {
+            Err("Failed to restore badge".to_string())
         }
+    }
+
+    pub async fn purge_badge(token: &str, name: String) -> Result<(), String> {
+        let response = Request::delete(&Self::admin_url(&format!("/trash/{}", urlencoding::encode(&name))))
+            .header("Authorization", &format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
 
-        if config.border_color != "#cccccc" {
-            params.push(format!("border_color={}", urlencoding::encode(&config.border_color)));
+        if response.ok() {
+            Ok(())
+        } else {
+            Err("Failed to delete badge permanently".to_string())
         }
+    }
+
+    /// Exports the counters of the namespace as `json` or `csv` text.
+    pub async fn export_counters(token: &str, format: &str) -> Result<String, String> {
+        let response = Request::get(&Self::admin_url(&format!("/export?format={}", format)))
+            .header("Authorization", &format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
 
-        if config.border_radius != 3 {
-            params.push(format!("border_radius={}", config.border_radius));
+        if response.ok() {
+            response
+                .text()
+                .await
+                .map_err(|e| format!("Failed to read export: {}", e))
+        } else {
+            Err("Failed to export counters".to_string())
         }
+    }
+
+    /// Imports an export, the legacy counter files, or the data of another visit counter (`format`).
+    /// With `dry_run`, only reports what would change.
+    pub async fn import_counters(token: &str, body: String, format: &str, strategy: &str, dry_run: bool) -> Result<ImportReport, String> {
+        let url = Self::admin_url(&format!("/import?format={}&strategy={}&dry_run={}", format, strategy, dry_run));
+        let response = Request::post(&url)
+            .header("Authorization", &format!("Bearer {}", token))
+            .body(body)
+            .map_err(|e| format!("Network error: {}", e))?
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
 
+        if response.ok() {
+            response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))
+        } else if response.status() == 400 {
+            match response.json::<ApiErrorResponse>().await {
+                Ok(error) => Err(format!("Invalid import: {}", error.message)),
+                Err(_) => Err("Invalid import file".to_string()),
+            }
+        } else if response.status() == 413 {
+            Err("The import file is too large".to_string())
+        } else {
+            Err("Failed to import counters".to_string())
+        }
+    }
 
-        if !config.logo_url.is_empty() {
-            params.push(format!("logo_url={}", urlencoding::encode(&config.logo_url)));
+    pub async fn fetch_audit(token: &str, filter: &AuditFilter, page: usize, per_page: usize) -> Result<AuditListResponse, String> {
+        let mut params = vec![format!("page={}", page), format!("per_page={}", per_page)];
+        for (key, value) in [("counter", &filter.counter), ("actor", &filter.actor), ("action", &filter.action)] {
+            if !value.is_empty() {
+                params.push(format!("{}={}", key, urlencoding::encode(value)));
+            }
         }
 
-        if config.logo_width != 30 {
-            params.push(format!("logo_width={}", config.logo_width));
+        let response = Request::get(&Self::admin_url(&format!("/audit?{}", params.join("&"))))
+            .header("Authorization", &format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if response.ok() {
+            response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))
+        } else {
+            Err("Failed to fetch audit log".to_string())
         }
+    }
 
-        if config.element_positions != "label,logo,counter" {
-            params.push(format!("element_positions={}", urlencoding::encode(&config.element_positions)));
+    pub fn build_badge_url(config: &BadgeConfig) -> String {
+        let namespaced = !config.namespace.is_empty() && config.namespace != DEFAULT_NAMESPACE;
+        let mut url = if namespaced {
+            format!("/counter/{}/{}/svg", urlencoding::encode(&config.namespace), urlencoding::encode(&config.name))
+        } else {
+            format!("/counter/{}/svg", urlencoding::encode(&config.name))
+        };
+        // Only the options differing from the defaults, named like the backend's query parameters
+        let mut params = config.svg_options().query_pairs();
+
+        if !config.secret.is_empty() {
+            // Counters of other namespaces are signed under their storage key, `<namespace>/<name>`;
+            // the message lowercases it, so names typed in another case match the server's.
+            let key = if namespaced { format!("{}/{}", config.namespace, config.name) } else { config.name.clone() };
+            let message = signing::canonical_message(&key, params.iter().map(|(name, value)| (*name, value.as_str())));
+            params.push((signing::SIGNATURE_PARAM, signing::sign(&config.secret, &message)));
         }
 
         if !params.is_empty() {
+            let query: Vec<String> = params.iter()
+                .map(|(key, value)| format!("{}={}", key, urlencoding::encode(value)))
+                .collect();
             url.push('?');
-            url.push_str(&params.join("&"));
+            url.push_str(&query.join("&"));
         }
 
         url
@@ -216,7 +599,7 @@ impl ApiService {
     pub fn build_full_badge_url(config: &BadgeConfig) -> String {
         let base_url = web_sys::window()
             .and_then(|w| w.location().origin().ok())
-            .unwrap_or_else(|| "".to_string());
+            .unwrap_or_default();
         format!("{}{}", base_url, Self::build_badge_url(config))
     }
 }
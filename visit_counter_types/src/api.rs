@@ -0,0 +1,824 @@
+// visit_counter_types/src/api.rs
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// JSON response structure for counter endpoints.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CounterResponse {
+    pub name: String,
+    pub count: u64,
+}
+
+/// JSON request structure for setting a counter.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CounterSetRequest {
+    pub count: u64,
+}
+
+/// JSON error returned by the API: `{"error": "<code>", "message": "<details>"}`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ApiErrorResponse {
+    pub error: String,
+    pub message: String,
+}
+
+/// How often a periodic counter starts over
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    /// The counter never starts over
+    #[default]
+    None,
+    Daily,
+    /// Weeks start on Monday
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Period {
+    pub const ALL: [Period; 5] = [Period::None, Period::Daily, Period::Weekly, Period::Monthly, Period::Yearly];
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Period::None => "none",
+            Period::Daily => "daily",
+            Period::Weekly => "weekly",
+            Period::Monthly => "monthly",
+            Period::Yearly => "yearly",
+        })
+    }
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Period::ALL.into_iter()
+            .find(|period| period.to_string() == value)
+            .ok_or_else(|| format!("unknown period `{}`", value))
+    }
+}
+
+/// Request to change how often a counter starts over
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PeriodRequest {
+    pub period: Period,
+    /// IANA time zone, UTC unless given
+    pub timezone: Option<String>,
+}
+
+/// Period of a badge, rolled over to the time of the response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PeriodResponse {
+    pub period: Period,
+    pub timezone: String,
+    /// First day of the active window
+    pub start: NaiveDate,
+    pub current: u64,
+    pub previous: u64,
+    /// Totals of earlier windows, by their first day
+    #[serde(default)]
+    pub archive: BTreeMap<NaiveDate, u64>,
+}
+
+/// Longest tag a badge accepts.
+pub const MAX_TAG_LENGTH: usize = 32;
+
+/// Whether `tag` can label badges: 1 to 32 ASCII letters, digits, `-` and `_`.
+pub fn is_valid_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag.len() <= MAX_TAG_LENGTH
+        && tag.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+}
+
+/// What a badge is for and who looks after it, edited by admins
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BadgeMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Person or team responsible for the badge
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Page the badge is embedded on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Labels grouping badges, e.g. to filter the list or select badges for a bulk operation
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+}
+
+impl BadgeMetadata {
+    pub const MAX_DESCRIPTION_LENGTH: usize = 500;
+    pub const MAX_OWNER_LENGTH: usize = 100;
+    pub const MAX_URL_LENGTH: usize = 2048;
+
+    /// Trims every field and drops the empty ones.
+    pub fn normalized(self) -> Self {
+        let text = |value: Option<String>| value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+        BadgeMetadata {
+            description: text(self.description),
+            owner: text(self.owner),
+            url: text(self.url),
+            tags: self.tags.into_iter().map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()).collect(),
+        }
+    }
+
+    /// Why this metadata cannot be stored: a field that is too long, a URL that is not http(s), or an invalid tag.
+    pub fn validate(&self) -> Result<(), String> {
+        let too_long = |field: &str, value: &Option<String>, max: usize| {
+            value.as_ref().filter(|value| value.chars().count() > max).map(|_| format!("{} must be at most {} characters", field, max))
+        };
+        if let Some(error) = too_long("description", &self.description, Self::MAX_DESCRIPTION_LENGTH)
+            .or_else(|| too_long("owner", &self.owner, Self::MAX_OWNER_LENGTH))
+            .or_else(|| too_long("url", &self.url, Self::MAX_URL_LENGTH))
+        {
+            return Err(error);
+        }
+        if self.url.as_ref().is_some_and(|url| !url.starts_with("http://") && !url.starts_with("https://")) {
+            return Err("url must start with http:// or https://".to_string());
+        }
+        match self.tags.iter().find(|tag| !is_valid_tag(tag)) {
+            Some(tag) => Err(format!(
+                "tag `{}` must be 1 to {} ASCII letters, digits, '-' or '_'", tag, MAX_TAG_LENGTH
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Fields given in `other` replace these, and its tags are added to these.
+    pub fn update(&mut self, other: BadgeMetadata) {
+        self.description = other.description.or(self.description.take());
+        self.owner = other.owner.or(self.owner.take());
+        self.url = other.url.or(self.url.take());
+        self.tags.extend(other.tags);
+    }
+}
+
+/// Request to create/update a badge
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BadgeCreateRequest {
+    pub name: String,
+    pub count: Option<u64>,
+}
+
+/// Response for badge operations
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BadgeResponse {
+    pub name: String,
+    pub count: u64,
+    pub created_at: DateTime<Utc>,
+    pub last_accessed: DateTime<Utc>,
+    /// Other names that count into this badge
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Whether only signed badge URLs count visits
+    #[serde(default)]
+    pub signed: bool,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Set for counters that start over every day, week, month or year
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub period: Option<PeriodResponse>,
+    /// Visits per day, only returned for a single badge
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<BTreeMap<NaiveDate, u64>>,
+}
+
+/// Request to rename a badge
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BadgeRenameRequest {
+    pub new_name: String,
+}
+
+/// Request to merge badges into one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BadgeMergeRequest {
+    pub sources: Vec<String>,
+    /// Existing badge or new name receiving the merged counts
+    pub target: String,
+}
+
+/// Request to apply one operation to many badges: the listed `names`, or every badge matching `filter`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BulkRequest {
+    #[serde(default)]
+    pub names: Vec<String>,
+    pub filter: Option<BulkFilter>,
+    #[serde(flatten)]
+    pub operation: BulkOperation,
+}
+
+/// Badges selected by a bulk request, like the query parameters of the badge list
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct BulkFilter {
+    pub q: Option<String>,
+    pub min_count: Option<u64>,
+    /// RFC 3339 timestamp or date
+    pub inactive_since: Option<String>,
+    pub tag: Option<String>,
+    pub owner: Option<String>,
+}
+
+/// Operation of a bulk request, named by its `operation` field
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub enum BulkOperation {
+    /// Move the badges to the trash
+    Delete,
+    /// Set the counts to zero
+    Reset,
+    /// Add `offset` to the counts; negative offsets stop at zero
+    AddOffset { offset: i64 },
+    Tag { tags: Vec<String> },
+    Untag { tags: Vec<String> },
+    /// Move the badges and their aliases to another namespace
+    Move { namespace: String },
+}
+
+/// Result of a bulk operation for one badge
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BulkItemResult {
+    pub name: String,
+    pub ok: bool,
+    pub old_count: Option<u64>,
+    pub new_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Results of a bulk operation. Nothing is changed unless every badge succeeds (`applied`).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BulkResponse {
+    pub applied: bool,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult>,
+}
+
+/// Signing secret of a badge
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SigningSecretResponse {
+    pub name: String,
+    pub secret: String,
+    /// Until when URLs signed with the replaced secret keep counting
+    pub previous_expires_at: Option<DateTime<Utc>>,
+}
+
+/// Request to add an alias for a counter
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AliasCreateRequest {
+    pub alias: String,
+    pub target: String,
+}
+
+/// An alias and the counter it counts into
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AliasResponse {
+    pub alias: String,
+    pub target: String,
+}
+
+/// List all aliases response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AliasListResponse {
+    pub aliases: Vec<AliasResponse>,
+    pub total: usize,
+}
+
+/// Field the admin badge list is sorted by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "rocket", derive(rocket::FromFormField))]
+pub enum BadgeSort {
+    #[default]
+    Name,
+    Count,
+    #[cfg_attr(feature = "rocket", field(value = "created_at"))]
+    CreatedAt,
+    #[cfg_attr(feature = "rocket", field(value = "last_accessed"))]
+    LastAccessed,
+}
+
+/// Direction of a sort
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "rocket", derive(rocket::FromFormField))]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Query parameters of the admin badge list
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "rocket", derive(rocket::FromForm))]
+pub struct BadgeQuery {
+    /// Case-insensitive part of the name or description
+    pub q: Option<String>,
+    pub sort: Option<BadgeSort>,
+    /// Ascending for names, descending otherwise, unless given
+    pub order: Option<SortOrder>,
+    pub min_count: Option<u64>,
+    /// RFC 3339 timestamp or date; only badges not accessed since then are listed
+    pub inactive_since: Option<String>,
+    /// Only badges with this tag
+    pub tag: Option<String>,
+    /// Only badges of this owner, ignoring case
+    pub owner: Option<String>,
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+}
+
+/// One page of the badges matching a query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BadgeListResponse {
+    pub badges: Vec<BadgeResponse>,
+    /// Number of matching badges, over all pages
+    pub total: usize,
+    /// Sum of the counts of the matching badges
+    pub visits: u64,
+    pub page: usize,
+    pub per_page: usize,
+}
+
+/// Format of an export
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "rocket", derive(rocket::FromFormField))]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// Layout of an import: an export of this server, or the data of another visit counter
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[cfg_attr(feature = "rocket", derive(rocket::FromFormField))]
+pub enum ImportFormat {
+    /// A JSON or CSV export, or the legacy counter files, detected from the contents
+    #[default]
+    Auto,
+    /// `name,count` lines, with or without a header
+    Csv,
+    /// CountAPI dump: `[{"namespace": ..., "key": ..., "value": ...}]`
+    #[cfg_attr(feature = "rocket", field(value = "countapi"))]
+    #[serde(rename = "countapi")]
+    CountApi,
+    /// Moe-Counter records: `[{"name": ..., "num": ...}]`, or one record per line as written by mongoexport
+    #[cfg_attr(feature = "rocket", field(value = "moe-counter"))]
+    MoeCounter,
+    /// Old names mapped to new counters: `[{"from": ..., "name": ..., "count": ...}]`
+    Mapping,
+}
+
+/// The `format` query parameter of an import.
+impl fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ImportFormat::Auto => "auto",
+            ImportFormat::Csv => "csv",
+            ImportFormat::CountApi => "countapi",
+            ImportFormat::MoeCounter => "moe-counter",
+            ImportFormat::Mapping => "mapping",
+        })
+    }
+}
+
+/// How an import treats counters that already exist
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[cfg_attr(feature = "rocket", derive(rocket::FromFormField))]
+pub enum ImportStrategy {
+    /// Replace the count and history with the imported ones
+    Overwrite,
+    /// Keep the higher count, and the higher number of visits of each day
+    #[default]
+    #[cfg_attr(feature = "rocket", field(value = "keep-max"))]
+    KeepMax,
+    /// Add the imported count and history to the existing ones
+    Sum,
+    /// Leave existing counters untouched
+    #[cfg_attr(feature = "rocket", field(value = "skip-existing"))]
+    SkipExisting,
+}
+
+/// The `strategy` query parameter of an import.
+impl fmt::Display for ImportStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ImportStrategy::Overwrite => "overwrite",
+            ImportStrategy::KeepMax => "keep-max",
+            ImportStrategy::Sum => "sum",
+            ImportStrategy::SkipExisting => "skip-existing",
+        })
+    }
+}
+
+/// What an import did, or would do, to one counter
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportOutcome {
+    Created,
+    Updated,
+    Unchanged,
+    Skipped,
+    Failed,
+}
+
+/// Result of importing one counter
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ImportChange {
+    pub name: String,
+    pub outcome: ImportOutcome,
+    pub old_count: Option<u64>,
+    pub new_count: Option<u64>,
+    /// Old names added, or to be added, as aliases of the counter
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Report of an import. Nothing is changed when `dry_run` is set.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub format: ImportFormat,
+    pub strategy: ImportStrategy,
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub changes: Vec<ImportChange>,
+}
+
+/// Response for badges in the trash
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TrashedBadgeResponse {
+    pub name: String,
+    pub count: u64,
+    pub created_at: DateTime<Utc>,
+    pub deleted_at: DateTime<Utc>,
+    /// When the badge is purged automatically, if a retention period is set
+    pub purge_at: Option<DateTime<Utc>>,
+}
+
+/// List the trash response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TrashListResponse {
+    pub badges: Vec<TrashedBadgeResponse>,
+    pub total: usize,
+}
+
+/// Response of the liveness endpoint
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HealthResponse {
+    pub status: String,
+}
+
+/// Result of a single readiness check
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HealthCheck {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Response of the readiness endpoint
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReadinessResponse {
+    pub status: String,
+    pub storage: HealthCheck,
+    pub last_flush: HealthCheck,
+    pub last_flush_at: Option<DateTime<Utc>>,
+}
+
+/// Permission granted to an API key
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiKeyScope {
+    /// Read counter values and metrics
+    Read,
+    /// Increment counters through the JSON API
+    Increment,
+    /// Set counters to arbitrary values
+    Set,
+    /// Everything above
+    Admin,
+}
+
+/// Request to create an API key
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ApiKeyCreateRequest {
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    #[serde(default)]
+    pub prefixes: Vec<String>,
+}
+
+/// API key details, without the key itself
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ApiKeyResponse {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub prefixes: Vec<String>,
+    pub namespace: String,
+    /// Whether the key may read `/metrics`, which covers every namespace
+    #[serde(default)]
+    pub global: bool,
+    pub created_at: DateTime<Utc>,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+/// Response to API key creation. The key is only ever returned here.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ApiKeyCreatedResponse {
+    pub key: String,
+    #[serde(flatten)]
+    pub details: ApiKeyResponse,
+}
+
+/// List all API keys response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ApiKeyListResponse {
+    pub keys: Vec<ApiKeyResponse>,
+    pub total: usize,
+}
+
+/// Role of an admin user. Each role includes the permissions of the ones before it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// List badges and API keys
+    Viewer,
+    /// Create badges and API keys, and set counters
+    Editor,
+    /// Delete badges, revoke API keys and manage users
+    Owner,
+}
+
+impl Role {
+    /// Create badges and set counters.
+    pub fn can_edit(&self) -> bool {
+        *self >= Role::Editor
+    }
+
+    /// Delete badges and manage users.
+    pub fn can_delete(&self) -> bool {
+        *self == Role::Owner
+    }
+}
+
+/// Request to create an admin user
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UserCreateRequest {
+    pub username: String,
+    pub password: String,
+    pub role: Role,
+    /// Restricts the user to one namespace. Always set to their own for namespaced owners.
+    #[serde(default)]
+    pub namespace: Option<String>,
+}
+
+/// Request to change the role or password of an admin user
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UserUpdateRequest {
+    pub role: Option<Role>,
+    pub password: Option<String>,
+}
+
+/// Admin user details, without the password hash
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UserResponse {
+    pub username: String,
+    pub role: Role,
+    pub namespace: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// List all admin users response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UserListResponse {
+    pub users: Vec<UserResponse>,
+    pub total: usize,
+}
+
+/// Admin login request. Without a username, logs in as the `admin` user.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LoginRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    pub password: String,
+}
+
+/// Bearer token of an admin session
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TokenResponse {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+    pub username: String,
+    pub role: Role,
+    /// The only namespace the user may administer, `None` for all of them
+    #[serde(default)]
+    pub namespace: Option<String>,
+}
+
+/// The user behind the current session
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MeResponse {
+    pub username: String,
+    pub role: Role,
+    pub namespace: Option<String>,
+}
+
+/// A configured namespace with its usage
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NamespaceResponse {
+    pub name: String,
+    pub counters: usize,
+    /// Counters the namespace may hold. `0` means no limit.
+    pub max_counters: usize,
+}
+
+/// List the namespaces visible to the user
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NamespaceListResponse {
+    pub namespaces: Vec<NamespaceResponse>,
+    pub total: usize,
+}
+
+/// Kind of change recorded in the audit log
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "rocket", derive(rocket::FromFormField))]
+pub enum AuditAction {
+    /// A badge was created
+    Create,
+    /// A counter was set to a new value
+    Set,
+    /// A badge was moved to the trash
+    Delete,
+    /// A badge was restored from the trash
+    Restore,
+    /// A badge was permanently removed from the trash
+    Purge,
+    /// A badge was renamed, leaving an alias
+    Rename,
+    /// A badge was merged into another one, leaving an alias
+    Merge,
+    /// An alias was added for a counter
+    Alias,
+    /// An alias was removed
+    Unalias,
+    /// A counter was created or changed by an import
+    Import,
+    /// Tags were added to a badge; the target lists them
+    Tag,
+    /// Tags were removed from a badge; the target lists them
+    Untag,
+    /// A badge was moved to the namespace in the target
+    Move,
+    /// The description, owner, URL or tags of a badge were edited
+    Metadata,
+    /// The period of a counter was changed; the target is the new period and time zone
+    Period,
+}
+
+impl AuditAction {
+    pub const ALL: [AuditAction; 15] = [
+        AuditAction::Create, AuditAction::Set, AuditAction::Delete, AuditAction::Restore, AuditAction::Purge,
+        AuditAction::Rename, AuditAction::Merge, AuditAction::Alias, AuditAction::Unalias, AuditAction::Import,
+        AuditAction::Tag, AuditAction::Untag, AuditAction::Move, AuditAction::Metadata, AuditAction::Period,
+    ];
+}
+
+/// The `action` query parameter of the audit log.
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AuditAction::Create => "create",
+            AuditAction::Set => "set",
+            AuditAction::Delete => "delete",
+            AuditAction::Restore => "restore",
+            AuditAction::Purge => "purge",
+            AuditAction::Rename => "rename",
+            AuditAction::Merge => "merge",
+            AuditAction::Alias => "alias",
+            AuditAction::Unalias => "unalias",
+            AuditAction::Import => "import",
+            AuditAction::Tag => "tag",
+            AuditAction::Untag => "untag",
+            AuditAction::Move => "move",
+            AuditAction::Metadata => "metadata",
+            AuditAction::Period => "period",
+        })
+    }
+}
+
+/// One administrative change to a counter
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    /// `user:<username>` or `api_key:<id>`
+    pub actor: String,
+    pub action: AuditAction,
+    pub counter: String,
+    /// New name of a renamed or merged counter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub old_value: Option<u64>,
+    pub new_value: Option<u64>,
+    pub client_ip: Option<IpAddr>,
+}
+
+/// Query parameters of the audit log endpoint
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "rocket", derive(rocket::FromForm))]
+pub struct AuditQuery {
+    pub counter: Option<String>,
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    /// RFC 3339 timestamps or dates bounding the entries, `until` excluded
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+}
+
+/// One page of the audit log, newest entries first
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AuditListResponse {
+    pub entries: Vec<AuditEntry>,
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(description: &str, owner: &str, url: &str, tags: &[&str]) -> BadgeMetadata {
+        let text = |value: &str| Some(value.to_string());
+        BadgeMetadata {
+            description: text(description),
+            owner: text(owner),
+            url: text(url),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn metadata_is_trimmed_and_empty_fields_dropped() {
+        let normalized = metadata("  Home page ", " ", "https://example.com ", &[" docs ", "", "docs"]).normalized();
+        assert_eq!(normalized, BadgeMetadata {
+            description: Some("Home page".to_string()),
+            owner: None,
+            url: Some("https://example.com".to_string()),
+            tags: BTreeSet::from(["docs".to_string()]),
+        });
+    }
+
+    #[test]
+    fn metadata_is_validated() {
+        assert_eq!(metadata("Home", "Team", "http://example.com", &["docs", "v2_beta"]).validate(), Ok(()));
+        assert_eq!(BadgeMetadata::default().validate(), Ok(()));
+
+        let long = "é".repeat(BadgeMetadata::MAX_DESCRIPTION_LENGTH);
+        assert_eq!(metadata(&long, "Team", "https://example.com", &[]).validate(), Ok(()));
+        let errors = [
+            (metadata(&format!("{}a", long), "Team", "https://example.com", &[]), "description must be at most 500 characters"),
+            (metadata("Home", &"a".repeat(101), "https://example.com", &[]), "owner must be at most 100 characters"),
+            (metadata("Home", "Team", "javascript:alert(1)", &[]), "url must start with http:// or https://"),
+            (metadata("Home", "Team", "https://example.com", &["two words"]), "tag `two words` must be 1 to 32 ASCII letters, digits, '-' or '_'"),
+            (metadata("Home", "Team", "https://example.com", &[&"a".repeat(33)]), &format!("tag `{}` must be 1 to 32 ASCII letters, digits, '-' or '_'", "a".repeat(33))),
+        ];
+        for (metadata, error) in errors {
+            assert_eq!(metadata.validate(), Err(error.to_string()));
+        }
+    }
+
+    #[test]
+    fn updates_replace_given_fields_and_add_tags() {
+        let mut current = metadata("Home", "Team", "https://example.com", &["docs"]);
+        current.update(BadgeMetadata {
+            owner: Some("Web".to_string()),
+            tags: BTreeSet::from(["public".to_string()]),
+            ..BadgeMetadata::default()
+        });
+        assert_eq!(current, metadata("Home", "Web", "https://example.com", &["docs", "public"]));
+    }
+}
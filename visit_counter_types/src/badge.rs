@@ -0,0 +1,260 @@
+// visit_counter_types/src/badge.rs
+use std::fmt;
+use std::str::FromStr;
+
+/// Query parameters of the SVG endpoint. With the `rocket` feature, derives `FromForm` so Rocket can parse query parameters into this struct.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "rocket", derive(rocket::FromForm))]
+pub struct SvgOptions {
+    pub label: Option<String>,
+    pub style: Option<String>,
+    // SVG Dimensions
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub label_width: Option<u32>,
+    pub counter_width: Option<u32>,
+    pub radius: Option<u32>,
+    // Gradient Settings
+    pub grad_stop1_color: Option<String>,
+    pub grad_stop1_opacity: Option<f32>,
+    pub grad_stop2_opacity: Option<f32>,
+    // Text Settings
+    pub font_family: Option<String>,
+    pub font_size: Option<u32>,
+    pub label_offset_x: Option<u32>,
+    pub label_offset_y: Option<u32>,
+    pub counter_offset_x: Option<u32>,
+    pub counter_offset_y: Option<u32>,
+    pub shadow_fill: Option<String>,
+    pub shadow_opacity: Option<f32>,
+    // Color Settings
+    pub background_label: Option<String>,
+    pub background_counter: Option<String>,
+    pub label_color: Option<String>,
+    pub counter_color: Option<String>,
+    // Extended customization options
+    pub font_weight: Option<String>,
+    pub text_color: Option<String>,
+    pub background_color: Option<String>,
+    pub border_width: Option<u32>,
+    pub border_color: Option<String>,
+    pub border_radius: Option<u32>,
+    pub logo_url: Option<String>,
+    pub logo_width: Option<u32>,
+    pub element_positions: Option<String>, // "label,logo,counter" or similar format
+    /// Visits shown by the badge of a periodic counter: `current`, `previous` or `all` (the default)
+    pub period: Option<PeriodView>,
+}
+
+impl SvgOptions {
+    /// The options that are set as query parameters, named like the fields. Values are not URL-encoded.
+    pub fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = Vec::new();
+        let mut push = |name: &'static str, value: Option<String>| {
+            if let Some(value) = value {
+                pairs.push((name, value));
+            }
+        };
+        let number = |value: Option<u32>| value.map(|value| value.to_string());
+        let opacity = |value: Option<f32>| value.map(|value| value.to_string());
+
+        push("label", self.label.clone());
+        push("style", self.style.clone());
+        push("width", number(self.width));
+        push("height", number(self.height));
+        push("label_width", number(self.label_width));
+        push("counter_width", number(self.counter_width));
+        push("radius", number(self.radius));
+        push("grad_stop1_color", self.grad_stop1_color.clone());
+        push("grad_stop1_opacity", opacity(self.grad_stop1_opacity));
+        push("grad_stop2_opacity", opacity(self.grad_stop2_opacity));
+        push("font_family", self.font_family.clone());
+        push("font_size", number(self.font_size));
+        push("label_offset_x", number(self.label_offset_x));
+        push("label_offset_y", number(self.label_offset_y));
+        push("counter_offset_x", number(self.counter_offset_x));
+        push("counter_offset_y", number(self.counter_offset_y));
+        push("shadow_fill", self.shadow_fill.clone());
+        push("shadow_opacity", opacity(self.shadow_opacity));
+        push("background_label", self.background_label.clone());
+        push("background_counter", self.background_counter.clone());
+        push("label_color", self.label_color.clone());
+        push("counter_color", self.counter_color.clone());
+        push("font_weight", self.font_weight.clone());
+        push("text_color", self.text_color.clone());
+        push("background_color", self.background_color.clone());
+        push("border_width", number(self.border_width));
+        push("border_color", self.border_color.clone());
+        push("border_radius", number(self.border_radius));
+        push("logo_url", self.logo_url.clone());
+        push("logo_width", number(self.logo_width));
+        push("element_positions", self.element_positions.clone());
+        push("period", self.period.map(|period| period.to_string()));
+        pairs
+    }
+}
+
+/// Which value of a counter a badge shows
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "rocket", derive(rocket::FromFormField))]
+pub enum PeriodView {
+    /// Visits of the active window of a periodic counter
+    Current,
+    /// Visits of the window before it
+    Previous,
+    /// Every visit ever counted
+    #[default]
+    All,
+}
+
+impl fmt::Display for PeriodView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PeriodView::Current => "current",
+            PeriodView::Previous => "previous",
+            PeriodView::All => "all",
+        })
+    }
+}
+
+impl FromStr for PeriodView {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        [PeriodView::Current, PeriodView::Previous, PeriodView::All].into_iter()
+            .find(|view| view.to_string() == value)
+            .ok_or_else(|| format!("unknown period view `{}`", value))
+    }
+}
+
+/// Badge edited in the generator of the frontend, with the defaults of the server
+#[derive(Debug, Clone, PartialEq)]
+pub struct BadgeConfig {
+    pub name: String,
+    /// Empty for the default namespace
+    pub namespace: String,
+    pub label: String,
+    pub style: String,
+    pub width: u32,
+    pub height: u32,
+    pub font_family: String,
+    pub font_size: u32,
+    pub font_weight: String,
+    pub text_color: String,
+    pub background_color: String,
+    pub label_color: String,
+    pub counter_color: String,
+    pub background_label: String,
+    pub background_counter: String,
+    pub border_width: u32,
+    pub border_color: String,
+    pub border_radius: u32,
+    pub logo_url: String,
+    pub logo_width: u32,
+    pub element_positions: String, // "label,logo,counter" format
+    /// Value shown by the badge of a periodic counter, every visit unless set
+    pub period: Option<PeriodView>,
+    pub secret: String, // Signing secret, empty for unsigned URLs
+}
+
+impl Default for BadgeConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            namespace: String::new(),
+            label: "Visits".to_string(),
+            style: String::new(),
+            width: 150,
+            height: 20,
+            font_family: "Comfortaa, Metrophobic, sans-serif".to_string(),
+            font_size: 11,
+            font_weight: "normal".to_string(),
+            text_color: "#ffffff".to_string(),
+            background_color: "#18181b".to_string(),
+            label_color: "#ffffff".to_string(),
+            counter_color: "#ffffff".to_string(),
+            background_label: "#18181b".to_string(),
+            background_counter: "#DC26B6".to_string(),
+            border_width: 0,
+            border_color: "#cccccc".to_string(),
+            border_radius: 3,
+            logo_url: String::new(),
+            logo_width: 30,
+            element_positions: "label,logo,counter".to_string(),
+            period: None,
+            secret: String::new(),
+        }
+    }
+}
+
+impl BadgeConfig {
+    /// Options of the badge URL: only the ones differing from the defaults, so URLs stay short.
+    /// The deprecated `text_color` and `background_color` are left out.
+    pub fn svg_options(&self) -> SvgOptions {
+        let defaults = BadgeConfig::default();
+        let text = |value: &String, default: &String| Some(value.clone()).filter(|value| !value.is_empty() && value != default);
+        let number = |value: u32, default: u32| Some(value).filter(|value| *value != default);
+
+        SvgOptions {
+            label: text(&self.label, &defaults.label),
+            style: text(&self.style, &defaults.style),
+            width: number(self.width, defaults.width),
+            height: number(self.height, defaults.height),
+            font_family: text(&self.font_family, &defaults.font_family),
+            font_size: number(self.font_size, defaults.font_size),
+            font_weight: text(&self.font_weight, &defaults.font_weight),
+            label_color: text(&self.label_color, &defaults.label_color),
+            counter_color: text(&self.counter_color, &defaults.counter_color),
+            background_label: text(&self.background_label, &defaults.background_label),
+            background_counter: text(&self.background_counter, &defaults.background_counter),
+            border_width: number(self.border_width, defaults.border_width),
+            border_color: text(&self.border_color, &defaults.border_color),
+            border_radius: number(self.border_radius, defaults.border_radius),
+            logo_url: text(&self.logo_url, &defaults.logo_url),
+            logo_width: number(self.logo_width, defaults.logo_width),
+            element_positions: text(&self.element_positions, &defaults.element_positions),
+            period: self.period,
+            ..SvgOptions::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_badges_have_no_parameters() {
+        assert_eq!(BadgeConfig::default().svg_options(), SvgOptions::default());
+        assert!(SvgOptions::default().query_pairs().is_empty());
+    }
+
+    #[test]
+    fn changed_options_become_parameters() {
+        let config = BadgeConfig {
+            name: "home".to_string(),
+            label: "Views".to_string(),
+            width: 200,
+            border_radius: 0,
+            logo_url: String::new(),
+            text_color: "#000000".to_string(),
+            period: Some(PeriodView::Previous),
+            secret: "secret".to_string(),
+            ..BadgeConfig::default()
+        };
+        assert_eq!(config.svg_options().query_pairs(), vec![
+            ("label", "Views".to_string()),
+            ("width", "200".to_string()),
+            ("border_radius", "0".to_string()),
+            ("period", "previous".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn period_views_parse_back() {
+        for view in [PeriodView::Current, PeriodView::Previous, PeriodView::All] {
+            assert_eq!(view.to_string().parse(), Ok(view));
+        }
+        assert!("".parse::<PeriodView>().is_err());
+    }
+}
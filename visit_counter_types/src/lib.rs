@@ -0,0 +1,10 @@
+// visit_counter_types/src/lib.rs
+//! Types shared by the backend and the frontend: the JSON bodies of the API, the parameters
+//! of badges, and the signatures of badge URLs. The backend enables the `rocket` feature to read
+//! them from query strings; the frontend builds them for wasm without it.
+mod api;
+mod badge;
+pub mod signing;
+
+pub use api::*;
+pub use badge::*;
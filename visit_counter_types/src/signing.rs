@@ -0,0 +1,65 @@
+// visit_counter_types/src/signing.rs
+//! Signatures of badge URLs, computed by the badge generator and checked by the server.
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Query parameter carrying the signature of a badge URL.
+pub const SIGNATURE_PARAM: &str = "sig";
+
+/// Hex characters of the HMAC kept in URLs (128 bits).
+pub const SIGNATURE_LEN: usize = 32;
+
+/// The signed message: the counter key (`<namespace>/<name>` outside the default namespace), then every
+/// query parameter except `sig`, percent-decoded and sorted, one `key=value` per line.
+/// The key is lowercased like the names of case-insensitive servers, so the name typed into the badge
+/// generator and the one the server stores sign the same; secrets are per counter, so keys differing
+/// only in case still cannot share signatures.
+pub fn canonical_message<'a>(key: &str, params: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let mut params: Vec<_> = params.into_iter().filter(|(name, _)| *name != SIGNATURE_PARAM).collect();
+    params.sort();
+
+    let mut message = key.to_ascii_lowercase();
+    for (name, value) in params {
+        message.push('\n');
+        message.push_str(name);
+        message.push('=');
+        message.push_str(value);
+    }
+    message
+}
+
+/// Hex HMAC-SHA256 of `message`, truncated to `SIGNATURE_LEN` characters.
+pub fn sign(secret: &str, message: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(message.as_bytes());
+    let mut signature: String = mac.finalize().into_bytes().iter().map(|byte| format!("{:02x}", byte)).collect();
+    signature.truncate(SIGNATURE_LEN);
+    signature
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_sort_the_parameters_and_leave_out_the_signature() {
+        let params = [("width", "200"), ("sig", "0123"), ("label", "Views and more")];
+        assert_eq!(canonical_message("docs/home", params), "docs/home\nlabel=Views and more\nwidth=200");
+        assert_eq!(canonical_message("home", []), "home");
+    }
+
+    #[test]
+    fn keys_are_signed_in_lowercase() {
+        let params = [("label", "My Repo")];
+        assert_eq!(canonical_message("Docs/MyRepo", params), "docs/myrepo\nlabel=My Repo");
+        assert_eq!(canonical_message("MyRepo", []), canonical_message("myrepo", []));
+    }
+
+    #[test]
+    fn signatures_are_truncated_hmacs() {
+        // HMAC-SHA256("key", "The quick brown fox jumps over the lazy dog")
+        let signature = sign("key", "The quick brown fox jumps over the lazy dog");
+        assert_eq!(signature, "f7bc83f430538424b13298e6aa6fb143");
+        assert_ne!(sign("other", "home"), sign("key", "home"));
+    }
+}